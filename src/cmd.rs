@@ -1,24 +1,26 @@
-use crate::parser::Sql;
-use pest::Parser;
+use anyhow::{bail, Context};
 #[allow(unused)]
 use rayon::prelude::*;
-use regex::Regex;
-use sql_parse::Statement::{self, InsertReplace};
-use sql_parse::{parse_statements, Expression, ParseOptions, SQLDialect};
-use std::fmt::write;
-use std::fs::File;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use clap::Parser as ClapParser;
 
-use crate::masker::Transform;
-use crate::parser::statements::Insert;
-use crate::parser::{MySqlParser, Rule};
+use crate::codegen::{generate, Language};
+use crate::compression::{open_input, Output, STDIO_PATH};
+use crate::docs::{self, DocsFormat};
+use crate::dump::{read_schema, DatabaseSchema};
+use crate::erd::{Erd, ErdFormat};
+use crate::export::{ExportFormat, ExportOptions, Exporter, Layout, DEFAULT_ROW_GROUP_SIZE};
+use crate::generate::{GenerateOptions, Generator};
+use crate::json_schema::{SchemaDocument, SchemaFormat};
+use crate::masker::{Transform, Unmask};
+use crate::profile::{render_table, ProfileFormat, ProfileOptions, Profiler};
+use crate::rewrite::Pipeline;
+use crate::rules::fakers;
 // use crate::parser::MyParser;
-use crate::rules::get_struct_by_name;
 use crate::ExtractResult;
 use crate::{settings::parse_masking_config, simple_parse, sqlparse::to_json, types::Database};
-use iterate_text::file::lines::IterateFileLines;
 
 #[allow(unused)]
 static DEFAULT_JSON_FILTER: &str = r#"to_entries | map({table: .key, columns: .value.columns | map(select(.name | test("pass"; "i")))}) | map(select(.columns | length > 0))"#;
@@ -31,14 +33,25 @@ Usage:
 --sql-file <sql_file>
 
 --query <query>
+
+--output <output>
 "))]
 pub struct Args {
-    #[arg(short, long)]
+    /// SQL dump to read, or `-` for stdin
+    #[arg(short, long, default_value = STDIO_PATH)]
     pub sql_file: String,
 
     #[arg(short, long)]
     pub query: Option<String>,
 
+    /// Write to this path (`-` for stdout), compressed by extension (.gz, .zst, .xz, .bz2)
+    #[arg(short, long, global = true)]
+    pub output: Option<String>,
+
+    /// Compression level used when `--output` is compressed
+    #[arg(long, global = true)]
+    pub compression_level: Option<i32>,
+
     #[command(subcommand)]
     pub cmd: Option<Commands>,
 }
@@ -47,6 +60,28 @@ pub struct Args {
 pub enum Commands {
     #[command(about = "Mask PII from a SQL file")]
     MaskPII(MaskPIIArgs),
+    #[command(about = "Export table data to one CSV or NDJSON file per table under --output")]
+    Export(ExportArgs),
+    #[command(about = "Generate model types from the tables in a SQL file")]
+    Codegen(CodegenArgs),
+    #[command(about = "Describe the tables in a SQL file as JSON Schema or OpenAPI documents")]
+    Schema(SchemaArgs),
+    #[command(about = "Draw an entity-relationship diagram from the foreign keys in a SQL file")]
+    Erd(ErdArgs),
+    #[command(
+        about = "Generate a Markdown or HTML data dictionary for each database in a SQL file"
+    )]
+    Docs(DocsArgs),
+    #[command(about = "Generate INSERT statements of synthetic rows for the tables in a SQL file")]
+    Generate(GenerateArgs),
+    #[command(about = "Report statistics about the values of every column in a SQL file")]
+    Profile(ProfileArgs),
+    #[command(about = "Restore the values masked by the fpe() rules of a masking config")]
+    Unmask(UnmaskArgs),
+    #[command(about = "Inspect the tokenization vault of a masking config")]
+    Vault(VaultArgs),
+    #[command(about = "Inspect the fakers available to masking rules")]
+    Rules(RulesArgs),
 }
 
 #[derive(ClapParser)]
@@ -55,72 +90,496 @@ pub struct MaskPIIArgs {
     masking_config: String,
 }
 
+#[derive(ClapParser)]
+pub struct ExportArgs {
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+    format: ExportFormat,
+
+    /// Field delimiter for CSV files
+    #[arg(short, long, default_value_t = ',')]
+    delimiter: char,
+
+    /// Text written for NULL values in CSV files
+    #[arg(long, default_value = "")]
+    null: String,
+
+    #[arg(long, value_enum, default_value_t = Layout::Nested)]
+    layout: Layout,
+
+    /// Rows per Parquet row group
+    #[arg(long, default_value_t = DEFAULT_ROW_GROUP_SIZE)]
+    row_group_size: usize,
+}
+
+#[derive(ClapParser)]
+pub struct CodegenArgs {
+    #[arg(short, long, value_enum)]
+    language: Language,
+}
+
+#[derive(ClapParser)]
+pub struct SchemaArgs {
+    #[arg(short, long, value_enum, default_value_t = SchemaFormat::Jsonschema)]
+    format: SchemaFormat,
+}
+
+#[derive(ClapParser)]
+pub struct ErdArgs {
+    #[arg(short, long, value_enum, default_value_t = ErdFormat::Mermaid)]
+    format: ErdFormat,
+
+    /// Only include these tables (repeatable), plus their neighbours up to --hops
+    #[arg(short, long = "table")]
+    tables: Vec<String>,
+
+    /// Number of foreign keys to follow from the selected tables
+    #[arg(long, default_value_t = 0)]
+    hops: usize,
+}
+
+#[derive(ClapParser)]
+pub struct DocsArgs {
+    #[arg(short, long, value_enum, default_value_t = DocsFormat::Markdown)]
+    format: DocsFormat,
+
+    /// Flag the columns this masking config would mask
+    #[arg(short, long)]
+    masking_config: Option<String>,
+}
+
+#[derive(ClapParser)]
+pub struct GenerateArgs {
+    /// Rows per table
+    #[arg(short, long, default_value_t = 10)]
+    rows: usize,
+
+    /// Only generate these tables (repeatable, `NAME=COUNT`), plus the tables they reference
+    #[arg(short, long = "table", value_parser = parse_table_rows)]
+    tables: Vec<(String, usize)>,
+
+    /// Use the fakers from the `rules` section of this masking config
+    #[arg(short, long)]
+    masking_config: Option<String>,
+
+    /// Rows per INSERT statement
+    #[arg(long, default_value_t = 100)]
+    batch_size: usize,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(ClapParser)]
+pub struct ProfileArgs {
+    #[arg(short, long, value_enum, default_value_t = ProfileFormat::Table)]
+    format: ProfileFormat,
+
+    /// Most frequent values reported per column
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+
+    /// Report every value as a SHA-256 digest
+    #[arg(long)]
+    hash_values: bool,
+}
+
+#[derive(ClapParser)]
+pub struct UnmaskArgs {
+    /// The masking config the dump was masked with
+    #[arg(short, long)]
+    masking_config: String,
+}
+
+#[derive(ClapParser)]
+pub struct VaultArgs {
+    #[arg(short, long)]
+    masking_config: String,
+
+    #[command(subcommand)]
+    cmd: VaultCommands,
+}
+
+#[derive(ClapParser)]
+pub enum VaultCommands {
+    #[command(about = "Print the original values a fake value stands for")]
+    Lookup(VaultLookupArgs),
+}
+
+#[derive(ClapParser)]
+pub struct VaultLookupArgs {
+    /// Only look in the mappings of this column
+    #[arg(short, long)]
+    column: Option<String>,
+
+    /// The fake value found in a masked dump
+    value: String,
+}
+
+#[derive(ClapParser)]
+pub struct RulesArgs {
+    #[command(subcommand)]
+    cmd: RulesCommands,
+}
+
+#[derive(ClapParser)]
+pub enum RulesCommands {
+    #[command(about = "Print every faker as `domain::name()` with a sample value")]
+    List,
+}
+
+fn parse_table_rows(value: &str) -> std::result::Result<(String, usize), String> {
+    let (table, rows) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=COUNT, not `{value}`"))?;
+    let rows = rows
+        .parse()
+        .map_err(|_| format!("invalid row count `{rows}`"))?;
+    Ok((table.to_string(), rows))
+}
+
 pub fn exec() -> ExtractResult<Vec<String>> {
     let args = Args::parse();
 
     match args.cmd {
-        Some(Commands::MaskPII(pii_args)) => {
-            run_mask_pii_action(args.sql_file, &pii_args);
+        Some(Commands::MaskPII(ref pii_args)) => {
+            run_mask_pii_action(&args, pii_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Export(ref export_args)) => {
+            let paths = run_export_action(&args, export_args)?;
+            Ok(paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect())
+        }
+        Some(Commands::Codegen(ref codegen_args)) => {
+            run_codegen_action(&args, codegen_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Schema(ref schema_args)) => {
+            let paths = run_schema_action(&args, schema_args)?;
+            Ok(paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect())
+        }
+        Some(Commands::Erd(ref erd_args)) => {
+            run_erd_action(&args, erd_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Docs(ref docs_args)) => {
+            let paths = run_docs_action(&args, docs_args)?;
+            Ok(paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect())
         }
-        _ => {
-            run_default_action(&args);
+        Some(Commands::Generate(ref generate_args)) => {
+            run_generate_action(&args, generate_args)?;
+            Ok(vec![])
         }
+        Some(Commands::Profile(ref profile_args)) => {
+            run_profile_action(&args, profile_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Unmask(ref unmask_args)) => {
+            run_unmask_action(&args, unmask_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Vault(ref vault_args)) => {
+            run_vault_action(&args, vault_args)?;
+            Ok(vec![])
+        }
+        Some(Commands::Rules(ref rules_args)) => {
+            run_rules_action(&args, rules_args)?;
+            Ok(vec![])
+        }
+        _ => run_default_action(&args),
     }
-    Ok(vec![])
+}
+
+/// Open the destination selected by `--output`, falling back to stdout.
+fn open_output(args: &Args) -> ExtractResult<Output> {
+    let path = args.output.as_deref().unwrap_or(STDIO_PATH);
+    Output::create(Path::new(path), args.compression_level)
 }
 
 /// Mask PII from a SQL file
-///  
-/// 1. Parse the SQL file and print the JSON representation of the SQL.
-/// 2. If the `--mask-pii` flag is provided, mask the PII in the SQL file.
 ///
-/// Returns a list of statements to be executed.
-fn run_mask_pii_action(sql_file: String, args: &MaskPIIArgs) -> ExtractResult<Vec<Statement>> {
-    let sqlfile_path = Path::new(&sql_file);
-    if !sqlfile_path.exists() {
-        eprintln!("File {} does not exist", sqlfile_path.display());
-        std::process::exit(1);
+/// Streams the (optionally compressed) SQL file statement by statement,
+/// masking the values of every `INSERT` and writing the result to the
+/// destination selected by `--output`.
+fn run_mask_pii_action(args: &Args, pii_args: &MaskPIIArgs) -> ExtractResult<()> {
+    let sqlfile_path = Path::new(&args.sql_file);
+    if sqlfile_path != Path::new(STDIO_PATH) && !sqlfile_path.exists() {
+        bail!("file {} does not exist", sqlfile_path.display());
     }
 
-    let masking_config = args.masking_config.clone();
-    let config = parse_masking_config(&masking_config).expect("unable to load masking config");
-    let dml_regex = Regex::new(r"^insert").unwrap();
-    let mut out_sql: Vec<String> = vec![];
-    let transform = Transform::new(&config);
+    let config =
+        parse_masking_config(&pii_args.masking_config).context("unable to load masking config")?;
+    let mut transform = Transform::new(&config);
+    if let Some(vault) = config.open_vault()? {
+        transform = transform.with_vault(vault);
+    }
 
-    let file_descriptor = File::open(sqlfile_path).unwrap();
-    let mut iter = IterateFileLines::from(file_descriptor);
+    let mut out = open_output(args)?;
+    Pipeline::new()
+        .rewriter(&mut transform)
+        .run(open_input(sqlfile_path)?, &mut out)?;
 
-    loop {
-        let line = iter.next();
-        if line.is_none() {
-            break;
+    transform.save_vault()?;
+    out.finish()
+}
+
+/// Export table data
+///
+/// Streams the SQL file and writes the rows of every `INSERT` into one file per
+/// table below the `--output` directory.
+///
+/// Returns the paths of the files that were written.
+fn run_export_action(args: &Args, export_args: &ExportArgs) -> ExtractResult<Vec<PathBuf>> {
+    let out_dir = match args.output.as_deref() {
+        Some(STDIO_PATH) | None => bail!("export requires an --output directory"),
+        Some(dir) => PathBuf::from(dir),
+    };
+    if !export_args.delimiter.is_ascii() {
+        bail!("the CSV delimiter must be a single ASCII character");
+    }
+
+    let mut exporter = Exporter::new(ExportOptions {
+        format: export_args.format,
+        delimiter: export_args.delimiter as u8,
+        null: export_args.null.clone(),
+        layout: export_args.layout,
+        out_dir,
+        row_group_size: export_args.row_group_size,
+    });
+    exporter.export(open_input(Path::new(&args.sql_file))?)?;
+    exporter.finish()
+}
+
+/// Generate code
+///
+/// Renders the `CREATE TABLE` statements of the SQL file as model types in the
+/// selected language, written to the destination selected by `--output`.
+fn run_codegen_action(args: &Args, codegen_args: &CodegenArgs) -> ExtractResult<()> {
+    let databases = read_schema(open_input(Path::new(&args.sql_file))?)?;
+    let mut out = open_output(args)?;
+    out.write_all(generate(&databases, codegen_args.language).as_bytes())?;
+    out.finish()
+}
+
+/// Describe tables as JSON Schema
+///
+/// Builds one JSON Schema or OpenAPI document per database of the SQL file.
+/// With an `--output` directory each document is written to
+/// `<db>.schema.json` or `<db>.openapi.json`, otherwise the documents are
+/// printed to stdout one after another.
+///
+/// Returns the paths of the files that were written.
+fn run_schema_action(args: &Args, schema_args: &SchemaArgs) -> ExtractResult<Vec<PathBuf>> {
+    let databases = read_schema(open_input(Path::new(&args.sql_file))?)?;
+
+    write_per_database(
+        args,
+        &databases,
+        schema_args.format.extension(),
+        |database| {
+            let mut document =
+                serde_json::to_vec_pretty(&SchemaDocument::new(database, schema_args.format))?;
+            document.push(b'\n');
+            Ok(document)
+        },
+    )
+}
+
+/// Write one document per database.
+///
+/// With an `--output` directory each document goes to `<db>.<extension>`,
+/// otherwise the documents are written to stdout one after another.
+fn write_per_database(
+    args: &Args,
+    databases: &[DatabaseSchema],
+    extension: &str,
+    render: impl Fn(&DatabaseSchema) -> ExtractResult<Vec<u8>>,
+) -> ExtractResult<Vec<PathBuf>> {
+    let out_dir = match args.output.as_deref() {
+        Some(STDIO_PATH) | None => {
+            let mut out = open_output(args)?;
+            for database in databases {
+                out.write_all(&render(database)?)?;
+            }
+            out.finish()?;
+            return Ok(vec![]);
         }
-        if dml_regex.is_match(line.as_ref().unwrap()) {
-            let mut insert_block = line.clone().unwrap();
-            let values = iter.next();
-            insert_block.push_str(&values.unwrap());
-            insert_block.retain(|c| c != '\n' && c != '\r');
-            let dml_stmt = Insert::from(
-                MySqlParser::parse(Rule::INSERT_STATEMENT, &insert_block)
-                    .expect("Invalid input")
-                    .next()
-                    .expect("Unable to parse input"),
-            );
+        Some(dir) => PathBuf::from(dir),
+    };
+
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("unable to create {}", out_dir.display()))?;
+    let mut paths = Vec::new();
+    for database in databases {
+        let name = database
+            .name
+            .as_deref()
+            .unwrap_or("default")
+            .replace(['/', '\\'], "_");
+        let path = out_dir.join(format!("{name}.{extension}"));
+        let mut out = Output::create(&path, args.compression_level)?;
+        out.write_all(&render(database)?)?;
+        out.finish()?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
 
-            let mut dml_stmts = vec![dml_stmt];
-            transform.mask_dml_stmts(dml_stmts.as_mut_slice());
-            out_sql.push(dml_stmts[0].as_sql());
-        } else {
-            out_sql.push(line.unwrap());
+/// Generate a data dictionary
+///
+/// Documents the tables, columns, keys, indexes and foreign keys of every
+/// database in the SQL file, flagging the columns the masking config would mask.
+///
+/// Returns the paths of the files that were written.
+fn run_docs_action(args: &Args, docs_args: &DocsArgs) -> ExtractResult<Vec<PathBuf>> {
+    let masking_config = match &docs_args.masking_config {
+        Some(path) => Some(
+            parse_masking_config(path)
+                .with_context(|| format!("unable to load masking config {path}"))?,
+        ),
+        None => None,
+    };
+    let databases = read_schema(open_input(Path::new(&args.sql_file))?)?;
+
+    write_per_database(args, &databases, docs_args.format.extension(), |database| {
+        Ok(docs::render(database, masking_config.as_ref(), docs_args.format).into_bytes())
+    })
+}
+
+/// Draw an ERD
+///
+/// Renders the tables of the SQL file, their key columns and the foreign keys
+/// between them as a DOT or Mermaid diagram.
+fn run_erd_action(args: &Args, erd_args: &ErdArgs) -> ExtractResult<()> {
+    let databases = read_schema(open_input(Path::new(&args.sql_file))?)?;
+    let mut erd = Erd::new(&databases);
+    if !erd_args.tables.is_empty() {
+        erd.retain_neighbourhood(&erd_args.tables, erd_args.hops);
+    }
+
+    let mut out = open_output(args)?;
+    out.write_all(erd.render(erd_args.format).as_bytes())?;
+    out.finish()
+}
+
+/// Generate test data
+///
+/// Writes `INSERT` statements of synthetic rows for the tables of the SQL file,
+/// parents before children, to the destination selected by `--output`.
+fn run_generate_action(args: &Args, generate_args: &GenerateArgs) -> ExtractResult<()> {
+    let masking_config = match &generate_args.masking_config {
+        Some(path) => Some(
+            parse_masking_config(path)
+                .with_context(|| format!("unable to load masking config {path}"))?,
+        ),
+        None => None,
+    };
+    let databases = read_schema(open_input(Path::new(&args.sql_file))?)?;
+
+    let mut generator = Generator::new(
+        GenerateOptions {
+            rows: generate_args.rows,
+            table_rows: generate_args.tables.iter().cloned().collect(),
+            batch_size: generate_args.batch_size,
+            seed: generate_args.seed,
+        },
+        masking_config.as_ref(),
+    );
+    let mut out = open_output(args)?;
+    generator.generate(&databases, &mut out)?;
+    out.finish()
+}
+
+/// Profile the data
+///
+/// Streams the `INSERT` statements of the SQL file and reports, per column, the
+/// null ratio, distinct count, range, lengths, most frequent values and the
+/// kinds of values found.
+fn run_profile_action(args: &Args, profile_args: &ProfileArgs) -> ExtractResult<()> {
+    let mut profiler = Profiler::new(ProfileOptions {
+        top_k: profile_args.top,
+        hash_values: profile_args.hash_values,
+    });
+    profiler.profile(open_input(Path::new(&args.sql_file))?)?;
+    let profiles = profiler.finish();
+
+    let mut out = open_output(args)?;
+    match profile_args.format {
+        ProfileFormat::Table => out.write_all(render_table(&profiles).as_bytes())?,
+        ProfileFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &profiles)?;
+            out.write_all(b"\n")?;
         }
     }
+    out.finish()
+}
 
-    let out_sql = out_sql.join("");
-    println!("{}", out_sql);
+/// Unmask a SQL file
+///
+/// Decrypts the values of every column whose rule is `fpe(value, ...)`, given
+/// the masking config and secret the dump was masked with.
+fn run_unmask_action(args: &Args, unmask_args: &UnmaskArgs) -> ExtractResult<()> {
+    let config = parse_masking_config(&unmask_args.masking_config)
+        .context("unable to load masking config")?;
+    if config.cipher.is_none() {
+        bail!("the masking config has no `fpe` section, so there is nothing to unmask");
+    }
 
-    Ok(vec![])
+    let mut out = open_output(args)?;
+    Pipeline::new()
+        .rewriter(Unmask::new(&config))
+        .run(open_input(Path::new(&args.sql_file))?, &mut out)?;
+    out.finish()
 }
+
+/// Query the vault
+///
+/// Writes one `column<TAB>original` line per original value that was masked
+/// as the given fake.
+fn run_vault_action(args: &Args, vault_args: &VaultArgs) -> ExtractResult<()> {
+    let config = parse_masking_config(&vault_args.masking_config)
+        .context("unable to load masking config")?;
+    let Some(vault) = config.open_vault()? else {
+        bail!("the masking config has no `vault` section");
+    };
+
+    match &vault_args.cmd {
+        VaultCommands::Lookup(lookup) => {
+            let found = vault.lookup(lookup.column.as_deref(), &lookup.value);
+            if found.is_empty() {
+                bail!("`{}` is not in the vault", lookup.value);
+            }
+            let mut out = open_output(args)?;
+            for (column, original) in found {
+                writeln!(out, "{column}\t{original}")?;
+            }
+            out.finish()
+        }
+    }
+}
+
+fn run_rules_action(args: &Args, rules_args: &RulesArgs) -> ExtractResult<()> {
+    match rules_args.cmd {
+        RulesCommands::List => {
+            let mut out = open_output(args)?;
+            for faker in fakers() {
+                let sample = faker.create().fake();
+                writeln!(out, "{}::{}()\t{sample}", faker.domain, faker.name)?;
+            }
+            out.finish()
+        }
+    }
+}
+
 ///
 ///
 /// Default action.
@@ -130,22 +589,23 @@ fn run_mask_pii_action(sql_file: String, args: &MaskPIIArgs) -> ExtractResult<Ve
 /// 3. If the `--mask-pii` flag is provided, mask the PII in the SQL file.
 fn run_default_action(args: &Args) -> ExtractResult<Vec<String>> {
     let sqlfile_path = Path::new(&args.sql_file);
-    if !sqlfile_path.exists() {
-        eprintln!("File {} does not exist", sqlfile_path.display());
-        std::process::exit(1);
+    if sqlfile_path != Path::new(STDIO_PATH) && !sqlfile_path.exists() {
+        bail!("file {} does not exist", sqlfile_path.display());
     }
     let mut vals: Vec<String> = Vec::new();
+    let mut out = open_output(args)?;
     if let Some(query) = args.query.as_ref() {
-        let res = simple_parse(sqlfile_path).expect("unable to load input file");
+        let res = simple_parse(sqlfile_path).context("unable to load input file")?;
         // let input = to_json(res.clone());
-        let result = find_pass_columns(&res, &query);
-        println!("{}", serde_json::to_string(&result).unwrap());
+        let result = find_pass_columns(&res, query);
+        writeln!(out, "{}", serde_json::to_string(&result).unwrap())?;
     } else {
-        let res = simple_parse(sqlfile_path).expect("unable to load input file");
+        let res = simple_parse(sqlfile_path).context("unable to load input file")?;
         let input = to_json(res.clone());
-        println!("{}", input.to_string());
+        writeln!(out, "{}", input)?;
         vals.push(input.to_string());
     }
+    out.finish()?;
     Ok(vals)
 }
 
@@ -184,12 +644,15 @@ fn find_pass_columns(databases: &Vec<Database>, query_str: &str) -> Vec<Result>
 
 #[cfg(test)]
 mod tests {
-    use std::{io::Write, path::PathBuf};
+    use std::{
+        io::{Read, Write},
+        path::PathBuf,
+    };
     use tempfile::TempDir;
 
     use super::*;
 
-      fn create_test_masking_config(temp_dir: &TempDir) -> PathBuf {
+    fn create_test_masking_config(temp_dir: &TempDir) -> PathBuf {
         let temp_file_in_path = temp_dir.path().join("test.yaml");
         let test_config = r#"
 columns:
@@ -209,11 +672,184 @@ rules:
 
     fn create_temp_sql_with_insert(temp_dir: &TempDir) -> PathBuf {
         let temp_file_in_path = temp_dir.path().join("test.sql");
-        let sql_single_insert = r#"USE `users`;\nINSERT INTO users (id, name, email, password) VALUES (1, 'John Doe', 'john.doe@example.com', 'password');"#;
+        let sql_single_insert = "USE `users`;\ninsert into `users` (`id`, `name`, `email`, `password`) values \n(1, 'John Doe', 'john.doe@example.com', 'password');\n";
         let mut file = std::fs::File::create(temp_file_in_path.clone()).unwrap();
         file.write_all(sql_single_insert.as_bytes()).unwrap();
         file.flush().unwrap();
         file.sync_data().unwrap();
         temp_file_in_path
     }
+
+    #[test]
+    fn test_mask_pii_writes_compressed_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let masking_config = create_test_masking_config(&temp_dir);
+        let sql_file = create_temp_sql_with_insert(&temp_dir);
+        let output = temp_dir.path().join("masked.sql.zst");
+        let args = Args::parse_from([
+            "sqlex",
+            "--sql-file",
+            sql_file.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "mask-pii",
+            "--masking-config",
+            masking_config.to_str().unwrap(),
+        ]);
+        let Some(Commands::MaskPII(ref pii_args)) = args.cmd else {
+            panic!("expected the mask-pii subcommand");
+        };
+        run_mask_pii_action(&args, pii_args).unwrap();
+
+        let mut masked = String::new();
+        open_input(&output)
+            .unwrap()
+            .read_to_string(&mut masked)
+            .unwrap();
+        assert!(masked.starts_with("USE `users`;\nINSERT INTO `users`"));
+        assert!(!masked.contains("john.doe@example.com"));
+    }
+
+    /// Run mask-pii with the test config over `sql`, returning the output.
+    fn mask_pii(temp_dir: &TempDir, sql: &str) -> String {
+        try_mask_pii(temp_dir, sql).unwrap()
+    }
+
+    fn try_mask_pii(temp_dir: &TempDir, sql: &str) -> ExtractResult<String> {
+        let masking_config = create_test_masking_config(temp_dir);
+        let sql_file = temp_dir.path().join("dump.sql");
+        std::fs::write(&sql_file, sql).unwrap();
+        let output = temp_dir.path().join("masked.sql");
+        let args = Args::parse_from([
+            "sqlex",
+            "--sql-file",
+            sql_file.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "mask-pii",
+            "--masking-config",
+            masking_config.to_str().unwrap(),
+        ]);
+        let Some(Commands::MaskPII(ref pii_args)) = args.cmd else {
+            panic!("expected the mask-pii subcommand");
+        };
+        run_mask_pii_action(&args, pii_args)?;
+        Ok(std::fs::read_to_string(&output)?)
+    }
+
+    #[test]
+    fn test_mask_pii_masks_mysqldump_inserts() {
+        let temp_dir = TempDir::new().unwrap();
+        let masked = mask_pii(
+            &temp_dir,
+            "/*!40101 SET NAMES utf8mb4 */;\nINSERT INTO `users` (`id`, `email`, `account`) VALUES (1,'real@x.com','acct-1');\nINSERT INTO `users` (`id`, `email`, `account`) VALUES\n(2,\n'other@y.org',\n'acct-2');\n",
+        );
+
+        assert!(
+            masked.starts_with("/*!40101 SET NAMES utf8mb4 */;\n"),
+            "{masked}"
+        );
+        assert_eq!(masked.matches("INSERT INTO `users`").count(), 2, "{masked}");
+        for original in ["real@x.com", "other@y.org", "acct-1", "acct-2"] {
+            assert!(!masked.contains(original), "{masked}");
+        }
+    }
+
+    #[test]
+    fn test_mask_pii_masks_a_mysqldump() {
+        let temp_dir = TempDir::new().unwrap();
+        let dump = std::fs::read_to_string("./tests/mysqldump.sql").unwrap();
+        let masked = mask_pii(&temp_dir, &dump);
+
+        assert!(!masked.contains("@real.com"), "{masked}");
+        assert!(masked.contains("  `created_at` timestamp NULL DEFAULT CURRENT_TIMESTAMP,\n"));
+        assert!(masked.contains("END */;;\nDELIMITER ;\n"), "{masked}");
+    }
+
+    #[test]
+    fn test_mask_pii_fails_on_inserts_of_unknown_tables() {
+        let temp_dir = TempDir::new().unwrap();
+        let err = try_mask_pii(
+            &temp_dir,
+            "CREATE TABLE `orders` (`id` int NOT NULL, `paid` bool, `contact` varchar(255));\nINSERT INTO `orders` VALUES (1,1,'leak@real.com');\n",
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("`orders`"), "{err}");
+        assert!(err.to_string().contains("no column list"), "{err}");
+    }
+
+    #[test]
+    fn test_mask_pii_keeps_triggers() {
+        let temp_dir = TempDir::new().unwrap();
+        let trigger = "DELIMITER ;;\n/*!50003 CREATE*/ /*!50003 TRIGGER `t_bi` BEFORE INSERT ON `t` FOR EACH ROW BEGIN\n  SET NEW.a = 1;\n  SET NEW.b = 2;\nEND */;;\nDELIMITER ;\n";
+        let masked = mask_pii(&temp_dir, trigger);
+
+        assert_eq!(masked, trigger);
+    }
+
+    #[test]
+    fn test_mask_pii_masks_inserts_without_column_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let masked = mask_pii(
+            &temp_dir,
+            "CREATE TABLE `u` (\n  `id` int NOT NULL,\n  `email` varchar(255) DEFAULT NULL,\n  PRIMARY KEY (`id`)\n) ENGINE=InnoDB;\ninsert into `u` values\n(1,'real@x.com'),(2,'other@y.org');\n",
+        );
+
+        let insert = masked
+            .lines()
+            .find(|line| line.starts_with("INSERT"))
+            .unwrap();
+        assert!(
+            insert.starts_with("INSERT INTO `u` (`id`, `email`) VALUES (1, '"),
+            "{masked}"
+        );
+        assert!(
+            !masked.contains("real@x.com") && !masked.contains("other@y.org"),
+            "{masked}"
+        );
+    }
+
+    #[test]
+    fn test_lists_fakers() {
+        let temp_dir = TempDir::new().unwrap();
+        let output = temp_dir.path().join("fakers.txt");
+        let args = Args::parse_from([
+            "sqlex",
+            "--output",
+            output.to_str().unwrap(),
+            "rules",
+            "list",
+        ]);
+        let Some(Commands::Rules(ref rules_args)) = args.cmd else {
+            panic!("expected the rules subcommand");
+        };
+        run_rules_action(&args, rules_args).unwrap();
+
+        let listed = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(listed.lines().count(), fakers().len());
+        assert!(listed
+            .lines()
+            .any(|line| line.starts_with("payment::credit_card()\t")));
+        for line in listed.lines() {
+            let (call, _sample) = line.split_once('\t').unwrap();
+            assert!(crate::masker::Expression::parse(call).is_ok(), "{call}");
+        }
+    }
+
+    #[test]
+    fn test_output_accepted_after_subcommand() {
+        let args = Args::parse_from([
+            "sqlex",
+            "--sql-file",
+            "-",
+            "mask-pii",
+            "--masking-config",
+            "./tests/more.yaml",
+            "--output",
+            "-",
+        ]);
+        assert_eq!(args.sql_file, STDIO_PATH);
+        assert_eq!(args.output.as_deref(), Some(STDIO_PATH));
+    }
 }
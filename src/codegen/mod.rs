@@ -0,0 +1,339 @@
+use clap::ValueEnum;
+
+use crate::dump::DatabaseSchema;
+use crate::parser::parse_utils::unescape_str;
+use crate::parser::statements::CreateTable;
+use crate::parser::types::{Column, DataType, TableOption};
+
+mod python;
+mod rust;
+mod typescript;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    /// serde-annotated Rust structs
+    Rust,
+    /// TypeScript interfaces
+    Typescript,
+    /// zod schemas with inferred TypeScript types
+    Zod,
+    /// Python dataclasses
+    Python,
+}
+
+/// Render the tables of every database in `databases` as source code in `language`.
+pub fn generate(databases: &[DatabaseSchema], language: Language) -> String {
+    let models = databases
+        .iter()
+        .map(|database| {
+            (
+                database.name.as_deref(),
+                database.tables.iter().map(Model::from).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    match language {
+        Language::Rust => rust::render(&models),
+        Language::Typescript => typescript::render(&models, false),
+        Language::Zod => typescript::render(&models, true),
+        Language::Python => python::render(&models),
+    }
+}
+
+/// The tables of one database, as handed to each renderer.
+type DatabaseModels<'a> = (Option<&'a str>, Vec<Model>);
+
+/// A table, reduced to what the renderers need.
+struct Model {
+    /// The type name derived from the table name.
+    name: String,
+    doc: Option<String>,
+    fields: Vec<Field>,
+}
+
+struct Field {
+    column: String,
+    kind: FieldKind,
+    nullable: bool,
+    doc: Option<String>,
+}
+
+/// A language-neutral view of a column's [`DataType`].
+enum FieldKind {
+    Bool,
+    Int {
+        bits: u8,
+        signed: bool,
+    },
+    Decimal,
+    Float,
+    Double,
+    Date,
+    /// `DATETIME` or `TIMESTAMP`, both dumped as `YYYY-MM-DD HH:MM:SS`
+    /// without a time zone.
+    DateTime,
+    Time,
+    Year,
+    String {
+        max_length: Option<u32>,
+    },
+    /// A named enum type holding the column's allowed values.
+    Enum {
+        name: String,
+        values: Vec<String>,
+    },
+    Set,
+    Json,
+    Bytes,
+}
+
+impl From<&CreateTable> for Model {
+    fn from(table: &CreateTable) -> Self {
+        let name = pascal_case(&table.name);
+        let fields = table
+            .columns
+            .iter()
+            .map(|column| Field::new(&name, column))
+            .collect();
+        let doc = table.options.iter().find_map(|option| match option {
+            TableOption::Comment { value } if !value.is_empty() => Some(unescape_str(value)),
+            _ => None,
+        });
+
+        Self { name, doc, fields }
+    }
+}
+
+impl Model {
+    fn enums(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.fields.iter().filter_map(|field| match &field.kind {
+            FieldKind::Enum { name, values } => Some((name.as_str(), values.as_slice())),
+            _ => None,
+        })
+    }
+}
+
+impl Field {
+    fn new(model: &str, column: &Column) -> Self {
+        let kind = match &column.data_type {
+            DataType::TinyInt { m: Some(1), .. } | DataType::Bit { m: None | Some(1) } => {
+                FieldKind::Bool
+            }
+            DataType::TinyInt { unsigned, .. } => FieldKind::Int {
+                bits: 8,
+                signed: !unsigned,
+            },
+            DataType::SmallInt { unsigned, .. } => FieldKind::Int {
+                bits: 16,
+                signed: !unsigned,
+            },
+            DataType::MediumInt { unsigned, .. } | DataType::Int { unsigned, .. } => {
+                FieldKind::Int {
+                    bits: 32,
+                    signed: !unsigned,
+                }
+            }
+            DataType::BigInt { unsigned, .. } => FieldKind::Int {
+                bits: 64,
+                signed: !unsigned,
+            },
+            DataType::Bit { .. } => FieldKind::Int {
+                bits: 64,
+                signed: false,
+            },
+            DataType::Decimal { .. } => FieldKind::Decimal,
+            DataType::Float { .. } => FieldKind::Float,
+            DataType::Double { .. } => FieldKind::Double,
+            DataType::Date => FieldKind::Date,
+            DataType::DateTime { .. } | DataType::Timestamp { .. } => FieldKind::DateTime,
+            DataType::Time { .. } => FieldKind::Time,
+            DataType::Year { .. } => FieldKind::Year,
+            DataType::Char { m, .. } | DataType::Varchar { m, .. } => {
+                FieldKind::String { max_length: *m }
+            }
+            DataType::Text { .. }
+            | DataType::TinyText { .. }
+            | DataType::MediumText { .. }
+            | DataType::LongText { .. } => FieldKind::String { max_length: None },
+            DataType::Enum { values, .. } => FieldKind::Enum {
+                name: format!("{model}{}", pascal_case(&column.name)),
+                values: values.iter().map(|value| unescape_str(value)).collect(),
+            },
+            DataType::Set { .. } => FieldKind::Set,
+            DataType::Json => FieldKind::Json,
+            DataType::Binary { .. }
+            | DataType::Varbinary { .. }
+            | DataType::Blob { .. }
+            | DataType::TinyBlob
+            | DataType::MediumBlob
+            | DataType::LongBlob => FieldKind::Bytes,
+        };
+
+        Self {
+            column: column.name.clone(),
+            kind,
+            nullable: column.nullable,
+            doc: column
+                .comment
+                .as_deref()
+                .filter(|comment| !comment.is_empty())
+                .map(unescape_str),
+        }
+    }
+}
+
+/// Split an identifier into lowercase words on punctuation and camelCase boundaries.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_lowercase() || c.is_numeric();
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    let name = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+    prefix_leading_digit(name, "T")
+}
+
+/// Type-level names for the values of an enum, made unique.
+fn variant_names(values: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(values.len());
+    for value in values {
+        let base = if words(value).is_empty() {
+            String::from("Empty")
+        } else {
+            pascal_case(value)
+        };
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while names.contains(&name) {
+            name = format!("{base}{suffix}");
+            suffix += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+fn snake_case(name: &str) -> String {
+    prefix_leading_digit(words(name).join("_"), "_")
+}
+
+fn prefix_leading_digit(name: String, prefix: &str) -> String {
+    match name.chars().next() {
+        None => prefix.to_string(),
+        Some(first) if first.is_numeric() => format!("{prefix}{name}"),
+        Some(_) => name,
+    }
+}
+
+/// Render `doc` as one comment line per line of text, each starting with `prefix`.
+fn doc_comment(doc: Option<&str>, indent: &str, prefix: &str) -> String {
+    doc.map(|doc| {
+        doc.lines()
+            .map(|line| format!("{indent}{prefix} {line}").trim_end().to_string() + "\n")
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Quote `value` as a double-quoted string literal, which reads the same in
+/// Rust, TypeScript and Python.
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => literal.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::read_schema;
+
+    pub(super) const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `user_accounts` (
+  `id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `email` varchar(255) NOT NULL COMMENT 'Login address',
+  `status` enum('active','on-hold') NOT NULL DEFAULT 'active',
+  `is_admin` tinyint(1) NOT NULL DEFAULT '0',
+  `balance` decimal(10,2) DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  `type` varchar(20) DEFAULT NULL,
+  `prefs` json DEFAULT NULL
+) ENGINE=InnoDB COMMENT='Registered users';
+"#;
+
+    pub(super) fn schema() -> Vec<DatabaseSchema> {
+        read_schema(DUMP.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_case_conversion() {
+        assert_eq!(pascal_case("user_accounts"), "UserAccounts");
+        assert_eq!(pascal_case("userAccounts"), "UserAccounts");
+        assert_eq!(pascal_case("2fa codes"), "T2faCodes");
+        assert_eq!(snake_case("createdAt"), "created_at");
+        assert_eq!(snake_case("Order-Total"), "order_total");
+        assert_eq!(
+            variant_names(&[String::from(""), String::from("a b"), String::from("a-b")]),
+            vec!["Empty", "AB", "AB2"]
+        );
+    }
+
+    #[test]
+    fn test_builds_models_from_schema() {
+        let model = Model::from(&schema()[0].tables[0]);
+
+        assert_eq!(model.name, "UserAccounts");
+        assert_eq!(model.doc.as_deref(), Some("Registered users"));
+        assert_eq!(
+            model.enums().collect::<Vec<_>>(),
+            vec![(
+                "UserAccountsStatus",
+                &[String::from("active"), String::from("on-hold")][..]
+            )]
+        );
+        assert!(matches!(model.fields[3].kind, FieldKind::Bool));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+use super::{
+    doc_comment, snake_case, string_literal, variant_names, DatabaseModels, Field, FieldKind,
+};
+
+const HEADER: &str = "# Generated by sqlex. Do not edit.
+
+from __future__ import annotations
+
+import datetime
+from dataclasses import dataclass
+from decimal import Decimal
+from enum import Enum
+from typing import Any, Optional
+";
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+pub(super) fn render(databases: &[DatabaseModels]) -> String {
+    let mut out = String::from(HEADER);
+    let nested = databases.len() > 1;
+
+    for (database, models) in databases {
+        if nested {
+            let _ = write!(out, "\n\n# Database: {}\n", database.unwrap_or("default"));
+        }
+        for model in models {
+            for (name, values) in model.enums() {
+                let _ = write!(out, "\n\nclass {name}(str, Enum):\n");
+                for (value, variant) in values.iter().zip(variant_names(values)) {
+                    let _ = writeln!(
+                        out,
+                        "    {} = {}",
+                        snake_case(&variant).to_uppercase(),
+                        string_literal(value)
+                    );
+                }
+            }
+
+            let _ = write!(out, "\n\n@dataclass\nclass {}:\n", model.name);
+            if let Some(doc) = &model.doc {
+                let _ = write!(out, "    \"\"\"{}\"\"\"\n\n", escape_docstring(doc));
+            }
+            if model.fields.is_empty() {
+                out.push_str("    pass\n");
+            }
+            for field in &model.fields {
+                out.push_str(&doc_comment(field.doc.as_deref(), "    ", "#:"));
+                let _ = writeln!(
+                    out,
+                    "    {}: {}",
+                    field_name(&field.column),
+                    field_type(field)
+                );
+            }
+        }
+    }
+
+    out
+}
+
+fn field_name(column: &str) -> String {
+    let name = snake_case(column);
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+fn field_type(field: &Field) -> String {
+    let base = match &field.kind {
+        FieldKind::Bool => "bool",
+        FieldKind::Int { .. } | FieldKind::Year => "int",
+        FieldKind::Decimal => "Decimal",
+        FieldKind::Float | FieldKind::Double => "float",
+        FieldKind::Date => "datetime.date",
+        FieldKind::DateTime => "datetime.datetime",
+        // MySQL TIME is a duration that may exceed a day.
+        FieldKind::Time => "datetime.timedelta",
+        FieldKind::String { .. } => "str",
+        FieldKind::Enum { name, .. } => name,
+        FieldKind::Set => "list[str]",
+        FieldKind::Json => "Any",
+        FieldKind::Bytes => "bytes",
+    };
+
+    if field.nullable {
+        format!("Optional[{base}]")
+    } else {
+        base.to_string()
+    }
+}
+
+fn escape_docstring(doc: &str) -> String {
+    doc.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::tests::schema;
+    use crate::codegen::{generate, Language};
+
+    #[test]
+    fn test_renders_dataclasses() {
+        let code = generate(&schema(), Language::Python);
+
+        assert!(code.contains(
+            "class UserAccountsStatus(str, Enum):\n    ACTIVE = \"active\"\n    ON_HOLD = \"on-hold\"\n"
+        ));
+        assert!(code.contains(
+            "@dataclass\nclass UserAccounts:\n    \"\"\"Registered users\"\"\"\n\n    id: int\n    #: Login address\n    email: str\n    status: UserAccountsStatus\n    is_admin: bool\n    balance: Optional[Decimal]\n    created_at: datetime.datetime\n    type: Optional[str]\n    prefs: Optional[Any]\n"
+        ));
+    }
+}
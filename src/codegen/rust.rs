@@ -0,0 +1,162 @@
+use std::fmt::Write;
+
+use super::{
+    doc_comment, snake_case, string_literal, variant_names, DatabaseModels, Field, FieldKind, Model,
+};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
+/// Keywords that cannot be used as raw identifiers.
+const RESERVED: &[&str] = &["crate", "self", "super", "Self"];
+
+pub(super) fn render(databases: &[DatabaseModels]) -> String {
+    let mut out = String::from(
+        "// Generated by sqlex. Do not edit.\n\nuse serde::{Deserialize, Serialize};\n",
+    );
+    let nested = databases.len() > 1;
+
+    for (database, models) in databases {
+        let indent = if nested { "    " } else { "" };
+        if nested {
+            let module = snake_case(database.unwrap_or("default"));
+            let _ = write!(out, "\npub mod {module} {{\n    use super::*;\n");
+        }
+        for model in models {
+            render_model(&mut out, model, indent);
+        }
+        if nested {
+            out.push_str("}\n");
+        }
+    }
+
+    out
+}
+
+fn render_model(out: &mut String, model: &Model, indent: &str) {
+    let _ = write!(
+        out,
+        "\n{}{indent}#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n{indent}pub struct {} {{\n",
+        doc_comment(model.doc.as_deref(), indent, "///"),
+        model.name
+    );
+    for field in &model.fields {
+        out.push_str(&doc_comment(
+            field.doc.as_deref(),
+            &format!("{indent}    "),
+            "///",
+        ));
+        let (name, rename) = field_name(&field.column);
+        if rename {
+            let _ = writeln!(
+                out,
+                "{indent}    #[serde(rename = {})]",
+                string_literal(&field.column)
+            );
+        }
+        let _ = writeln!(out, "{indent}    pub {name}: {},", field_type(field));
+    }
+    let _ = writeln!(out, "{indent}}}");
+
+    for (name, values) in model.enums() {
+        let _ = write!(
+            out,
+            "\n{indent}#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]\n{indent}pub enum {name} {{\n"
+        );
+        for (value, variant) in values.iter().zip(variant_names(values)) {
+            let _ = writeln!(
+                out,
+                "{indent}    #[serde(rename = {})]\n{indent}    {variant},",
+                string_literal(value)
+            );
+        }
+        let _ = writeln!(out, "{indent}}}");
+    }
+}
+
+/// The field identifier for `column`, and whether serde needs to rename it.
+fn field_name(column: &str) -> (String, bool) {
+    let name = snake_case(column);
+    if RESERVED.contains(&name.as_str()) {
+        (format!("{name}_"), true)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        // serde strips the `r#` prefix, so no rename is needed when it matches.
+        let rename = name != column;
+        (format!("r#{name}"), rename)
+    } else {
+        let rename = name != column;
+        (name, rename)
+    }
+}
+
+fn field_type(field: &Field) -> String {
+    let base = match &field.kind {
+        FieldKind::Bool => "bool",
+        FieldKind::Int { bits, signed } => match (bits, signed) {
+            (8, true) => "i8",
+            (8, false) => "u8",
+            (16, true) => "i16",
+            (16, false) => "u16",
+            (32, true) => "i32",
+            (32, false) => "u32",
+            (_, true) => "i64",
+            (_, false) => "u64",
+        },
+        // Kept as text so no precision is lost.
+        FieldKind::Decimal => "String",
+        FieldKind::Float => "f32",
+        FieldKind::Double => "f64",
+        FieldKind::Date => "chrono::NaiveDate",
+        FieldKind::DateTime => "chrono::NaiveDateTime",
+        FieldKind::Time => "chrono::NaiveTime",
+        FieldKind::Year => "u16",
+        FieldKind::String { .. } => "String",
+        FieldKind::Enum { name, .. } => name,
+        FieldKind::Set => "Vec<String>",
+        FieldKind::Json => "serde_json::Value",
+        FieldKind::Bytes => "Vec<u8>",
+    };
+
+    if field.nullable {
+        format!("Option<{base}>")
+    } else {
+        base.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::tests::schema;
+    use crate::codegen::{generate, Language};
+    use crate::dump::read_schema;
+
+    #[test]
+    fn test_renders_rust() {
+        let code = generate(&schema(), Language::Rust);
+
+        assert!(code.contains(
+            "/// Registered users\n#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\npub struct UserAccounts {\n    pub id: u64,\n    /// Login address\n    pub email: String,\n    pub status: UserAccountsStatus,\n    pub is_admin: bool,\n    pub balance: Option<String>,\n    pub created_at: chrono::NaiveDateTime,\n    pub r#type: Option<String>,\n    pub prefs: Option<serde_json::Value>,\n}\n"
+        ));
+        assert!(code.contains(
+            "pub enum UserAccountsStatus {\n    #[serde(rename = \"active\")]\n    Active,\n    #[serde(rename = \"on-hold\")]\n    OnHold,\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_reads_timestamps_without_time_zone() {
+        let databases = read_schema(
+            "CREATE TABLE `events` (`at` timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP);\n"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert!(
+            generate(&databases, Language::Rust).contains("    pub at: chrono::NaiveDateTime,\n")
+        );
+    }
+}
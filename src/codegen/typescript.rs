@@ -0,0 +1,234 @@
+use std::fmt::Write;
+
+use super::{pascal_case, string_literal, variant_names, DatabaseModels, Field, FieldKind, Model};
+
+pub(super) fn render(databases: &[DatabaseModels], zod: bool) -> String {
+    let mut out = String::from("// Generated by sqlex. Do not edit.\n");
+    if zod {
+        out.push_str("\nimport { z } from \"zod\";\n");
+    }
+    let nested = databases.len() > 1;
+
+    for (database, models) in databases {
+        let indent = if nested { "  " } else { "" };
+        if nested {
+            let namespace = pascal_case(database.unwrap_or("default"));
+            let _ = write!(out, "\nexport namespace {namespace} {{\n");
+        }
+        for model in models {
+            if zod {
+                render_schema(&mut out, model, indent);
+            } else {
+                render_interface(&mut out, model, indent);
+            }
+        }
+        if nested {
+            out.push_str("}\n");
+        }
+    }
+
+    out
+}
+
+fn render_interface(out: &mut String, model: &Model, indent: &str) {
+    let _ = write!(
+        out,
+        "\n{}{indent}export interface {} {{\n",
+        jsdoc(model.doc.as_deref(), indent),
+        model.name
+    );
+    for field in &model.fields {
+        let _ = writeln!(
+            out,
+            "{}{indent}  {}: {};",
+            jsdoc(field.doc.as_deref(), &format!("{indent}  ")),
+            property_name(&field.column),
+            interface_type(field)
+        );
+    }
+    let _ = writeln!(out, "{indent}}}");
+
+    for (name, values) in model.enums() {
+        let _ = write!(out, "\n{indent}export enum {name} {{\n");
+        for (value, variant) in values.iter().zip(variant_names(values)) {
+            let _ = writeln!(out, "{indent}  {variant} = {},", string_literal(value));
+        }
+        let _ = writeln!(out, "{indent}}}");
+    }
+}
+
+fn interface_type(field: &Field) -> String {
+    let base = match &field.kind {
+        FieldKind::Bool => "boolean",
+        FieldKind::Int { .. } | FieldKind::Float | FieldKind::Double | FieldKind::Year => "number",
+        FieldKind::Decimal
+        | FieldKind::Date
+        | FieldKind::DateTime
+        | FieldKind::Time
+        | FieldKind::String { .. }
+        | FieldKind::Bytes => "string",
+        FieldKind::Enum { name, .. } => name,
+        FieldKind::Set => "string[]",
+        FieldKind::Json => return String::from("unknown"),
+    };
+
+    if field.nullable {
+        format!("{base} | null")
+    } else {
+        base.to_string()
+    }
+}
+
+fn render_schema(out: &mut String, model: &Model, indent: &str) {
+    // Enum schemas are constants, so they have to be declared before use.
+    for (name, values) in model.enums() {
+        let values = values
+            .iter()
+            .map(|value| string_literal(value))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = write!(
+            out,
+            "\n{indent}export const {name} = z.enum([{values}]);\n{indent}export type {name} = z.infer<typeof {name}>;\n"
+        );
+    }
+
+    let _ = write!(
+        out,
+        "\n{}{indent}export const {}Schema = z.object({{\n",
+        jsdoc(model.doc.as_deref(), indent),
+        model.name
+    );
+    for field in &model.fields {
+        let _ = writeln!(
+            out,
+            "{}{indent}  {}: {},",
+            jsdoc(field.doc.as_deref(), &format!("{indent}  ")),
+            property_name(&field.column),
+            zod_type(field)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{indent}}});\n{indent}export type {0} = z.infer<typeof {0}Schema>;",
+        model.name
+    );
+}
+
+fn zod_type(field: &Field) -> String {
+    let base = match &field.kind {
+        FieldKind::Bool => String::from("z.boolean()"),
+        FieldKind::Int { bits, signed } if *bits < 64 => {
+            let (min, max) = if *signed {
+                (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1)
+            } else {
+                (0, (1i64 << bits) - 1)
+            };
+            format!("z.number().int().min({min}).max({max})")
+        }
+        FieldKind::Int { signed: true, .. } => String::from("z.number().int()"),
+        FieldKind::Int { signed: false, .. } => String::from("z.number().int().nonnegative()"),
+        FieldKind::Year => String::from("z.number().int()"),
+        FieldKind::Float | FieldKind::Double => String::from("z.number()"),
+        FieldKind::String {
+            max_length: Some(max_length),
+        } => format!("z.string().max({max_length})"),
+        FieldKind::Decimal
+        | FieldKind::Date
+        | FieldKind::DateTime
+        | FieldKind::Time
+        | FieldKind::String { .. }
+        | FieldKind::Bytes => String::from("z.string()"),
+        FieldKind::Enum { name, .. } => name.clone(),
+        FieldKind::Set => String::from("z.array(z.string())"),
+        FieldKind::Json => return String::from("z.unknown()"),
+    };
+
+    if field.nullable {
+        format!("{base}.nullable()")
+    } else {
+        base
+    }
+}
+
+/// `name` as an object key, quoted when it is not a plain identifier.
+fn property_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if is_identifier {
+        name.to_string()
+    } else {
+        string_literal(name)
+    }
+}
+
+fn jsdoc(doc: Option<&str>, indent: &str) -> String {
+    match doc {
+        None => String::new(),
+        Some(doc) if !doc.contains('\n') => format!("{indent}/** {} */\n", escape_comment(doc)),
+        Some(doc) => {
+            let mut comment = format!("{indent}/**\n");
+            for line in escape_comment(doc).lines() {
+                let _ = writeln!(comment, "{indent} * {line}");
+            }
+            let _ = writeln!(comment, "{indent} */");
+            comment
+        }
+    }
+}
+
+fn escape_comment(doc: &str) -> String {
+    doc.replace("*/", "*\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::tests::schema;
+    use crate::codegen::{generate, Language};
+    use crate::dump::read_schema;
+
+    #[test]
+    fn test_renders_interfaces() {
+        let code = generate(&schema(), Language::Typescript);
+
+        assert!(code.contains(
+            "/** Registered users */\nexport interface UserAccounts {\n  id: number;\n  /** Login address */\n  email: string;\n  status: UserAccountsStatus;\n  is_admin: boolean;\n  balance: string | null;\n  created_at: string;\n  type: string | null;\n  prefs: unknown;\n}\n"
+        ));
+        assert!(code.contains(
+            "export enum UserAccountsStatus {\n  Active = \"active\",\n  OnHold = \"on-hold\",\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_renders_zod_schemas() {
+        let code = generate(&schema(), Language::Zod);
+
+        assert!(code.contains("import { z } from \"zod\";"));
+        assert!(code.contains(
+            "export const UserAccountsStatus = z.enum([\"active\", \"on-hold\"]);\nexport type UserAccountsStatus = z.infer<typeof UserAccountsStatus>;\n"
+        ));
+        assert!(code.contains("  email: z.string().max(255),\n"));
+        assert!(code.contains("  id: z.number().int().nonnegative(),\n"));
+        assert!(code.contains("  balance: z.string().nullable(),\n"));
+        assert!(
+            code.contains("});\nexport type UserAccounts = z.infer<typeof UserAccountsSchema>;\n")
+        );
+    }
+
+    #[test]
+    fn test_names_namespaces_with_identifiers() {
+        let databases = read_schema(
+            "USE `my-db`;\nCREATE TABLE `a` (`id` int NOT NULL);\nUSE `default`;\nCREATE TABLE `b` (`id` int NOT NULL);\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        let code = generate(&databases, Language::Typescript);
+
+        assert!(code.contains("\nexport namespace MyDb {\n"), "{code}");
+        assert!(code.contains("\nexport namespace Default {\n"), "{code}");
+    }
+}
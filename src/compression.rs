@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use bzip2::read::MultiBzDecoder;
+use bzip2::write::BzEncoder;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+
+use crate::ExtractResult;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// Passing this as a path reads from stdin or writes to stdout.
+pub const STDIO_PATH: &str = "-";
+
+/// Compression formats understood on input and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    /// Detect the compression format from the leading bytes of a stream.
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(GZIP_MAGIC) {
+            Self::Gzip
+        } else if magic.starts_with(ZSTD_MAGIC) {
+            Self::Zstd
+        } else if magic.starts_with(XZ_MAGIC) {
+            Self::Xz
+        } else if magic.starts_with(BZIP2_MAGIC) {
+            Self::Bzip2
+        } else {
+            Self::None
+        }
+    }
+
+    /// Pick the compression format for an output path from its extension.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") | Some("zstd") => Self::Zstd,
+            Some("xz") => Self::Xz,
+            Some("bz2") => Self::Bzip2,
+            _ => Self::None,
+        }
+    }
+
+    fn level_range(&self) -> (i32, i32, i32) {
+        // (min, max, default)
+        match self {
+            Self::None => (0, 0, 0),
+            Self::Gzip => (0, 9, 6),
+            Self::Zstd => (1, 22, zstd::DEFAULT_COMPRESSION_LEVEL),
+            Self::Xz => (0, 9, 6),
+            Self::Bzip2 => (1, 9, 6),
+        }
+    }
+
+    fn level(&self, level: Option<i32>) -> ExtractResult<i32> {
+        let (min, max, default) = self.level_range();
+        match level {
+            None => Ok(default),
+            Some(_) if *self == Self::None => {
+                bail!("a compression level requires a compressed output (.gz, .zst, .xz or .bz2)")
+            }
+            Some(level) if level < min || level > max => {
+                bail!("compression level for {self:?} must be between {min} and {max}, got {level}")
+            }
+            Some(level) => Ok(level),
+        }
+    }
+}
+
+/// Wrap `reader` in the decoder matching its magic bytes.
+pub fn decompress<R: BufRead + 'static>(mut reader: R) -> ExtractResult<Box<dyn BufRead>> {
+    let compression = Compression::detect(reader.fill_buf()?);
+
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(reader))),
+    })
+}
+
+/// Open a SQL dump, transparently decompressing gzip, zstd, xz and bzip2 input.
+///
+/// A path of `-` reads from stdin.
+pub fn open_input(path: &Path) -> ExtractResult<Box<dyn BufRead>> {
+    if path == Path::new(STDIO_PATH) {
+        return decompress(std::io::stdin().lock());
+    }
+
+    let file = File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
+
+    decompress(BufReader::new(file))
+}
+
+enum Encoder {
+    Plain(Box<dyn Write>),
+    Gzip(GzEncoder<Box<dyn Write>>),
+    Zstd(zstd::Encoder<'static, Box<dyn Write>>),
+    Xz(XzEncoder<Box<dyn Write>>),
+    Bzip2(BzEncoder<Box<dyn Write>>),
+}
+
+/// A streaming, optionally compressed output sink.
+///
+/// [`Output::finish`] must be called once everything has been written so the
+/// compressed stream is terminated and flushed.
+pub struct Output {
+    encoder: Encoder,
+}
+
+impl Output {
+    pub fn new(
+        sink: Box<dyn Write>,
+        compression: Compression,
+        level: Option<i32>,
+    ) -> ExtractResult<Self> {
+        let level = compression.level(level)?;
+        let encoder = match compression {
+            Compression::None => Encoder::Plain(sink),
+            Compression::Gzip => {
+                Encoder::Gzip(GzEncoder::new(sink, flate2::Compression::new(level as u32)))
+            }
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(sink, level)?),
+            Compression::Xz => Encoder::Xz(XzEncoder::new(sink, level as u32)),
+            Compression::Bzip2 => {
+                Encoder::Bzip2(BzEncoder::new(sink, bzip2::Compression::new(level as u32)))
+            }
+        };
+
+        Ok(Self { encoder })
+    }
+
+    /// Create `path`, compressing according to its extension.
+    ///
+    /// A path of `-` writes uncompressed to stdout.
+    pub fn create(path: &Path, level: Option<i32>) -> ExtractResult<Self> {
+        if path == Path::new(STDIO_PATH) {
+            return Self::new(
+                Box::new(BufWriter::new(std::io::stdout().lock())),
+                Compression::None,
+                level,
+            );
+        }
+
+        // Check the level first, so an invalid one leaves an existing file alone.
+        let compression = Compression::from_path(path);
+        compression.level(level)?;
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+
+        Self::new(Box::new(BufWriter::new(file)), compression, level)
+    }
+
+    pub fn finish(self) -> ExtractResult<()> {
+        let mut sink = match self.encoder {
+            Encoder::Plain(sink) => sink,
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Zstd(encoder) => encoder.finish()?,
+            Encoder::Xz(encoder) => encoder.finish()?,
+            Encoder::Bzip2(encoder) => encoder.finish()?,
+        };
+        sink.flush()?;
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.encoder {
+            Encoder::Plain(w) => w.write(buf),
+            Encoder::Gzip(w) => w.write(buf),
+            Encoder::Zstd(w) => w.write(buf),
+            Encoder::Xz(w) => w.write(buf),
+            Encoder::Bzip2(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.encoder {
+            Encoder::Plain(w) => w.flush(),
+            Encoder::Gzip(w) => w.flush(),
+            Encoder::Zstd(w) => w.flush(),
+            Encoder::Xz(w) => w.flush(),
+            Encoder::Bzip2(w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    const SQL: &str = "USE `users`;\nINSERT INTO `users` (`id`) VALUES (1);\n";
+
+    fn round_trip(file_name: &str, expected: Compression) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(file_name);
+        let mut output = Output::create(&path, None).unwrap();
+        output.write_all(SQL.as_bytes()).unwrap();
+        output.finish().unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert_eq!(Compression::detect(&raw), expected);
+
+        let mut contents = String::new();
+        open_input(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, SQL);
+    }
+
+    #[test]
+    fn test_round_trip_plain() {
+        round_trip("dump.sql", Compression::None);
+    }
+
+    #[test]
+    fn test_round_trip_gzip() {
+        round_trip("dump.sql.gz", Compression::Gzip);
+    }
+
+    #[test]
+    fn test_round_trip_zstd() {
+        round_trip("dump.sql.zst", Compression::Zstd);
+    }
+
+    #[test]
+    fn test_round_trip_xz() {
+        round_trip("dump.sql.xz", Compression::Xz);
+    }
+
+    #[test]
+    fn test_round_trip_bzip2() {
+        round_trip("dump.sql.bz2", Compression::Bzip2);
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_level() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(Output::create(&temp_dir.path().join("dump.sql.gz"), Some(12)).is_err());
+        assert!(Output::create(&temp_dir.path().join("dump.sql"), Some(3)).is_err());
+
+        let existing = temp_dir.path().join("masked.sql.gz");
+        std::fs::write(&existing, b"keep me").unwrap();
+        assert!(Output::create(&existing, Some(12)).is_err());
+        assert_eq!(std::fs::read(&existing).unwrap(), b"keep me");
+    }
+}
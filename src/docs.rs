@@ -0,0 +1,551 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::dump::DatabaseSchema;
+use crate::erd::html_escape;
+use crate::masker::root_of;
+use crate::parser::parse_utils::unescape_str;
+use crate::parser::statements::CreateTable;
+use crate::parser::types::TableOption;
+use crate::settings::MaskingConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DocsFormat {
+    Markdown,
+    /// A single self-contained HTML page
+    Html,
+}
+
+impl DocsFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// A lowercased `(table, column)` of one database.
+type ColumnKey = (String, String);
+
+const COLUMN_HEADERS: [&str; 7] = [
+    "Column", "Type", "Nullable", "Default", "Key", "Masked", "Comment",
+];
+const INDEX_HEADERS: [&str; 3] = ["Name", "Columns", "Unique"];
+
+const STYLE: &str = "body { font-family: -apple-system, Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 72em; padding: 0 1em; color: #222; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f3f3f3; }
+code { font-size: 0.9em; }
+section { border-top: 1px solid #ddd; margin-top: 2em; }
+.masked { color: #b00; font-weight: bold; }";
+
+/// A foreign key as seen from one end of it.
+struct Link {
+    table: String,
+    local_columns: Vec<String>,
+    remote_columns: Vec<String>,
+    name: Option<String>,
+}
+
+/// Everything the data dictionary shows about one table.
+struct TableDoc<'a> {
+    table: &'a CreateTable,
+    comment: Option<String>,
+    /// One row per column, in [`COLUMN_HEADERS`] order.
+    columns: Vec<[String; 7]>,
+    /// One row per index, in [`INDEX_HEADERS`] order.
+    indexes: Vec<[String; 3]>,
+    outgoing: Vec<Link>,
+    incoming: Vec<Link>,
+}
+
+impl<'a> TableDoc<'a> {
+    fn new(table: &'a CreateTable, database: &DatabaseSchema, masked: &HashSet<ColumnKey>) -> Self {
+        let comment = table.options.iter().find_map(|option| match option {
+            TableOption::Comment { value } if !value.is_empty() => Some(unescape_str(value)),
+            _ => None,
+        });
+
+        let columns = table
+            .columns
+            .iter()
+            .map(|column| {
+                let mut keys = Vec::new();
+                if table
+                    .primary_key
+                    .as_ref()
+                    .is_some_and(|key| key.column_names.contains(&column.name))
+                {
+                    keys.push("PK");
+                }
+                if table
+                    .foreign_keys
+                    .iter()
+                    .any(|key| key.local_column_names.contains(&column.name))
+                {
+                    keys.push("FK");
+                }
+                if table
+                    .indexes
+                    .iter()
+                    .any(|index| index.unique && index.columns.contains(&column.name))
+                {
+                    keys.push("UK");
+                }
+
+                let mut default = column
+                    .default
+                    .as_ref()
+                    .map(|default| default.to_string())
+                    .unwrap_or_default();
+                if column.auto_increment {
+                    default = String::from("AUTO_INCREMENT");
+                }
+                let masked =
+                    masked.contains(&(table.name.to_lowercase(), column.name.to_lowercase()));
+
+                [
+                    column.name.clone(),
+                    column.data_type.to_string(),
+                    String::from(if column.nullable { "yes" } else { "no" }),
+                    default,
+                    keys.join(", "),
+                    String::from(if masked { "yes" } else { "" }),
+                    column
+                        .comment
+                        .as_deref()
+                        .map(unescape_str)
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        let mut indexes = Vec::new();
+        if let Some(primary_key) = &table.primary_key {
+            indexes.push([
+                String::from("PRIMARY"),
+                primary_key.column_names.join(", "),
+                String::from("yes"),
+            ]);
+        }
+        for index in &table.indexes {
+            indexes.push([
+                index.name.clone(),
+                index.columns.join(", "),
+                String::from(if index.unique { "yes" } else { "no" }),
+            ]);
+        }
+
+        let outgoing = table
+            .foreign_keys
+            .iter()
+            .map(|key| Link {
+                table: key.foreign_table_name.clone(),
+                local_columns: key.local_column_names.clone(),
+                remote_columns: key.foreign_column_names.clone(),
+                name: key.name.clone(),
+            })
+            .collect();
+        let incoming = database
+            .tables
+            .iter()
+            .flat_map(|other| {
+                other
+                    .foreign_keys
+                    .iter()
+                    .filter(|key| key.foreign_table_name == table.name)
+                    .map(|key| Link {
+                        table: other.name.clone(),
+                        local_columns: key.foreign_column_names.clone(),
+                        remote_columns: key.local_column_names.clone(),
+                        name: key.name.clone(),
+                    })
+            })
+            .collect();
+
+        Self {
+            table,
+            comment,
+            columns,
+            indexes,
+            outgoing,
+            incoming,
+        }
+    }
+}
+
+/// Render the data dictionary of `database`, marking the columns `masking` would mask.
+pub fn render(
+    database: &DatabaseSchema,
+    masking: Option<&MaskingConfig>,
+    format: DocsFormat,
+) -> String {
+    let title = database.name.as_deref().unwrap_or("default");
+    let masked = masking
+        .map(|config| masked_columns(database, config))
+        .unwrap_or_default();
+    let tables = database
+        .tables
+        .iter()
+        .map(|table| TableDoc::new(table, database, &masked))
+        .collect::<Vec<_>>();
+
+    match format {
+        DocsFormat::Markdown => render_markdown(title, &tables, masking),
+        DocsFormat::Html => render_html(title, &tables, masking),
+    }
+}
+
+/// The columns of `database` that `config` masks, following foreign keys
+/// as the masker does.
+fn masked_columns(database: &DatabaseSchema, config: &MaskingConfig) -> HashSet<ColumnKey> {
+    let mut references = HashMap::new();
+    for table in &database.tables {
+        for foreign_key in &table.foreign_keys {
+            for (local, foreign) in foreign_key
+                .local_column_names
+                .iter()
+                .zip(&foreign_key.foreign_column_names)
+            {
+                references.insert(
+                    (table.name.to_lowercase(), local.to_lowercase()),
+                    (
+                        foreign_key.foreign_table_name.to_lowercase(),
+                        foreign.to_lowercase(),
+                    ),
+                );
+            }
+        }
+    }
+    let linked = references
+        .keys()
+        .chain(references.values())
+        .cloned()
+        .collect::<HashSet<_>>();
+    let masked_chains = linked
+        .iter()
+        .filter(|(_, column)| config.masks_linked_column(column))
+        .map(|key| root_of(&references, key.clone()))
+        .collect::<HashSet<_>>();
+
+    database
+        .tables
+        .iter()
+        .flat_map(|table| {
+            table
+                .columns
+                .iter()
+                .map(|column| (table.name.to_lowercase(), column.name.to_lowercase()))
+        })
+        .filter(|key| {
+            if linked.contains(key) {
+                masked_chains.contains(&root_of(&references, key.clone()))
+            } else {
+                config.masks_column(&key.1)
+            }
+        })
+        .collect()
+}
+
+fn masking_note(masking: Option<&MaskingConfig>) -> Option<String> {
+    let config = masking?;
+    let patterns = config
+        .patterns
+        .iter()
+        .map(|pattern| {
+            pattern
+                .name
+                .clone()
+                .unwrap_or_else(|| pattern.regex.clone())
+        })
+        .collect::<Vec<_>>();
+    (!patterns.is_empty()).then(|| {
+        format!(
+            "Values matching the masking patterns ({}) are masked in any column.",
+            patterns.join(", ")
+        )
+    })
+}
+
+/// The anchor GitHub generates for a heading.
+fn anchor(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn markdown_table<const N: usize>(out: &mut String, headers: [&str; N], rows: &[[String; N]]) {
+    let _ = writeln!(out, "| {} |", headers.join(" | "));
+    let _ = writeln!(out, "|{}", "---|".repeat(N));
+    for row in rows {
+        let cells = row
+            .iter()
+            .map(|cell| markdown_cell(cell))
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "| {} |", cells.join(" | "));
+    }
+    out.push('\n');
+}
+
+fn render_markdown(title: &str, tables: &[TableDoc], masking: Option<&MaskingConfig>) -> String {
+    let mut out = format!("# Data dictionary: {title}\n\n");
+    if let Some(note) = masking_note(masking) {
+        let _ = writeln!(out, "{note}\n");
+    }
+
+    out.push_str("## Tables\n\n");
+    for doc in tables {
+        let _ = write!(out, "- [{}](#{})", doc.table.name, anchor(&doc.table.name));
+        if let Some(comment) = &doc.comment {
+            let _ = write!(out, " — {}", comment.replace('\n', " "));
+        }
+        out.push('\n');
+    }
+
+    out.push('\n');
+
+    for doc in tables {
+        let _ = write!(out, "## {}\n\n", doc.table.name);
+        if let Some(comment) = &doc.comment {
+            let _ = writeln!(out, "{comment}\n");
+        }
+        markdown_table(&mut out, COLUMN_HEADERS, &doc.columns);
+
+        if !doc.indexes.is_empty() {
+            out.push_str("### Indexes\n\n");
+            markdown_table(&mut out, INDEX_HEADERS, &doc.indexes);
+        }
+        for (heading, links) in [
+            ("References", &doc.outgoing),
+            ("Referenced by", &doc.incoming),
+        ] {
+            if links.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "### {heading}\n");
+            for link in links {
+                let _ = write!(
+                    out,
+                    "- `{}` → [{}](#{}) (`{}`)",
+                    link.local_columns.join("`, `"),
+                    link.table,
+                    anchor(&link.table),
+                    link.remote_columns.join("`, `")
+                );
+                if let Some(name) = &link.name {
+                    let _ = write!(out, " via `{name}`");
+                }
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+    }
+
+    out.trim_end().to_string() + "\n"
+}
+
+fn html_table<const N: usize>(
+    out: &mut String,
+    headers: [&str; N],
+    rows: &[[String; N]],
+    masked_column: Option<usize>,
+) {
+    out.push_str("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(out, "<th>{header}</th>");
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for (i, cell) in row.iter().enumerate() {
+            let cell = html_escape(cell).replace('\n', "<br>");
+            if i == 0 {
+                let _ = write!(out, "<td><code>{cell}</code></td>");
+            } else if Some(i) == masked_column && !cell.is_empty() {
+                let _ = write!(out, "<td class=\"masked\">{cell}</td>");
+            } else {
+                let _ = write!(out, "<td>{cell}</td>");
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn render_html(title: &str, tables: &[TableDoc], masking: Option<&MaskingConfig>) -> String {
+    let title = html_escape(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Data dictionary: {title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>Data dictionary: {title}</h1>\n"
+    );
+    if let Some(note) = masking_note(masking) {
+        let _ = writeln!(out, "<p>{}</p>", html_escape(&note));
+    }
+
+    out.push_str("<nav>\n<ul>\n");
+    for doc in tables {
+        let _ = write!(
+            out,
+            "<li><a href=\"#{}\">{}</a>",
+            html_escape(&anchor(&doc.table.name)),
+            html_escape(&doc.table.name)
+        );
+        if let Some(comment) = &doc.comment {
+            let _ = write!(out, " — {}", html_escape(comment));
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n</nav>\n");
+
+    for doc in tables {
+        let _ = writeln!(
+            out,
+            "<section id=\"{}\">\n<h2>{}</h2>",
+            html_escape(&anchor(&doc.table.name)),
+            html_escape(&doc.table.name)
+        );
+        if let Some(comment) = &doc.comment {
+            let _ = writeln!(out, "<p>{}</p>", html_escape(comment));
+        }
+        html_table(&mut out, COLUMN_HEADERS, &doc.columns, Some(5));
+
+        if !doc.indexes.is_empty() {
+            out.push_str("<h3>Indexes</h3>\n");
+            html_table(&mut out, INDEX_HEADERS, &doc.indexes, None);
+        }
+        for (heading, links) in [
+            ("References", &doc.outgoing),
+            ("Referenced by", &doc.incoming),
+        ] {
+            if links.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "<h3>{heading}</h3>\n<ul>");
+            for link in links {
+                let _ = write!(
+                    out,
+                    "<li><code>{}</code> → <a href=\"#{}\">{}</a> (<code>{}</code>)",
+                    html_escape(&link.local_columns.join(", ")),
+                    html_escape(&anchor(&link.table)),
+                    html_escape(&link.table),
+                    html_escape(&link.remote_columns.join(", "))
+                );
+                if let Some(name) = &link.name {
+                    let _ = write!(out, " via <code>{}</code>", html_escape(name));
+                }
+                out.push_str("</li>\n");
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::read_schema;
+    use crate::settings::{parse_masking_config, InlineConfig};
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `users` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `password` varchar(64) NOT NULL COMMENT 'bcrypt | hashed',
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB COMMENT='Registered users';
+CREATE TABLE `orders` (
+  `id` int NOT NULL,
+  `user_id` int DEFAULT NULL,
+  `status` varchar(10) NOT NULL DEFAULT 'new',
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `orders_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB;
+"#;
+
+    fn docs(format: DocsFormat) -> String {
+        let databases = read_schema(DUMP.as_bytes()).unwrap();
+        let config = parse_masking_config("./tests/more.yaml").unwrap();
+        render(&databases[0], Some(&config), format)
+    }
+
+    #[test]
+    fn test_renders_markdown() {
+        let markdown = docs(DocsFormat::Markdown);
+
+        assert!(markdown.starts_with("# Data dictionary: shop\n"));
+        assert!(markdown.contains("- [users](#users) — Registered users\n"));
+        assert!(markdown.contains(
+            "| id | INT | no | AUTO_INCREMENT | PK |  |  |\n| password | VARCHAR (64) | no |  |  | yes | bcrypt \\| hashed |\n"
+        ));
+        assert!(markdown.contains("| status | VARCHAR (10) | no | 'new' |  |  |  |\n"));
+        assert!(markdown.contains(
+            "### Referenced by\n\n- `id` → [orders](#orders) (`user_id`) via `orders_user`\n"
+        ));
+        assert!(markdown.contains(
+            "### References\n\n- `user_id` → [users](#users) (`id`) via `orders_user`\n"
+        ));
+        assert!(markdown.contains("| user_id | user_id | no |\n"));
+    }
+
+    #[test]
+    fn test_renders_html() {
+        let html = docs(DocsFormat::Html);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<section id=\"orders\">"));
+        assert!(html.contains("<td class=\"masked\">yes</td>"));
+        assert!(html.contains("bcrypt | hashed"));
+    }
+
+    #[test]
+    fn test_marks_columns_masked_by_any_rule() {
+        let databases = read_schema(
+            "CREATE TABLE `customers` (`code` varchar(8) NOT NULL, `email` varchar(64), `preferences` json, `bio` text, `age` int, PRIMARY KEY (`code`));
+CREATE TABLE `orders` (`id` int NOT NULL, `customer_code` varchar(8), CONSTRAINT `fk` FOREIGN KEY (`customer_code`) REFERENCES `customers` (`code`));
+"
+            .as_bytes(),
+        )
+        .unwrap();
+        let mut config = parse_masking_config("./tests/more.yaml").unwrap();
+        config
+            .rules
+            .insert(String::from("code"), "redact()".try_into().unwrap());
+        config.inline = Some(InlineConfig {
+            columns: vec![String::from("bio")],
+            detectors: None,
+            replacement: Default::default(),
+        });
+
+        let masked = masked_columns(&databases[0], &config);
+        for column in [
+            ("customers", "code"),
+            ("customers", "email"),
+            ("customers", "preferences"),
+            ("customers", "bio"),
+            ("orders", "customer_code"),
+        ] {
+            assert!(
+                masked.contains(&(column.0.to_string(), column.1.to_string())),
+                "{column:?}"
+            );
+        }
+        assert_eq!(masked.len(), 5, "{masked:?}");
+    }
+}
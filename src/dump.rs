@@ -0,0 +1,513 @@
+use std::io::{BufRead, BufReader, Read};
+use std::ops::Range;
+
+use pest::iterators::Pair;
+use pest::Parser;
+
+use crate::parser::statements::{CreateTable, Statement, UseDatabase};
+use crate::parser::{MySqlParser, Rule};
+use crate::ExtractResult;
+
+/// Parse `sql` as `rule`, returning `None` when it does not match the
+/// grammar. The whole statement must match, as what follows a matching
+/// prefix would be lost when the statement is written back.
+pub fn parse_statement<'a, T: From<Pair<'a, Rule>>>(rule: Rule, sql: &'a str) -> Option<T> {
+    let pair = MySqlParser::parse(rule, sql).ok()?.next()?;
+    only_comments(&sql[pair.as_span().end()..]).then(|| T::from(pair))
+}
+
+/// Whether `sql` holds nothing but whitespace and comments, such as the
+/// `/*!50100 PARTITION BY ... */` mysqldump writes after a table.
+fn only_comments(mut sql: &str) -> bool {
+    loop {
+        sql = sql.trim_start();
+        if sql.is_empty() {
+            return true;
+        }
+        sql = if let Some(comment) = sql.strip_prefix("/*") {
+            match comment.find("*/") {
+                Some(end) => &comment[end + 2..],
+                None => return false,
+            }
+        } else if let Some(comment) = sql.strip_prefix("--") {
+            comment.find('\n').map_or("", |end| &comment[end..])
+        } else {
+            return false;
+        };
+    }
+}
+
+/// The tables created in one database of a dump, in dump order.
+#[derive(Debug, Clone)]
+pub struct DatabaseSchema {
+    /// `None` for tables created before any `USE` statement.
+    pub name: Option<String>,
+    pub tables: Vec<CreateTable>,
+}
+
+/// Collect the `CREATE TABLE` statements of a dump, grouped by database.
+pub fn read_schema<R: BufRead>(reader: R) -> ExtractResult<Vec<DatabaseSchema>> {
+    let mut databases: Vec<DatabaseSchema> = Vec::new();
+    let mut current_db = None;
+
+    for statement in StatementSplitter::new(reader) {
+        let sql = statement?;
+        let keyword = sql
+            .split_ascii_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match keyword.as_str() {
+            "USE" => {
+                if let Some(use_database) = parse_statement::<UseDatabase>(Rule::USE_DATABASE, &sql)
+                {
+                    current_db = Some(use_database.name);
+                }
+            }
+            "CREATE" => {
+                let Some(create_table) = parse_statement::<CreateTable>(Rule::CREATE_TABLE, &sql)
+                else {
+                    continue;
+                };
+                match databases.iter_mut().find(|db| db.name == current_db) {
+                    Some(database) => database.tables.push(create_table),
+                    None => databases.push(DatabaseSchema {
+                        name: current_db.clone(),
+                        tables: vec![create_table],
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(databases)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Code,
+    SingleQuote,
+    DoubleQuote,
+    Backtick,
+    LineComment,
+    BlockComment,
+}
+
+/// Splits a SQL dump into individual statements without loading it into memory.
+///
+/// Statements are separated on `;` outside of string literals, quoted
+/// identifiers and comments. Comments leading up to a statement are dropped,
+/// except MySQL's executable `/*!...*/` comments, and the terminating `;` is
+/// not included in the yielded text.
+///
+/// A `DELIMITER` line, as mysqldump writes around triggers and routines, is
+/// yielded as a statement of its own and replaces the `;` for the statements
+/// after it.
+pub struct StatementSplitter<R> {
+    reader: R,
+    line: String,
+    delimiter: String,
+    statement: String,
+    state: State,
+    done: bool,
+    /// Byte offset of the start of `line` in the input.
+    offset: u64,
+    /// Byte offset of the first character of `statement`.
+    start: u64,
+}
+
+impl<R: BufRead> StatementSplitter<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            delimiter: String::from(";"),
+            statement: String::new(),
+            state: State::Code,
+            done: false,
+            offset: 0,
+            start: 0,
+        }
+    }
+
+    /// The delimiter ending statements at this point of the input.
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    /// Feed the current line into the statement buffer, returning the byte
+    /// index just past a terminating delimiter if one was found.
+    fn scan_line(&mut self) -> Option<usize> {
+        let bytes = self.line.as_bytes();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let c = bytes[i];
+            let next = bytes.get(i + 1).copied();
+            let at_start = self.statement.trim().is_empty();
+
+            if self.state == State::Code {
+                if at_start && !c.is_ascii_whitespace() {
+                    if let Some(delimiter) = delimiter_command(&self.line[i..]) {
+                        self.start = self.offset + i as u64;
+                        self.statement = self.line[i..].trim_end().to_string();
+                        self.delimiter = delimiter.to_string();
+                        return Some(self.line.len());
+                    }
+                }
+                if bytes[i..].starts_with(self.delimiter.as_bytes()) {
+                    return Some(i + self.delimiter.len());
+                }
+            }
+
+            match self.state {
+                State::Code => match c {
+                    b'\'' => self.state = State::SingleQuote,
+                    b'"' => self.state = State::DoubleQuote,
+                    b'`' => self.state = State::Backtick,
+                    b'#' => self.state = State::LineComment,
+                    b'-' if next == Some(b'-')
+                        && bytes.get(i + 2).is_none_or(|c| c.is_ascii_whitespace()) =>
+                    {
+                        self.state = State::LineComment
+                    }
+                    // MySQL runs the text of `/*!...*/`, so it is kept as code.
+                    b'/' if next == Some(b'*') && bytes.get(i + 2) != Some(&b'!') => {
+                        self.state = State::BlockComment;
+                        if at_start {
+                            i += 2;
+                            continue;
+                        }
+                    }
+                    _ => {}
+                },
+                State::SingleQuote | State::DoubleQuote => {
+                    let quote = if self.state == State::SingleQuote {
+                        b'\''
+                    } else {
+                        b'"'
+                    };
+                    if c == b'\\' {
+                        // Keep the escaped character verbatim.
+                        let width = next.map_or(0, utf8_width);
+                        let end = (i + 1 + width).min(bytes.len());
+                        self.statement.push_str(&self.line[i..end]);
+                        i = end;
+                        continue;
+                    } else if c == quote {
+                        self.state = State::Code;
+                    }
+                }
+                State::Backtick => {
+                    if c == b'`' {
+                        self.state = State::Code;
+                    }
+                }
+                State::LineComment => {
+                    if c == b'\n' {
+                        self.state = State::Code;
+                    }
+                }
+                State::BlockComment => {
+                    if c == b'*' && next == Some(b'/') {
+                        self.state = State::Code;
+                        if at_start {
+                            i += 2;
+                            continue;
+                        }
+                        self.statement.push_str("*/");
+                        i += 2;
+                        continue;
+                    }
+                }
+            }
+
+            let skip = at_start
+                && (c.is_ascii_whitespace()
+                    || matches!(self.state, State::LineComment | State::BlockComment));
+            if !skip {
+                if self.statement.is_empty() {
+                    self.start = self.offset + i as u64;
+                }
+                let width = utf8_width(c);
+                let end = (i + width).min(bytes.len());
+                self.statement.push_str(&self.line[i..end]);
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+
+        None
+    }
+
+    fn take_statement(&mut self) -> String {
+        let statement = self.statement.trim().to_string();
+        self.statement.clear();
+        statement
+    }
+}
+
+/// The new delimiter, when `line` is a `DELIMITER` command.
+fn delimiter_command(line: &str) -> Option<&str> {
+    let (command, rest) = line.split_once(|c: char| c.is_ascii_whitespace())?;
+    if !command.eq_ignore_ascii_case("DELIMITER") {
+        return None;
+    }
+    rest.split_ascii_whitespace().next()
+}
+
+fn utf8_width(first_byte: u8) -> usize {
+    match first_byte {
+        b if b < 0x80 => 1,
+        b if b >= 0xf0 => 4,
+        b if b >= 0xe0 => 3,
+        _ => 2,
+    }
+}
+
+impl<R: BufRead> StatementSplitter<R> {
+    /// The next statement with its byte range in the input, including the `;`.
+    pub fn next_with_span(&mut self) -> Option<ExtractResult<(String, Range<u64>)>> {
+        while !self.done {
+            if self.line.is_empty() {
+                match self.reader.read_line(&mut self.line) {
+                    Ok(0) => {
+                        self.done = true;
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(err) => return Some(Err(err.into())),
+                }
+            }
+
+            match self.scan_line() {
+                Some(end) => {
+                    self.line.drain(..end);
+                    self.offset += end as u64;
+                    let span = self.start..self.offset;
+                    let statement = self.take_statement();
+                    if !statement.is_empty() {
+                        return Some(Ok((statement, span)));
+                    }
+                }
+                None => {
+                    self.offset += self.line.len() as u64;
+                    self.line.clear();
+                }
+            }
+        }
+
+        let statement = self.take_statement();
+        let span = self.start..self.start + statement.len() as u64;
+        (!statement.is_empty()).then_some(Ok((statement, span)))
+    }
+}
+
+impl<R: BufRead> Iterator for StatementSplitter<R> {
+    type Item = ExtractResult<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_span()
+            .map(|result| result.map(|(statement, _)| statement))
+    }
+}
+
+/// A statement read from a dump.
+#[derive(Debug, Clone)]
+pub struct DumpStatement {
+    pub statement: Statement,
+    /// The text of the statement as read, without its delimiter.
+    pub sql: String,
+    /// The database selected by the last `USE` statement, this one included.
+    pub database: Option<String>,
+    /// Byte range of the statement in the input, including the `;`.
+    pub span: Range<u64>,
+    /// The delimiter that ended the statement, empty for a `DELIMITER`
+    /// command, which ends at its line.
+    pub terminator: String,
+}
+
+/// Streams a dump as typed statements.
+///
+/// ```no_run
+/// use sqlex::dump::DumpReader;
+/// use sqlex::parser::statements::Statement;
+///
+/// let file = std::fs::File::open("dump.sql")?;
+/// for entry in DumpReader::new(file) {
+///     let entry = entry?;
+///     if let Statement::Insert(insert) = &entry.statement {
+///         println!("{:?}.{}: {} rows", entry.database, insert.table_name, insert.values.len());
+///     }
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct DumpReader<R: Read> {
+    splitter: StatementSplitter<BufReader<R>>,
+    database: Option<String>,
+}
+
+impl<R: Read> DumpReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            splitter: StatementSplitter::new(BufReader::new(reader)),
+            database: None,
+        }
+    }
+
+    /// The database selected by the last `USE` statement read so far.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+}
+
+impl<R: Read> Iterator for DumpReader<R> {
+    type Item = ExtractResult<DumpStatement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (sql, span) = match self.splitter.next_with_span()? {
+            Ok(next) => next,
+            Err(err) => return Some(Err(err)),
+        };
+        let terminator = match delimiter_command(&sql) {
+            Some(_) => String::new(),
+            None => self.splitter.delimiter().to_string(),
+        };
+        let statement = Statement::parse(&sql);
+        if let Statement::UseDatabase(use_database) = &statement {
+            self.database = Some(use_database.name.clone());
+        }
+
+        Some(Ok(DumpStatement {
+            statement,
+            sql,
+            database: self.database.clone(),
+            span,
+            terminator,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(sql: &str) -> Vec<String> {
+        StatementSplitter::new(sql.as_bytes())
+            .collect::<ExtractResult<Vec<String>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let statements = split(
+            "-- a comment\n/*!40101 SET NAMES utf8 */;\n/* plain */ USE `db`; INSERT INTO `t` VALUES ('a;b', 'it''s', 'O\\'Brien');\n\nCREATE TABLE `t` (\n  `id` int -- trailing\n);",
+        );
+
+        assert_eq!(
+            statements,
+            vec![
+                "/*!40101 SET NAMES utf8 */",
+                "USE `db`",
+                "INSERT INTO `t` VALUES ('a;b', 'it''s', 'O\\'Brien')",
+                "CREATE TABLE `t` (\n  `id` int -- trailing\n)",
+            ]
+        );
+
+        let entry = DumpReader::new("/*!40014 SET FOREIGN_KEY_CHECKS=0 */;\n".as_bytes())
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(entry.statement, Statement::Unknown(_)));
+        assert_eq!(
+            entry.statement.to_string(),
+            "/*!40014 SET FOREIGN_KEY_CHECKS=0 */"
+        );
+    }
+
+    #[test]
+    fn test_split_on_changed_delimiter() {
+        let sql = "DELIMITER ;;\n/*!50003 CREATE*/ /*!50003 TRIGGER `t_bi` BEFORE INSERT ON `t` FOR EACH ROW BEGIN\n  SET NEW.a = 1;\n  SET NEW.b = 2;\nEND */;;\nDELIMITER ;\nSELECT 1;\n";
+
+        assert_eq!(
+            split(sql),
+            vec![
+                "DELIMITER ;;",
+                "/*!50003 CREATE*/ /*!50003 TRIGGER `t_bi` BEFORE INSERT ON `t` FOR EACH ROW BEGIN\n  SET NEW.a = 1;\n  SET NEW.b = 2;\nEND */",
+                "DELIMITER ;",
+                "SELECT 1",
+            ]
+        );
+        assert_eq!(
+            DumpReader::new(sql.as_bytes())
+                .map(|entry| entry.unwrap().terminator)
+                .collect::<Vec<_>>(),
+            ["", ";;", "", ";"]
+        );
+    }
+
+    #[test]
+    fn test_read_schema_groups_tables_by_database() {
+        let databases = read_schema(
+            "USE `a`;\nCREATE TABLE `t1` (\n  `id` int NOT NULL\n);\nUSE `b`;\nCREATE TABLE `t2` (\n  `id` int NOT NULL\n);\nINSERT INTO `t2` VALUES (1);"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            databases
+                .iter()
+                .map(|db| (db.name.as_deref(), db.tables[0].name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(Some("a"), "t1"), (Some("b"), "t2")]
+        );
+    }
+
+    #[test]
+    fn test_split_statement_across_lines() {
+        let statements = split("insert  into `t`(`a`) values \n('x\ny'),('z');\nSELECT 1");
+
+        assert_eq!(
+            statements,
+            vec!["insert  into `t`(`a`) values \n('x\ny'),('z')", "SELECT 1"]
+        );
+    }
+
+    #[test]
+    fn test_dump_reader_yields_statements_with_context() {
+        let sql = "-- header\nUSE `shop`;\nINSERT INTO `t` (`a`) VALUES ('x;y');\nLOCK TABLES `t` WRITE;\nUSE `crm`;\nDELETE FROM `t`";
+        let entries = DumpReader::new(sql.as_bytes())
+            .collect::<ExtractResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 5);
+        assert!(matches!(entries[0].statement, Statement::UseDatabase(_)));
+        assert!(matches!(entries[1].statement, Statement::Insert(_)));
+        assert!(matches!(entries[2].statement, Statement::Unknown(_)));
+        assert!(matches!(entries[4].statement, Statement::Delete(_)));
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.database.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                Some("shop"),
+                Some("shop"),
+                Some("shop"),
+                Some("crm"),
+                Some("crm")
+            ]
+        );
+
+        let slice = |span: &Range<u64>| &sql[span.start as usize..span.end as usize];
+        assert_eq!(slice(&entries[0].span), "USE `shop`;");
+        assert_eq!(
+            slice(&entries[1].span),
+            "INSERT INTO `t` (`a`) VALUES ('x;y');"
+        );
+        assert_eq!(slice(&entries[4].span), "DELETE FROM `t`");
+    }
+}
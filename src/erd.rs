@@ -0,0 +1,427 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::dump::DatabaseSchema;
+use crate::parser::statements::CreateTable;
+use crate::parser::types::Column;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErdFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid `erDiagram`
+    Mermaid,
+}
+
+/// A table in the diagram.
+struct Entity<'a> {
+    database: Option<&'a str>,
+    table: &'a CreateTable,
+    id: String,
+}
+
+/// A foreign key from `child` to `parent`, both indexes into the entities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relationship {
+    pub child: usize,
+    pub parent: usize,
+    pub name: Option<String>,
+    pub columns: Vec<String>,
+    /// A child row may reference no parent, because a key column is nullable.
+    pub optional: bool,
+    /// At most one child row references each parent, because the key columns are unique.
+    pub unique: bool,
+}
+
+/// The tables of a dump and the foreign keys between them.
+pub struct Erd<'a> {
+    entities: Vec<Entity<'a>>,
+    relationships: Vec<Relationship>,
+}
+
+impl<'a> Erd<'a> {
+    pub fn new(databases: &'a [DatabaseSchema]) -> Self {
+        let qualify = databases.len() > 1;
+        let entities = databases
+            .iter()
+            .flat_map(|database| {
+                database.tables.iter().map(move |table| Entity {
+                    database: database.name.as_deref(),
+                    table,
+                    id: match (qualify, database.name.as_deref()) {
+                        (true, Some(database)) => identifier(&format!("{database}_{}", table.name)),
+                        _ => identifier(&table.name),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut relationships = Vec::new();
+        for (child, entity) in entities.iter().enumerate() {
+            for foreign_key in &entity.table.foreign_keys {
+                // References resolve within the same database first.
+                let parent = entities
+                    .iter()
+                    .position(|parent| {
+                        parent.database == entity.database
+                            && parent.table.name == foreign_key.foreign_table_name
+                    })
+                    .or_else(|| {
+                        entities
+                            .iter()
+                            .position(|parent| parent.table.name == foreign_key.foreign_table_name)
+                    });
+                let Some(parent) = parent else {
+                    continue;
+                };
+
+                let columns = &foreign_key.local_column_names;
+                relationships.push(Relationship {
+                    child,
+                    parent,
+                    name: foreign_key.name.clone(),
+                    columns: columns.clone(),
+                    optional: columns.iter().any(|name| {
+                        column(entity.table, name).is_none_or(|column| column.nullable)
+                    }),
+                    unique: is_unique(entity.table, columns),
+                });
+            }
+        }
+
+        Self {
+            entities,
+            relationships,
+        }
+    }
+
+    pub fn relationships(&self) -> &[Relationship] {
+        &self.relationships
+    }
+
+    /// Keep only `tables` and the tables up to `hops` foreign keys away from them.
+    pub fn retain_neighbourhood(&mut self, tables: &[String], hops: usize) {
+        let mut distances = vec![None; self.entities.len()];
+        let mut queue = VecDeque::new();
+        for (i, entity) in self.entities.iter().enumerate() {
+            if tables.contains(&entity.table.name) {
+                distances[i] = Some(0);
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            let distance = distances[i].expect("visited");
+            if distance == hops {
+                continue;
+            }
+            for relationship in &self.relationships {
+                let neighbour = match (relationship.child == i, relationship.parent == i) {
+                    (true, _) => relationship.parent,
+                    (_, true) => relationship.child,
+                    _ => continue,
+                };
+                if distances[neighbour].is_none() {
+                    distances[neighbour] = Some(distance + 1);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        let mut new_index = Vec::with_capacity(self.entities.len());
+        let mut entities = Vec::new();
+        for (entity, distance) in std::mem::take(&mut self.entities)
+            .into_iter()
+            .zip(&distances)
+        {
+            new_index.push(distance.map(|_| entities.len()));
+            if distance.is_some() {
+                entities.push(entity);
+            }
+        }
+        self.entities = entities;
+        self.relationships.retain_mut(|relationship| {
+            match (
+                new_index[relationship.child],
+                new_index[relationship.parent],
+            ) {
+                (Some(child), Some(parent)) => {
+                    relationship.child = child;
+                    relationship.parent = parent;
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+
+    pub fn render(&self, format: ErdFormat) -> String {
+        match format {
+            ErdFormat::Dot => self.render_dot(),
+            ErdFormat::Mermaid => self.render_mermaid(),
+        }
+    }
+
+    fn render_dot(&self) -> String {
+        let mut out = String::from(
+            "digraph erd {\n  rankdir=LR;\n  node [shape=plaintext, fontname=\"Helvetica\"];\n  edge [dir=both, fontname=\"Helvetica\", fontsize=10];\n",
+        );
+
+        for entity in &self.entities {
+            let _ = write!(
+                out,
+                "\n  {} [label=<\n    <table border=\"0\" cellborder=\"1\" cellspacing=\"0\">\n      <tr><td bgcolor=\"lightgrey\" colspan=\"2\"><b>{}</b></td></tr>\n",
+                entity.id,
+                html_escape(&entity.table.name)
+            );
+            for (column, markers) in key_columns(entity.table) {
+                let _ = writeln!(
+                    out,
+                    "      <tr><td align=\"left\">{} {}</td><td>{}</td></tr>",
+                    html_escape(&column.name),
+                    html_escape(&type_name(column)),
+                    markers.join(", ")
+                );
+            }
+            out.push_str("    </table>\n  >];\n");
+        }
+
+        if !self.relationships.is_empty() {
+            out.push('\n');
+        }
+        for relationship in &self.relationships {
+            // Crow's foot notation: the tail sits at the child, the head at the parent.
+            let _ = writeln!(
+                out,
+                "  {} -> {} [arrowtail={}, arrowhead={}, label=\"{}\"];",
+                self.entities[relationship.child].id,
+                self.entities[relationship.parent].id,
+                if relationship.unique {
+                    "teeodot"
+                } else {
+                    "crowodot"
+                },
+                if relationship.optional {
+                    "teeodot"
+                } else {
+                    "teetee"
+                },
+                relationship.columns.join(", ").replace('"', "\\\"")
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn render_mermaid(&self) -> String {
+        let mut out = String::from("erDiagram\n");
+
+        for entity in &self.entities {
+            let _ = writeln!(out, "    {} {{", entity.id);
+            for (column, markers) in key_columns(entity.table) {
+                let _ = writeln!(
+                    out,
+                    "        {} {} {}",
+                    identifier(&type_name(column)),
+                    identifier(&column.name),
+                    markers.join(", ")
+                );
+            }
+            let _ = writeln!(out, "    }}");
+        }
+
+        for relationship in &self.relationships {
+            let _ = writeln!(
+                out,
+                "    {} {}--{} {} : \"{}\"",
+                self.entities[relationship.parent].id,
+                if relationship.optional { "|o" } else { "||" },
+                if relationship.unique { "o|" } else { "o{" },
+                self.entities[relationship.child].id,
+                relationship.columns.join(", ").replace('"', "'")
+            );
+        }
+
+        out
+    }
+}
+
+fn column<'a>(table: &'a CreateTable, name: &str) -> Option<&'a Column> {
+    table.columns.iter().find(|column| column.name == name)
+}
+
+/// Whether `columns` are covered by the primary key or a unique index.
+fn is_unique(table: &CreateTable, columns: &[String]) -> bool {
+    let covers = |key: &[String]| !key.is_empty() && key.iter().all(|name| columns.contains(name));
+
+    table
+        .primary_key
+        .as_ref()
+        .is_some_and(|primary_key| covers(&primary_key.column_names))
+        || table
+            .indexes
+            .iter()
+            .any(|index| index.unique && covers(&index.columns))
+}
+
+/// The primary, foreign and unique key columns of `table`, with their markers.
+fn key_columns(table: &CreateTable) -> Vec<(&Column, Vec<&'static str>)> {
+    let primary = table
+        .primary_key
+        .iter()
+        .flat_map(|primary_key| &primary_key.column_names)
+        .collect::<BTreeSet<_>>();
+    let foreign = table
+        .foreign_keys
+        .iter()
+        .flat_map(|foreign_key| &foreign_key.local_column_names)
+        .collect::<BTreeSet<_>>();
+    let unique = table
+        .indexes
+        .iter()
+        .filter(|index| index.unique)
+        .flat_map(|index| &index.columns)
+        .collect::<BTreeSet<_>>();
+
+    table
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let markers = [
+                (primary.contains(&column.name), "PK"),
+                (foreign.contains(&column.name), "FK"),
+                (unique.contains(&column.name), "UK"),
+            ]
+            .into_iter()
+            .filter_map(|(is_key, marker)| is_key.then_some(marker))
+            .collect::<Vec<_>>();
+            (!markers.is_empty()).then_some((column, markers))
+        })
+        .collect()
+}
+
+fn type_name(column: &Column) -> String {
+    let name: &'static str = (&column.data_type).into();
+    name.to_ascii_lowercase()
+}
+
+/// A DOT and Mermaid safe identifier.
+fn identifier(name: &str) -> String {
+    let mut identifier = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::read_schema;
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `users` (
+  `id` int NOT NULL,
+  `email` varchar(255) NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `email` (`email`)
+) ENGINE=InnoDB;
+CREATE TABLE `profiles` (
+  `user_id` int NOT NULL,
+  PRIMARY KEY (`user_id`),
+  CONSTRAINT `profiles_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB;
+CREATE TABLE `orders` (
+  `id` int NOT NULL,
+  `user_id` int DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `orders_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB;
+CREATE TABLE `order_items` (
+  `order_id` int NOT NULL,
+  `sku` varchar(20) NOT NULL,
+  KEY `order_id` (`order_id`),
+  CONSTRAINT `items_order` FOREIGN KEY (`order_id`) REFERENCES `orders` (`id`)
+) ENGINE=InnoDB;
+"#;
+
+    fn databases() -> Vec<DatabaseSchema> {
+        read_schema(DUMP.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_infers_cardinality() {
+        let databases = databases();
+        let erd = Erd::new(&databases);
+        let cardinality = erd
+            .relationships()
+            .iter()
+            .map(|r| (r.name.as_deref().unwrap(), r.optional, r.unique))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cardinality,
+            vec![
+                ("profiles_user", false, true),
+                ("orders_user", true, false),
+                ("items_order", false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_renders_mermaid() {
+        let databases = databases();
+        let diagram = Erd::new(&databases).render(ErdFormat::Mermaid);
+
+        assert!(diagram.starts_with(
+            "erDiagram\n    users {\n        int id PK\n        varchar email UK\n    }\n"
+        ));
+        assert!(diagram.contains("    users ||--o| profiles : \"user_id\"\n"));
+        assert!(diagram.contains("    users |o--o{ orders : \"user_id\"\n"));
+    }
+
+    #[test]
+    fn test_renders_dot() {
+        let databases = databases();
+        let diagram = Erd::new(&databases).render(ErdFormat::Dot);
+
+        assert!(diagram.starts_with("digraph erd {"));
+        assert!(diagram.contains(
+            "  order_items -> orders [arrowtail=crowodot, arrowhead=teetee, label=\"order_id\"];\n"
+        ));
+        // Only key columns are shown.
+        assert!(!diagram.contains("sku"));
+    }
+
+    #[test]
+    fn test_limits_to_neighbourhood() {
+        let databases = databases();
+        let mut erd = Erd::new(&databases);
+        erd.retain_neighbourhood(&[String::from("order_items")], 1);
+
+        let tables = erd
+            .entities
+            .iter()
+            .map(|entity| entity.table.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(tables, vec!["orders", "order_items"]);
+        assert_eq!(erd.relationships().len(), 1);
+        assert_eq!(erd.relationships()[0].parent, 0);
+    }
+}
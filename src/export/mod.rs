@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::dump::{parse_statement, StatementSplitter};
+use crate::parser::statements::{CreateTable, Insert, UseDatabase};
+use crate::parser::types::DataType;
+use crate::parser::Rule;
+use crate::value::Value;
+use crate::ExtractResult;
+
+mod parquet;
+
+use self::parquet::ParquetTableWriter;
+pub use self::parquet::ParquetType;
+
+/// Rows buffered per table before a Parquet row group is written.
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Where each table's file is placed inside the output directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// `<db>/<table>.<ext>`
+    Nested,
+    /// `<db>.<table>.<ext>`
+    Flat,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub delimiter: u8,
+    pub null: String,
+    pub layout: Layout,
+    pub out_dir: PathBuf,
+    pub row_group_size: usize,
+}
+
+type TableKey = (Option<String>, String);
+
+enum RowWriter {
+    Csv(Box<csv::Writer<BufWriter<File>>>),
+    Ndjson(BufWriter<File>),
+    Parquet(Box<ParquetTableWriter>),
+}
+
+struct TableWriter {
+    path: PathBuf,
+    columns: Vec<String>,
+    data_types: Vec<Option<DataType>>,
+    writer: RowWriter,
+}
+
+/// A row serialized as a JSON object with keys in column order.
+struct JsonRow<'a> {
+    columns: &'a [String],
+    values: &'a [Value],
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            map.serialize_entry(column, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Streams the `INSERT` statements of a dump into one CSV, NDJSON or Parquet file per table.
+pub struct Exporter {
+    options: ExportOptions,
+    current_db: Option<String>,
+    tables: HashMap<TableKey, CreateTable>,
+    writers: HashMap<TableKey, TableWriter>,
+}
+
+impl Exporter {
+    pub fn new(options: ExportOptions) -> Self {
+        Self {
+            options,
+            current_db: None,
+            tables: HashMap::new(),
+            writers: HashMap::new(),
+        }
+    }
+
+    /// Write the rows of every `INSERT` in `reader`. Fails on an `INSERT` the
+    /// grammar cannot read, rather than leaving its rows out.
+    pub fn export<R: BufRead>(&mut self, reader: R) -> ExtractResult<()> {
+        let mut splitter = StatementSplitter::new(reader);
+        while let Some(statement) = splitter.next_with_span() {
+            let (sql, span) = statement?;
+            self.handle_statement(&sql)
+                .with_context(|| format!("at byte {} of the dump", span.start))?;
+        }
+        Ok(())
+    }
+
+    fn handle_statement(&mut self, sql: &str) -> ExtractResult<()> {
+        let keyword = sql
+            .split_ascii_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match keyword.as_str() {
+            "USE" => {
+                if let Some(use_database) = parse_statement::<UseDatabase>(Rule::USE_DATABASE, sql)
+                {
+                    self.current_db = Some(use_database.name);
+                }
+            }
+            "CREATE" => {
+                if let Some(create_table) = parse_statement::<CreateTable>(Rule::CREATE_TABLE, sql)
+                {
+                    self.tables.insert(
+                        (self.current_db.clone(), create_table.name.clone()),
+                        create_table,
+                    );
+                }
+            }
+            "INSERT" => match parse_statement::<Insert>(Rule::INSERT_STATEMENT, sql) {
+                Some(insert) => self.write_insert(&insert)?,
+                None => bail!(
+                    "unable to parse the INSERT starting with `{}`",
+                    sql.chars().take(80).collect::<String>()
+                ),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn write_insert(&mut self, insert: &Insert) -> ExtractResult<()> {
+        let key = (self.current_db.clone(), insert.table_name.clone());
+        if !self.writers.contains_key(&key) {
+            let writer = self.open_table(&key, insert)?;
+            self.writers.insert(key.clone(), writer);
+        }
+        let table = self.writers.get_mut(&key).expect("table writer");
+
+        // Map the insert's columns onto the file's header.
+        let positions = if insert.column_names.is_empty() {
+            (0..table.columns.len()).collect::<Vec<_>>()
+        } else {
+            insert
+                .column_names
+                .iter()
+                .map(|name| match table.columns.iter().position(|c| c == name) {
+                    Some(i) => Ok(i),
+                    None => bail!(
+                        "`{}` has no column `{name}`, the columns of its file are {}",
+                        insert.table_name,
+                        table.columns.join(", ")
+                    ),
+                })
+                .collect::<ExtractResult<Vec<_>>>()?
+        };
+
+        for row in &insert.values {
+            if row.0.len() != positions.len() {
+                bail!(
+                    "a row of `{}` has {} values for {} columns",
+                    insert.table_name,
+                    row.0.len(),
+                    positions.len()
+                );
+            }
+            let mut values = vec![Value::Null; table.columns.len()];
+            for (value, &i) in row.0.iter().zip(&positions) {
+                values[i] = Value::from_insert(value, table.data_types[i].as_ref());
+            }
+            table.write_row(&values, &self.options.null)?;
+        }
+        Ok(())
+    }
+
+    fn open_table(&self, key: &TableKey, insert: &Insert) -> ExtractResult<TableWriter> {
+        let definition = self.tables.get(key);
+        let (columns, data_types) = match definition {
+            Some(create_table) => (
+                create_table
+                    .columns
+                    .iter()
+                    .map(|c| c.name.clone())
+                    .collect::<Vec<_>>(),
+                create_table
+                    .columns
+                    .iter()
+                    .map(|c| Some(c.data_type.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+            None => {
+                let columns = if insert.column_names.is_empty() {
+                    let width = insert.values.first().map_or(0, |row| row.0.len());
+                    (1..=width).map(|i| format!("column_{i}")).collect()
+                } else {
+                    insert.column_names.clone()
+                };
+                let data_types = vec![None; columns.len()];
+                (columns, data_types)
+            }
+        };
+
+        let path = self.table_path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("unable to create {}", parent.display()))?;
+        }
+        let file =
+            File::create(&path).with_context(|| format!("unable to create {}", path.display()))?;
+
+        let writer = match self.options.format {
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .delimiter(self.options.delimiter)
+                    .from_writer(BufWriter::new(file));
+                writer.write_record(&columns)?;
+                RowWriter::Csv(Box::new(writer))
+            }
+            ExportFormat::Ndjson => RowWriter::Ndjson(BufWriter::new(file)),
+            ExportFormat::Parquet => RowWriter::Parquet(Box::new(ParquetTableWriter::new(
+                file,
+                &columns,
+                &data_types,
+                self.options.row_group_size,
+            )?)),
+        };
+
+        Ok(TableWriter {
+            path,
+            columns,
+            data_types,
+            writer,
+        })
+    }
+
+    fn table_path(&self, (db, table): &TableKey) -> PathBuf {
+        let table = sanitize_file_name(table);
+        let extension = self.options.format.extension();
+
+        match (db, self.options.layout) {
+            (Some(db), Layout::Nested) => self
+                .options
+                .out_dir
+                .join(sanitize_file_name(db))
+                .join(format!("{table}.{extension}")),
+            (Some(db), Layout::Flat) => self
+                .options
+                .out_dir
+                .join(format!("{}.{table}.{extension}", sanitize_file_name(db))),
+            (None, _) => self.options.out_dir.join(format!("{table}.{extension}")),
+        }
+    }
+
+    /// Flush every table file, returning the paths that were written.
+    pub fn finish(self) -> ExtractResult<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for (_, table) in self.writers {
+            match table.writer {
+                RowWriter::Csv(mut writer) => writer.flush()?,
+                RowWriter::Ndjson(mut writer) => writer.flush()?,
+                RowWriter::Parquet(writer) => writer.finish()?,
+            }
+            paths.push(table.path);
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+impl TableWriter {
+    fn write_row(&mut self, values: &[Value], null: &str) -> ExtractResult<()> {
+        match &mut self.writer {
+            RowWriter::Csv(writer) => {
+                writer.write_record(
+                    values
+                        .iter()
+                        .map(|value| value.to_text().unwrap_or_else(|| null.to_string())),
+                )?;
+            }
+            RowWriter::Ndjson(writer) => {
+                let values = values
+                    .iter()
+                    .zip(&self.data_types)
+                    .map(|(value, data_type)| iso_datetime(value, data_type.as_ref()))
+                    .collect::<Vec<_>>();
+                serde_json::to_writer(
+                    &mut *writer,
+                    &JsonRow {
+                        columns: &self.columns,
+                        values: &values,
+                    },
+                )?;
+                writer.write_all(b"\n")?;
+            }
+            RowWriter::Parquet(writer) => writer.write_row(values)?,
+        }
+        Ok(())
+    }
+}
+
+/// A `DATETIME` or `TIMESTAMP` value in the ISO 8601 form JSON readers take,
+/// `2024-01-02T10:00:00`. Other values, and zero dates, are kept as they are.
+fn iso_datetime(value: &Value, data_type: Option<&DataType>) -> Value {
+    match (value, data_type) {
+        (Value::Text(text), Some(DataType::DateTime { .. } | DataType::Timestamp { .. }))
+            if parquet::parse_datetime_micros(text).is_some() =>
+        {
+            Value::Text(text.trim().replacen(' ', "T", 1))
+        }
+        _ => value.clone(),
+    }
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.replace(['/', '\\'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `users` (
+  `id` int unsigned NOT NULL AUTO_INCREMENT,
+  `name` varchar(50) DEFAULT NULL,
+  `balance` decimal(10,2) NOT NULL,
+  `prefs` json DEFAULT NULL,
+  `seen_at` datetime(3) DEFAULT NULL
+) ENGINE=InnoDB;
+INSERT INTO `users` (`id`, `name`, `balance`, `prefs`, `seen_at`) VALUES (1, 'O\'Brien', '10.50', '{"theme": "dark"}', '2024-01-02 10:00:00.250'), (2, NULL, '0.00', NULL, '0000-00-00 00:00:00.000');
+INSERT INTO `users` (`name`, `id`, `balance`) VALUES ('a;b', 3, '1.00');
+"#;
+
+    fn export(format: ExportFormat, layout: Layout) -> (TempDir, Vec<PathBuf>) {
+        let temp_dir = TempDir::new().unwrap();
+        let mut exporter = Exporter::new(ExportOptions {
+            format,
+            delimiter: b',',
+            null: String::from("\\N"),
+            layout,
+            out_dir: temp_dir.path().to_path_buf(),
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+        });
+        exporter.export(DUMP.as_bytes()).unwrap();
+        let paths = exporter.finish().unwrap();
+        (temp_dir, paths)
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let (temp_dir, paths) = export(ExportFormat::Csv, Layout::Nested);
+
+        assert_eq!(paths, vec![temp_dir.path().join("shop").join("users.csv")]);
+        assert_eq!(
+            std::fs::read_to_string(&paths[0]).unwrap(),
+            "id,name,balance,prefs,seen_at\n1,O'Brien,10.50,\"{\"\"theme\"\":\"\"dark\"\"}\",2024-01-02 10:00:00.250\n2,\\N,0.00,\\N,0000-00-00 00:00:00.000\n3,a;b,1.00,\\N,\\N\n"
+        );
+    }
+
+    #[test]
+    fn test_export_ndjson() {
+        let (temp_dir, paths) = export(ExportFormat::Ndjson, Layout::Flat);
+
+        assert_eq!(paths, vec![temp_dir.path().join("shop.users.ndjson")]);
+        assert_eq!(
+            std::fs::read_to_string(&paths[0]).unwrap(),
+            "{\"id\":1,\"name\":\"O'Brien\",\"balance\":\"10.50\",\"prefs\":{\"theme\":\"dark\"},\"seen_at\":\"2024-01-02T10:00:00.250\"}\n{\"id\":2,\"name\":null,\"balance\":\"0.00\",\"prefs\":null,\"seen_at\":\"0000-00-00 00:00:00.000\"}\n{\"id\":3,\"name\":\"a;b\",\"balance\":\"1.00\",\"prefs\":null,\"seen_at\":null}\n"
+        );
+    }
+
+    #[test]
+    fn test_export_fails_on_unknown_columns() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut exporter = Exporter::new(ExportOptions {
+            format: ExportFormat::Csv,
+            delimiter: b',',
+            null: String::from("\\N"),
+            layout: Layout::Flat,
+            out_dir: temp_dir.path().to_path_buf(),
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+        });
+        let err = exporter
+            .export(
+                "CREATE TABLE `users` (`id` int);\nINSERT INTO `users` (`id`, `email`) VALUES (1, 'ann@example.com');\n"
+                    .as_bytes(),
+            )
+            .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("`users` has no column `email`"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_export_fails_on_unreadable_insert() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut exporter = Exporter::new(ExportOptions {
+            format: ExportFormat::Csv,
+            delimiter: b',',
+            null: String::from("\\N"),
+            layout: Layout::Flat,
+            out_dir: temp_dir.path().to_path_buf(),
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+        });
+        let err = exporter
+            .export("USE `shop`;\nINSERT INTO `users` VALUES (NOW());\n".as_bytes())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("at byte 12"), "{err:#}");
+    }
+}
@@ -0,0 +1,600 @@
+use std::fs::File;
+use std::sync::Arc;
+
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{
+    ByteArray, ByteArrayType, DoubleType, FixedLenByteArray, FixedLenByteArrayType, FloatType,
+    Int32Type, Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+
+use crate::parser::types::DataType;
+use crate::value::Value;
+use crate::ExtractResult;
+
+/// Largest decimal precision Parquet can represent.
+const MAX_DECIMAL_PRECISION: u32 = 38;
+
+/// How a MySQL column is stored in Parquet, derived from its [`DataType`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetType {
+    Int32 { bit_width: i8, signed: bool },
+    Int64 { signed: bool },
+    Decimal { precision: u32, scale: u32 },
+    Float,
+    Double,
+    Date,
+    DateTime { unit: TimeUnit },
+    Timestamp { unit: TimeUnit },
+    Time { unit: TimeUnit },
+    String,
+    Enum,
+    Json,
+    Binary,
+}
+
+impl ParquetType {
+    /// Columns without a known [`DataType`] are written as strings.
+    pub fn from_data_type(data_type: Option<&DataType>) -> Self {
+        let Some(data_type) = data_type else {
+            return Self::String;
+        };
+
+        match data_type {
+            DataType::TinyInt { unsigned, .. } => Self::Int32 {
+                bit_width: 8,
+                signed: !unsigned,
+            },
+            DataType::SmallInt { unsigned, .. } => Self::Int32 {
+                bit_width: 16,
+                signed: !unsigned,
+            },
+            DataType::MediumInt { unsigned, .. } | DataType::Int { unsigned, .. } => Self::Int32 {
+                bit_width: 32,
+                signed: !unsigned,
+            },
+            DataType::BigInt { unsigned, .. } => Self::Int64 { signed: !unsigned },
+            DataType::Bit { .. } => Self::Int64 { signed: false },
+            DataType::Year { .. } => Self::Int32 {
+                bit_width: 16,
+                signed: false,
+            },
+            DataType::Decimal { m, d, .. } => {
+                // MySQL defaults to DECIMAL(10, 0).
+                let precision = m.unwrap_or(10);
+                let scale = d.unwrap_or(0);
+                if precision > MAX_DECIMAL_PRECISION {
+                    Self::String
+                } else {
+                    Self::Decimal { precision, scale }
+                }
+            }
+            DataType::Float { .. } => Self::Float,
+            DataType::Double { .. } => Self::Double,
+            DataType::Date => Self::Date,
+            DataType::DateTime { fsp } => Self::DateTime {
+                unit: time_unit(*fsp),
+            },
+            DataType::Timestamp { fsp } => Self::Timestamp {
+                unit: time_unit(*fsp),
+            },
+            DataType::Time { fsp } => Self::Time {
+                unit: time_unit(*fsp),
+            },
+            DataType::Enum { .. } => Self::Enum,
+            DataType::Json => Self::Json,
+            DataType::Binary { .. }
+            | DataType::Varbinary { .. }
+            | DataType::Blob { .. }
+            | DataType::TinyBlob
+            | DataType::MediumBlob
+            | DataType::LongBlob => Self::Binary,
+            DataType::Char { .. }
+            | DataType::Varchar { .. }
+            | DataType::Text { .. }
+            | DataType::TinyText { .. }
+            | DataType::MediumText { .. }
+            | DataType::LongText { .. }
+            | DataType::Set { .. } => Self::String,
+        }
+    }
+
+    fn physical_type(&self) -> PhysicalType {
+        match self {
+            Self::Int32 { .. } | Self::Date => PhysicalType::INT32,
+            Self::Decimal { precision, .. } if *precision <= 9 => PhysicalType::INT32,
+            Self::Decimal { precision, .. } if *precision <= 18 => PhysicalType::INT64,
+            Self::Decimal { .. } => PhysicalType::FIXED_LEN_BYTE_ARRAY,
+            Self::Int64 { .. }
+            | Self::DateTime { .. }
+            | Self::Timestamp { .. }
+            | Self::Time { .. } => PhysicalType::INT64,
+            Self::Float => PhysicalType::FLOAT,
+            Self::Double => PhysicalType::DOUBLE,
+            Self::String | Self::Enum | Self::Json | Self::Binary => PhysicalType::BYTE_ARRAY,
+        }
+    }
+
+    fn logical_type(&self) -> Option<LogicalType> {
+        match self {
+            Self::Int32 { bit_width, signed } => Some(LogicalType::integer(*bit_width, *signed)),
+            Self::Int64 { signed } => Some(LogicalType::integer(64, *signed)),
+            Self::Decimal { precision, scale } => {
+                Some(LogicalType::decimal(*scale as i32, *precision as i32))
+            }
+            Self::Float | Self::Double | Self::Binary => None,
+            Self::Date => Some(LogicalType::Date),
+            Self::DateTime { unit } => Some(LogicalType::timestamp(false, *unit)),
+            Self::Timestamp { unit } => Some(LogicalType::timestamp(true, *unit)),
+            Self::Time { unit } => Some(LogicalType::time(false, *unit)),
+            Self::String => Some(LogicalType::String),
+            Self::Enum => Some(LogicalType::Enum),
+            Self::Json => Some(LogicalType::Json),
+        }
+    }
+
+    fn field(&self, name: &str) -> ExtractResult<Type> {
+        let mut builder = Type::primitive_type_builder(name, self.physical_type())
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(self.logical_type());
+        if let Self::Decimal { precision, scale } = self {
+            builder = builder
+                .with_precision(*precision as i32)
+                .with_scale(*scale as i32);
+            if self.physical_type() == PhysicalType::FIXED_LEN_BYTE_ARRAY {
+                builder = builder.with_length(16);
+            }
+        }
+        Ok(builder.build()?)
+    }
+}
+
+fn time_unit(fsp: Option<u32>) -> TimeUnit {
+    match fsp {
+        Some(fsp) if fsp > 3 => TimeUnit::MICROS,
+        _ => TimeUnit::MILLIS,
+    }
+}
+
+enum Values {
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Bytes(Vec<ByteArray>),
+    Fixed(Vec<FixedLenByteArray>),
+}
+
+/// Values of one column for the row group being built.
+struct ColumnBuffer {
+    parquet_type: ParquetType,
+    values: Values,
+    def_levels: Vec<i16>,
+}
+
+impl ColumnBuffer {
+    fn new(parquet_type: ParquetType) -> Self {
+        let values = match parquet_type.physical_type() {
+            PhysicalType::INT32 => Values::Int32(Vec::new()),
+            PhysicalType::INT64 => Values::Int64(Vec::new()),
+            PhysicalType::FLOAT => Values::Float(Vec::new()),
+            PhysicalType::DOUBLE => Values::Double(Vec::new()),
+            PhysicalType::FIXED_LEN_BYTE_ARRAY => Values::Fixed(Vec::new()),
+            _ => Values::Bytes(Vec::new()),
+        };
+
+        Self {
+            parquet_type,
+            values,
+            def_levels: Vec::new(),
+        }
+    }
+
+    /// Append `value`, writing a null when it cannot be represented in the column.
+    fn push(&mut self, value: &Value) {
+        let pushed = match (&mut self.values, self.parquet_type) {
+            (_, _) if value.is_null() => false,
+            (Values::Int32(values), ParquetType::Int32 { .. }) => {
+                push_some(values, as_i64(value).map(|v| v as i32))
+            }
+            (Values::Int32(values), ParquetType::Date) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_date(&text))
+                    .map(|days| days as i32),
+            ),
+            (Values::Int32(values), ParquetType::Decimal { scale, .. }) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_decimal(&text, scale))
+                    .map(|v| v as i32),
+            ),
+            (Values::Int64(values), ParquetType::Decimal { scale, .. }) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_decimal(&text, scale))
+                    .map(|v| v as i64),
+            ),
+            (Values::Fixed(values), ParquetType::Decimal { scale, .. }) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_decimal(&text, scale))
+                    .map(|v| FixedLenByteArray::from(v.to_be_bytes().to_vec())),
+            ),
+            (Values::Int64(values), ParquetType::Int64 { .. }) => push_some(values, as_i64(value)),
+            (
+                Values::Int64(values),
+                ParquetType::DateTime { unit } | ParquetType::Timestamp { unit },
+            ) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_datetime_micros(&text))
+                    .map(|micros| scale_micros(micros, unit)),
+            ),
+            (Values::Int64(values), ParquetType::Time { unit }) => push_some(
+                values,
+                value
+                    .to_text()
+                    .and_then(|text| parse_time_micros(&text))
+                    .map(|micros| scale_micros(micros, unit)),
+            ),
+            (Values::Float(values), _) => push_some(values, as_f64(value).map(|v| v as f32)),
+            (Values::Double(values), _) => push_some(values, as_f64(value)),
+            (Values::Bytes(values), _) => push_some(
+                values,
+                value
+                    .to_text()
+                    .map(|text| ByteArray::from(text.into_bytes())),
+            ),
+            _ => false,
+        };
+
+        self.def_levels.push(pushed as i16);
+    }
+
+    fn write(
+        &mut self,
+        writer: &mut parquet::file::writer::SerializedColumnWriter<'_>,
+    ) -> ExtractResult<()> {
+        let def_levels = Some(self.def_levels.as_slice());
+        match &mut self.values {
+            Values::Int32(values) => {
+                writer
+                    .typed::<Int32Type>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+            Values::Int64(values) => {
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+            Values::Float(values) => {
+                writer
+                    .typed::<FloatType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+            Values::Double(values) => {
+                writer
+                    .typed::<DoubleType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+            Values::Bytes(values) => {
+                writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+            Values::Fixed(values) => {
+                writer
+                    .typed::<FixedLenByteArrayType>()
+                    .write_batch(values, def_levels, None)?;
+                values.clear();
+            }
+        }
+        self.def_levels.clear();
+        Ok(())
+    }
+}
+
+fn push_some<T>(values: &mut Vec<T>, value: Option<T>) -> bool {
+    match value {
+        Some(value) => {
+            values.push(value);
+            true
+        }
+        None => false,
+    }
+}
+
+fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(value) => Some(*value),
+        // Unsigned 64-bit values keep their bit pattern, as the Parquet spec requires.
+        Value::UInt(value) => Some(*value as i64),
+        Value::Bool(value) => Some(*value as i64),
+        other => other.to_text()?.parse().ok(),
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float(value) => Some(*value),
+        Value::Int(value) => Some(*value as f64),
+        Value::UInt(value) => Some(*value as f64),
+        other => other.to_text()?.parse().ok(),
+    }
+}
+
+fn scale_micros(micros: i64, unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::MILLIS => micros.div_euclid(1_000),
+        TimeUnit::MICROS => micros,
+        TimeUnit::NANOS => micros * 1_000,
+    }
+}
+
+/// Parse a decimal literal into an integer scaled by `10^scale`, truncating extra digits.
+fn parse_decimal(text: &str, scale: u32) -> Option<i128> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if whole.is_empty() && fraction.is_empty()
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let mut scaled: i128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut fraction = fraction.chars();
+    for _ in 0..scale {
+        let digit = fraction.next().and_then(|c| c.to_digit(10)).unwrap_or(0);
+        scaled = scaled.checked_mul(10)?.checked_add(digit as i128)?;
+    }
+
+    Some(if negative { -scaled } else { scaled })
+}
+
+/// Days since the Unix epoch for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parse `YYYY-MM-DD` into days since the epoch; zero dates are treated as null.
+fn parse_date(text: &str) -> Option<i64> {
+    let mut parts = text.trim().splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Parse `HH:MM:SS[.ffffff]` into microseconds, without any range checks.
+fn parse_clock_micros(text: &str) -> Option<i64> {
+    let (clock, fraction) = text.split_once('.').unwrap_or((text, ""));
+    let mut parts = clock.splitn(3, ':');
+    let hours = parts.next()?.parse::<i64>().ok()?;
+    let minutes = parts.next()?.parse::<i64>().ok()?;
+    let seconds = parts.next().unwrap_or("0").parse::<i64>().ok()?;
+    if fraction.len() > 6 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let micros = format!("{fraction:0<6}").parse::<i64>().ok()?;
+
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1_000_000 + micros)
+}
+
+/// Parse `YYYY-MM-DD HH:MM:SS[.ffffff]` into microseconds since the epoch.
+pub(super) fn parse_datetime_micros(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (date, clock) = text.split_once([' ', 'T']).unwrap_or((text, "00:00:00"));
+    let days = parse_date(date)?;
+    Some(days * 86_400_000_000 + parse_clock_micros(clock)?)
+}
+
+/// Parse a `TIME` value into microseconds since midnight. Values outside of a
+/// single day (MySQL allows intervals) cannot be stored as a Parquet time.
+fn parse_time_micros(text: &str) -> Option<i64> {
+    let micros = parse_clock_micros(text.trim())?;
+    (0..86_400_000_000).contains(&micros).then_some(micros)
+}
+
+/// Writes the rows of a single table to a Parquet file, one row group at a time.
+pub struct ParquetTableWriter {
+    writer: SerializedFileWriter<File>,
+    columns: Vec<ColumnBuffer>,
+    rows: usize,
+    row_group_size: usize,
+}
+
+impl ParquetTableWriter {
+    pub fn new(
+        file: File,
+        columns: &[String],
+        data_types: &[Option<DataType>],
+        row_group_size: usize,
+    ) -> ExtractResult<Self> {
+        let parquet_types = data_types
+            .iter()
+            .map(|data_type| ParquetType::from_data_type(data_type.as_ref()))
+            .collect::<Vec<_>>();
+        let fields = columns
+            .iter()
+            .zip(&parquet_types)
+            .map(|(name, parquet_type)| parquet_type.field(name).map(Arc::new))
+            .collect::<ExtractResult<Vec<_>>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+
+        Ok(Self {
+            writer: SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?,
+            columns: parquet_types.into_iter().map(ColumnBuffer::new).collect(),
+            rows: 0,
+            row_group_size: row_group_size.max(1),
+        })
+    }
+
+    pub fn write_row(&mut self, values: &[Value]) -> ExtractResult<()> {
+        for (column, value) in self.columns.iter_mut().zip(values) {
+            column.push(value);
+        }
+        self.rows += 1;
+        if self.rows >= self.row_group_size {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn flush_row_group(&mut self) -> ExtractResult<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+
+        let mut row_group = self.writer.next_row_group()?;
+        let mut columns = self.columns.iter_mut();
+        while let Some(mut column_writer) = row_group.next_column()? {
+            columns
+                .next()
+                .expect("a buffer for every column")
+                .write(&mut column_writer)?;
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        self.rows = 0;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> ExtractResult<()> {
+        self.flush_row_group()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_maps_data_types() {
+        assert_eq!(
+            ParquetType::from_data_type(Some(&DataType::Int {
+                m: Some(11),
+                unsigned: true,
+                zerofill: false
+            })),
+            ParquetType::Int32 {
+                bit_width: 32,
+                signed: false
+            }
+        );
+        assert_eq!(
+            ParquetType::from_data_type(Some(&DataType::Decimal {
+                m: Some(12),
+                d: Some(2),
+                unsigned: false,
+                zerofill: false
+            })),
+            ParquetType::Decimal {
+                precision: 12,
+                scale: 2
+            }
+        );
+        assert_eq!(
+            ParquetType::from_data_type(Some(&DataType::DateTime { fsp: Some(6) })),
+            ParquetType::DateTime {
+                unit: TimeUnit::MICROS
+            }
+        );
+        assert_eq!(ParquetType::from_data_type(None), ParquetType::String);
+    }
+
+    #[test]
+    fn test_parses_temporal_and_decimal_values() {
+        assert_eq!(parse_date("1970-01-02"), Some(1));
+        assert_eq!(parse_date("0000-00-00"), None);
+        assert_eq!(
+            parse_datetime_micros("2000-03-01 00:00:01.5"),
+            Some(951_868_801_500_000)
+        );
+        assert_eq!(parse_time_micros("838:59:59"), None);
+        assert_eq!(parse_decimal("-12.345", 2), Some(-1234));
+        assert_eq!(parse_decimal("7", 2), Some(700));
+        assert_eq!(parse_decimal("abc", 2), None);
+    }
+
+    #[test]
+    fn test_writes_row_groups() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("users.parquet");
+        let mut writer = ParquetTableWriter::new(
+            File::create(&path).unwrap(),
+            &[String::from("id"), String::from("joined")],
+            &[
+                Some(DataType::BigInt {
+                    m: None,
+                    unsigned: false,
+                    zerofill: false,
+                }),
+                Some(DataType::Date),
+            ],
+            2,
+        )
+        .unwrap();
+        for (id, joined) in [(1, "2024-01-01"), (2, "0000-00-00"), (3, "2024-02-01")] {
+            writer
+                .write_row(&[Value::Int(id), Value::Text(joined.to_string())])
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        assert_eq!(
+            metadata
+                .file_metadata()
+                .schema_descr()
+                .column(1)
+                .logical_type_ref(),
+            Some(&LogicalType::Date)
+        );
+    }
+}
@@ -0,0 +1,696 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use anyhow::{bail, Context};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::dump::DatabaseSchema;
+use crate::masker::{civil_from_days, ValuesRow};
+use crate::parser::parse_utils::{escape_str, unescape_str};
+use crate::parser::statements::{CreateTable, Insert};
+use crate::parser::types::{Column, DataType, DefaultValue, InsertValue, InsertValues};
+use crate::rules::{fakeit_with, find_struct_by_name, FromStrFaking};
+use crate::settings::MaskingConfig;
+use crate::ExtractResult;
+
+/// Attempts at drawing a row that satisfies every unique key before giving up.
+const UNIQUE_ATTEMPTS: usize = 100;
+
+/// Share of nullable columns that are left `NULL`.
+const NULL_RATIO: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// Rows generated for tables without an explicit count.
+    pub rows: usize,
+    /// Row counts per table. When not empty only these tables, and the tables
+    /// they reference, are generated.
+    pub table_rows: HashMap<String, usize>,
+    /// Rows per `INSERT` statement.
+    pub batch_size: usize,
+    pub seed: Option<u64>,
+}
+
+/// Generates `INSERT` statements of synthetic rows for the tables of a schema.
+pub struct Generator<'a> {
+    options: GenerateOptions,
+    config: Option<&'a MaskingConfig>,
+    rng: StdRng,
+}
+
+/// The rows generated so far for a table, kept so children can reference them.
+type Rows = Vec<Vec<InsertValue>>;
+
+impl<'a> Generator<'a> {
+    pub fn new(options: GenerateOptions, config: Option<&'a MaskingConfig>) -> Self {
+        let rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            options,
+            config,
+            rng,
+        }
+    }
+
+    pub fn generate<W: Write>(
+        &mut self,
+        databases: &[DatabaseSchema],
+        out: &mut W,
+    ) -> ExtractResult<()> {
+        // Reference cycles cannot be ordered, so checks are disabled the way mysqldump does.
+        writeln!(
+            out,
+            "/*!40014 SET @OLD_FOREIGN_KEY_CHECKS=@@FOREIGN_KEY_CHECKS, FOREIGN_KEY_CHECKS=0 */;"
+        )?;
+
+        for database in databases {
+            if let Some(name) = &database.name {
+                writeln!(out, "\nUSE `{name}`;")?;
+            }
+
+            let mut generated: HashMap<&str, Rows> = HashMap::new();
+            for (table, count) in self.plan(database) {
+                let rows = self.generate_table(database, table, count, &generated)?;
+                for batch in rows.chunks(self.options.batch_size.max(1)) {
+                    let insert = Insert {
+                        priority: None,
+                        ignore: false,
+                        table_name: table.name.clone(),
+                        column_names: table.columns.iter().map(|c| c.name.clone()).collect(),
+                        values: batch.iter().cloned().map(InsertValues).collect(),
+                    };
+                    writeln!(out, "{insert};")?;
+                }
+                generated.insert(&table.name, rows);
+            }
+        }
+
+        writeln!(
+            out,
+            "\n/*!40014 SET FOREIGN_KEY_CHECKS=@OLD_FOREIGN_KEY_CHECKS */;"
+        )?;
+        Ok(())
+    }
+
+    /// The tables to generate with their row counts, parents before children.
+    fn plan<'t>(&self, database: &'t DatabaseSchema) -> Vec<(&'t CreateTable, usize)> {
+        let table = |name: &str| database.tables.iter().find(|table| table.name == name);
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut pending: Vec<&CreateTable> = if self.options.table_rows.is_empty() {
+            database.tables.iter().collect()
+        } else {
+            database
+                .tables
+                .iter()
+                .filter(|table| self.options.table_rows.contains_key(&table.name))
+                .collect()
+        };
+
+        // Referenced tables are generated too, so foreign keys have rows to point at.
+        while let Some(current) = pending.pop() {
+            if counts.contains_key(current.name.as_str()) {
+                continue;
+            }
+            let count = self
+                .options
+                .table_rows
+                .get(&current.name)
+                .copied()
+                .unwrap_or(self.options.rows);
+            counts.insert(&current.name, count);
+            pending.extend(
+                current
+                    .foreign_keys
+                    .iter()
+                    .filter_map(|key| table(&key.foreign_table_name)),
+            );
+        }
+
+        let mut ordered = Vec::new();
+        let mut visited = HashSet::new();
+        for table in &database.tables {
+            visit(table, database, &counts, &mut visited, &mut ordered);
+        }
+        ordered
+    }
+
+    fn generate_table(
+        &mut self,
+        database: &DatabaseSchema,
+        table: &CreateTable,
+        count: usize,
+        generated: &HashMap<&str, Rows>,
+    ) -> ExtractResult<Rows> {
+        let unique_keys = unique_keys(table);
+        let mut seen: Vec<HashSet<Vec<String>>> = vec![HashSet::new(); unique_keys.len()];
+
+        // Columns use the masking config's rule, or a registry faker named after the column.
+        let config = self.config;
+        let registry = table
+            .columns
+            .iter()
+            .map(|column| find_struct_by_name(&column.name.to_lowercase().replace('_', "")))
+            .collect::<Vec<_>>();
+        let fakers = table
+            .columns
+            .iter()
+            .zip(&registry)
+            .map(|(column, faker)| {
+                config
+                    .and_then(|config| config.rule_for(&column.name))
+                    .and_then(|rule| rule.faker())
+                    .or(faker.as_deref())
+            })
+            .collect::<Vec<_>>();
+        let mut rows = Vec::with_capacity(count);
+
+        for row_number in 0..count {
+            let mut attempts = 0;
+            let row = loop {
+                let row = self.generate_row(
+                    database,
+                    table,
+                    &unique_keys,
+                    row_number,
+                    &fakers,
+                    generated,
+                )?;
+                let keys = unique_keys
+                    .iter()
+                    .map(|key| {
+                        key.iter()
+                            .map(|&i| match &row[i] {
+                                InsertValue::Null => None,
+                                value => Some(value.to_string()),
+                            })
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .collect::<Vec<_>>();
+                let is_unique = keys
+                    .iter()
+                    .zip(&seen)
+                    .all(|(key, seen)| key.as_ref().is_none_or(|key| !seen.contains(key)));
+
+                if is_unique {
+                    for (key, seen) in keys.into_iter().zip(&mut seen) {
+                        if let Some(key) = key {
+                            seen.insert(key);
+                        }
+                    }
+                    break row;
+                }
+
+                attempts += 1;
+                if attempts == UNIQUE_ATTEMPTS {
+                    bail!(
+                        "unable to generate {count} unique rows for `{}` after {} rows: the value space of its unique keys is too small",
+                        table.name,
+                        row_number
+                    );
+                }
+            };
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+
+    fn generate_row(
+        &mut self,
+        database: &DatabaseSchema,
+        table: &CreateTable,
+        unique_keys: &[Vec<usize>],
+        row_number: usize,
+        fakers: &[Option<&dyn FromStrFaking>],
+        generated: &HashMap<&str, Rows>,
+    ) -> ExtractResult<Vec<InsertValue>> {
+        let mut row = table
+            .columns
+            .iter()
+            .zip(fakers)
+            .map(|(column, faker)| self.generate_value(column, row_number, *faker))
+            .collect::<Vec<_>>();
+
+        for key in &table.foreign_keys {
+            let positions = key
+                .local_column_names
+                .iter()
+                .map(|name| table.columns.iter().position(|c| &c.name == name))
+                .collect::<Option<Vec<_>>>();
+            let Some(positions) = positions else {
+                continue;
+            };
+            let Some(parent) = generated.get(key.foreign_table_name.as_str()) else {
+                // Unknown or not yet generated parents (cycles) keep generated values.
+                continue;
+            };
+            let parent_table = database
+                .tables
+                .iter()
+                .find(|parent| parent.name == key.foreign_table_name);
+            let nullable = positions.iter().all(|&i| table.columns[i].nullable);
+
+            // Keys that are unique in the child map rows one-to-one.
+            let parent_row = if unique_keys
+                .iter()
+                .any(|unique| unique.iter().all(|i| positions.contains(i)))
+            {
+                parent.get(row_number)
+            } else if nullable && self.rng.gen_bool(NULL_RATIO) {
+                None
+            } else {
+                parent.choose(&mut self.rng)
+            };
+
+            match parent_row {
+                Some(parent_row) => {
+                    for (&i, foreign_column) in positions.iter().zip(&key.foreign_column_names) {
+                        let parent_position = parent_table
+                            .and_then(|parent| {
+                                parent.columns.iter().position(|c| &c.name == foreign_column)
+                            })
+                            .unwrap_or(i);
+                        if let Some(value) = parent_row.get(parent_position) {
+                            row[i] = value.clone();
+                        }
+                    }
+                }
+                None if nullable => {
+                    for &i in &positions {
+                        row[i] = InsertValue::Null;
+                    }
+                }
+                None => bail!(
+                    "`{}` needs a row in `{}` for each of its rows, generate at least as many parent rows",
+                    table.name,
+                    key.foreign_table_name
+                ),
+            }
+        }
+
+        // Expression rules, such as an email built from the name columns, see the generated row.
+        if let Some(config) = self.config {
+            let columns = table
+                .columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect::<Vec<_>>();
+            for (i, column) in table.columns.iter().enumerate() {
+                let Some(rule) = config.rule_for(&column.name) else {
+                    continue;
+                };
+                if rule.faker().is_some() {
+                    continue;
+                }
+                let value = rule
+                    .expression()
+                    .evaluate(&mut ValuesRow {
+                        columns: &columns,
+                        values: &row,
+                        index: i,
+                        config: Some(config),
+                        table: Some(table),
+                    })
+                    .with_context(|| {
+                        format!("unable to generate `{}`.`{}`", table.name, column.name)
+                    })?;
+                row[i] = match value.to_text() {
+                    Some(text) => {
+                        fit(&text, &column.data_type).unwrap_or_else(|| value.to_insert())
+                    }
+                    None => InsertValue::Null,
+                };
+            }
+        }
+
+        Ok(row)
+    }
+
+    fn generate_value(
+        &mut self,
+        column: &Column,
+        row_number: usize,
+        faker: Option<&dyn FromStrFaking>,
+    ) -> InsertValue {
+        if column.auto_increment {
+            return InsertValue::Number {
+                value: (row_number + 1).to_string(),
+            };
+        }
+        if column.nullable && self.rng.gen_bool(NULL_RATIO) {
+            return InsertValue::Null;
+        }
+        if faker.is_none() && self.rng.gen_bool(0.5) {
+            match &column.default {
+                Some(DefaultValue::Text { value }) => {
+                    return InsertValue::Text {
+                        value: value.clone(),
+                    }
+                }
+                Some(DefaultValue::Number { value }) => {
+                    return InsertValue::Number {
+                        value: value.clone(),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(faker) = faker {
+            let locale = self.config.map(MaskingConfig::default_locale);
+            let fake = faker.fake_in_with(locale.unwrap_or_default(), &mut self.rng);
+            if let Some(value) = fit(&fake, &column.data_type) {
+                return value;
+            }
+        }
+
+        self.random_value(&column.data_type)
+    }
+
+    fn random_value(&mut self, data_type: &DataType) -> InsertValue {
+        let number = |value: String| InsertValue::Number { value };
+        let text = |value: String| InsertValue::Text {
+            value: escape_str(&value),
+        };
+
+        match data_type {
+            DataType::TinyInt { m: Some(1), .. } => number(self.rng.gen_range(0..=1).to_string()),
+            DataType::TinyInt { unsigned, .. } => number(self.int(*unsigned, 127, 255)),
+            DataType::SmallInt { unsigned, .. } => number(self.int(*unsigned, 32_767, 65_535)),
+            DataType::MediumInt { unsigned, .. }
+            | DataType::Int { unsigned, .. }
+            | DataType::BigInt { unsigned, .. } => number(self.int(*unsigned, 100_000, 100_000)),
+            DataType::Bit { m } => {
+                let bits = m.unwrap_or(1).clamp(1, 63);
+                number(self.rng.gen_range(0..(1u64 << bits)).to_string())
+            }
+            DataType::Decimal { m, d, .. } => {
+                let scale = d.unwrap_or(0) as usize;
+                let digits = (m.unwrap_or(10) as usize).saturating_sub(scale).clamp(1, 6);
+                let whole = self.rng.gen_range(0..10u64.pow(digits as u32));
+                let fraction = (0..scale)
+                    .map(|_| char::from(b'0' + self.rng.gen_range(0..10)))
+                    .collect::<String>();
+                number(if scale > 0 {
+                    format!("{whole}.{fraction}")
+                } else {
+                    whole.to_string()
+                })
+            }
+            DataType::Float { .. } | DataType::Double { .. } => {
+                number(format!("{:.2}", self.rng.gen_range(0.0..1000.0)))
+            }
+            DataType::Date => text(self.date()),
+            DataType::DateTime { fsp } | DataType::Timestamp { fsp } => {
+                let date = self.date();
+                text(format!("{date} {}", self.time(*fsp)))
+            }
+            DataType::Time { fsp } => text(self.time(*fsp)),
+            DataType::Year { .. } => number(self.rng.gen_range(1970..=2030).to_string()),
+            DataType::Char { m, .. } => text(self.words(m.unwrap_or(1) as usize)),
+            DataType::Varchar { m, .. } => text(self.words(m.unwrap_or(255).min(64) as usize)),
+            DataType::TinyText { .. } => {
+                let sentence = fakeit_with(&mut self.rng, || fakeit::words::sentence(6));
+                text(truncate(&sentence, 255))
+            }
+            DataType::Text { .. } | DataType::MediumText { .. } | DataType::LongText { .. } => {
+                text(fakeit_with(&mut self.rng, || fakeit::words::sentence(12)))
+            }
+            DataType::Enum { values, .. } => InsertValue::Text {
+                value: values.choose(&mut self.rng).cloned().unwrap_or_default(),
+            },
+            DataType::Set { values, .. } => InsertValue::Text {
+                value: values
+                    .iter()
+                    .filter(|_| self.rng.gen_bool(0.5))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(","),
+            },
+            DataType::Json => text(
+                serde_json::json!({
+                    "id": self.rng.gen_range(1..1000),
+                    "tag": fakeit_with(&mut self.rng, fakeit::words::word),
+                })
+                .to_string(),
+            ),
+            DataType::Binary { m } => text(self.alphanumeric(m.unwrap_or(1) as usize)),
+            DataType::Varbinary { m } => {
+                let length = self.rng.gen_range(1..=(*m as usize).clamp(1, 32));
+                text(self.alphanumeric(length))
+            }
+            DataType::Blob { .. }
+            | DataType::TinyBlob
+            | DataType::MediumBlob
+            | DataType::LongBlob => text(self.alphanumeric(32)),
+        }
+    }
+
+    fn int(&mut self, unsigned: bool, signed_max: u64, unsigned_max: u64) -> String {
+        let max = if unsigned { unsigned_max } else { signed_max };
+        self.rng.gen_range(0..=max).to_string()
+    }
+
+    fn date(&mut self) -> String {
+        // 2000-01-01 plus up to ~25 years.
+        let (year, month, day) = civil_from_days(10_957 + self.rng.gen_range(0..9_131));
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    fn time(&mut self, fsp: Option<u32>) -> String {
+        let time = format!(
+            "{:02}:{:02}:{:02}",
+            self.rng.gen_range(0..24),
+            self.rng.gen_range(0..60),
+            self.rng.gen_range(0..60)
+        );
+        match fsp {
+            Some(fsp @ 1..=6) => {
+                let fraction = (0..fsp)
+                    .map(|_| char::from(b'0' + self.rng.gen_range(0..10)))
+                    .collect::<String>();
+                format!("{time}.{fraction}")
+            }
+            _ => time,
+        }
+    }
+
+    /// Random words, at most `max` characters long.
+    fn words(&mut self, max: usize) -> String {
+        let count = self.rng.gen_range(1..=3);
+        let words = fakeit_with(&mut self.rng, || fakeit::words::sentence(count))
+            .trim_end_matches('.')
+            .to_lowercase();
+        truncate(&words, max).trim_end().to_string()
+    }
+
+    fn alphanumeric(&mut self, length: usize) -> String {
+        (&mut self.rng)
+            .sample_iter(rand::distributions::Alphanumeric)
+            .take(length)
+            .map(char::from)
+            .collect()
+    }
+}
+
+/// Depth-first ordering of the planned tables so parents come before children.
+fn visit<'t>(
+    table: &'t CreateTable,
+    database: &'t DatabaseSchema,
+    counts: &HashMap<&str, usize>,
+    visited: &mut HashSet<&'t str>,
+    ordered: &mut Vec<(&'t CreateTable, usize)>,
+) {
+    let Some(&count) = counts.get(table.name.as_str()) else {
+        return;
+    };
+    if !visited.insert(&table.name) {
+        return;
+    }
+    for key in &table.foreign_keys {
+        if let Some(parent) = database
+            .tables
+            .iter()
+            .find(|parent| parent.name == key.foreign_table_name)
+        {
+            visit(parent, database, counts, visited, ordered);
+        }
+    }
+    ordered.push((table, count));
+}
+
+/// Column positions of the primary key and every unique index.
+fn unique_keys(table: &CreateTable) -> Vec<Vec<usize>> {
+    let position = |name: &String| table.columns.iter().position(|c| &c.name == name);
+
+    table
+        .primary_key
+        .iter()
+        .map(|key| &key.column_names)
+        .chain(
+            table
+                .indexes
+                .iter()
+                .filter(|index| index.unique)
+                .map(|index| &index.columns),
+        )
+        .filter_map(|columns| columns.iter().map(position).collect::<Option<Vec<_>>>())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// Convert a faked value into a value of `data_type`, if it fits.
+fn fit(fake: &str, data_type: &DataType) -> Option<InsertValue> {
+    let text = |value: &str| {
+        Some(InsertValue::Text {
+            value: escape_str(value),
+        })
+    };
+
+    match data_type {
+        DataType::Char { m, .. } | DataType::Varchar { m, .. } => {
+            text(&truncate(fake, m.unwrap_or(255) as usize))
+        }
+        DataType::TinyText { .. } => text(&truncate(fake, 255)),
+        DataType::Text { .. } | DataType::MediumText { .. } | DataType::LongText { .. } => {
+            text(fake)
+        }
+        DataType::Enum { values, .. } => values
+            .iter()
+            .find(|value| unescape_str(value).eq_ignore_ascii_case(fake))
+            .map(|value| InsertValue::Text {
+                value: value.clone(),
+            }),
+        DataType::TinyInt { .. }
+        | DataType::SmallInt { .. }
+        | DataType::MediumInt { .. }
+        | DataType::Int { .. }
+        | DataType::BigInt { .. }
+        | DataType::Year { .. } => fake.parse::<i64>().ok().map(|value| InsertValue::Number {
+            value: value.to_string(),
+        }),
+        DataType::Decimal { .. } | DataType::Float { .. } | DataType::Double { .. } => {
+            fake.parse::<f64>().ok().map(|_| InsertValue::Number {
+                value: fake.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn truncate(value: &str, max_chars: usize) -> String {
+    value.chars().take(max_chars).collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::{parse_statement, read_schema, StatementSplitter};
+    use crate::parser::Rule;
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `orders` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `user_id` int NOT NULL,
+  `status` enum('new','paid') NOT NULL DEFAULT 'new',
+  `total` decimal(8,2) NOT NULL,
+  `placed_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  CONSTRAINT `orders_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`)
+) ENGINE=InnoDB;
+CREATE TABLE `users` (
+  `id` int NOT NULL AUTO_INCREMENT,
+  `email` varchar(40) NOT NULL,
+  `code` tinyint unsigned NOT NULL,
+  `nickname` varchar(20) DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `email` (`email`),
+  UNIQUE KEY `code` (`code`)
+) ENGINE=InnoDB;
+"#;
+
+    fn generate_sql(table_rows: &[(&str, usize)]) -> ExtractResult<Vec<u8>> {
+        let databases = read_schema(DUMP.as_bytes()).unwrap();
+        let mut generator = Generator::new(
+            GenerateOptions {
+                rows: 5,
+                table_rows: table_rows
+                    .iter()
+                    .map(|(table, rows)| (table.to_string(), *rows))
+                    .collect(),
+                batch_size: 100,
+                seed: Some(7),
+            },
+            None,
+        );
+        let mut out = Vec::new();
+        generator.generate(&databases, &mut out)?;
+        Ok(out)
+    }
+
+    fn generate(table_rows: &[(&str, usize)]) -> ExtractResult<Vec<Insert>> {
+        let out = generate_sql(table_rows)?;
+        Ok(StatementSplitter::new(out.as_slice())
+            .map(|statement| statement.unwrap())
+            .filter_map(|sql| parse_statement::<Insert>(Rule::INSERT_STATEMENT, &sql))
+            .collect())
+    }
+
+    #[test]
+    fn test_generates_parents_before_children() {
+        let inserts = generate(&[("orders", 20)]).unwrap();
+
+        assert_eq!(inserts[0].table_name, "users");
+        assert_eq!(inserts[0].values.len(), 5);
+        assert_eq!(inserts[1].table_name, "orders");
+        assert_eq!(inserts[1].values.len(), 20);
+
+        let user_ids = inserts[0]
+            .values
+            .iter()
+            .map(|row| row.0[0].to_string())
+            .collect::<HashSet<_>>();
+        for row in &inserts[1].values {
+            assert!(user_ids.contains(&row.0[1].to_string()));
+            assert!(
+                matches!(&row.0[2], InsertValue::Text { value } if value == "new" || value == "paid")
+            );
+        }
+    }
+
+    #[test]
+    fn test_respects_types_and_unique_keys() {
+        let inserts = generate(&[("users", 50)]).unwrap();
+        let rows = &inserts[0].values;
+
+        let emails = rows
+            .iter()
+            .map(|row| row.0[1].to_string())
+            .collect::<HashSet<_>>();
+        assert_eq!(emails.len(), 50);
+        for row in rows {
+            assert!(row.0[1].to_string().trim_matches('\'').len() <= 40);
+            assert!(row.0[2].to_string().parse::<u8>().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_fails_when_unique_values_run_out() {
+        // `code` is a unique TINYINT UNSIGNED, which only has 256 values.
+        assert!(generate(&[("users", 300)]).is_err());
+    }
+
+    #[test]
+    fn test_generates_the_same_rows_for_the_same_seed() {
+        // `email` is faked by fakeit, `nickname` drawn from its word list.
+        let first = generate_sql(&[("orders", 20)]).unwrap();
+        assert_eq!(first, generate_sql(&[("orders", 20)]).unwrap());
+    }
+}
@@ -0,0 +1,440 @@
+use clap::ValueEnum;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::dump::DatabaseSchema;
+use crate::parser::parse_utils::unescape_str;
+use crate::parser::statements::CreateTable;
+use crate::parser::types::{Column, DataType, DefaultValue, InsertValue, TableOption};
+use crate::value::Value;
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+const OPENAPI_VERSION: &str = "3.1.0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaFormat {
+    /// A JSON Schema document with one `$defs` entry per table
+    Jsonschema,
+    /// An OpenAPI document with one component schema per table
+    Openapi,
+}
+
+impl SchemaFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonschema => "schema.json",
+            Self::Openapi => "openapi.json",
+        }
+    }
+}
+
+/// A map that serializes its entries in insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap<T>(Vec<(String, T)>);
+
+impl<T: Serialize> Serialize for OrderedMap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// The JSON Schema of a single table's rows.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableSchema {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    properties: OrderedMap<PropertySchema>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    required: Vec<String>,
+    additional_properties: bool,
+}
+
+/// The JSON Schema of a single column.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertySchema {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<serde_json::Value>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<PropertySchema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unique_items: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_only: Option<bool>,
+}
+
+impl From<&CreateTable> for TableSchema {
+    fn from(table: &CreateTable) -> Self {
+        let description = table.options.iter().find_map(|option| match option {
+            TableOption::Comment { value } if !value.is_empty() => Some(unescape_str(value)),
+            _ => None,
+        });
+        // Auto-increment columns are generated by the database, so like columns
+        // with a default they can be left out.
+        let required = table
+            .columns
+            .iter()
+            .filter(|column| !column.nullable && column.default.is_none() && !column.auto_increment)
+            .map(|column| column.name.clone())
+            .collect();
+
+        Self {
+            kind: "object",
+            title: table.name.clone(),
+            description,
+            properties: OrderedMap(
+                table
+                    .columns
+                    .iter()
+                    .map(|column| (column.name.clone(), PropertySchema::from(column)))
+                    .collect(),
+            ),
+            required,
+            additional_properties: false,
+        }
+    }
+}
+
+impl From<&Column> for PropertySchema {
+    fn from(column: &Column) -> Self {
+        let mut schema = Self::for_data_type(&column.data_type);
+
+        if column.nullable {
+            schema.kind = schema.kind.map(|kind| json!([kind, "null"]));
+            if let Some(values) = &mut schema.values {
+                values.push(serde_json::Value::Null);
+            }
+        }
+        schema.description = column
+            .comment
+            .as_deref()
+            .filter(|comment| !comment.is_empty())
+            .map(unescape_str);
+        schema.default = column.default.as_ref().and_then(|default| {
+            let value = match default {
+                DefaultValue::Null => InsertValue::Null,
+                DefaultValue::Text { value } => InsertValue::Text {
+                    value: value.clone(),
+                },
+                DefaultValue::Number { value } => InsertValue::Number {
+                    value: value.clone(),
+                },
+                // Evaluated by the database on insert.
+                DefaultValue::CurrentTimestamp { .. } => return None,
+            };
+            serde_json::to_value(Value::from_insert(&value, Some(&column.data_type))).ok()
+        });
+        if column.auto_increment {
+            schema.read_only = Some(true);
+        }
+
+        schema
+    }
+}
+
+impl PropertySchema {
+    fn typed(kind: &str) -> Self {
+        Self {
+            kind: Some(json!(kind)),
+            ..Default::default()
+        }
+    }
+
+    fn integer(bits: u32, unsigned: bool) -> Self {
+        let (minimum, maximum) = match (bits, unsigned) {
+            (bits, true) => (json!(0), json!(u64::MAX >> (64 - bits))),
+            (bits, false) => (
+                json!(i64::MIN >> (64 - bits)),
+                json!(i64::MAX >> (64 - bits)),
+            ),
+        };
+        Self {
+            minimum: Some(minimum),
+            maximum: Some(maximum),
+            ..Self::typed("integer")
+        }
+    }
+
+    fn string(max_length: Option<u64>) -> Self {
+        Self {
+            max_length,
+            ..Self::typed("string")
+        }
+    }
+
+    fn formatted(format: &'static str) -> Self {
+        Self {
+            format: Some(format),
+            ..Self::typed("string")
+        }
+    }
+
+    fn patterned(pattern: String) -> Self {
+        Self {
+            pattern: Some(pattern),
+            ..Self::typed("string")
+        }
+    }
+
+    fn for_data_type(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::TinyInt { unsigned, .. } => Self::integer(8, *unsigned),
+            DataType::SmallInt { unsigned, .. } => Self::integer(16, *unsigned),
+            DataType::MediumInt { unsigned, .. } => Self::integer(24, *unsigned),
+            DataType::Int { unsigned, .. } => Self::integer(32, *unsigned),
+            DataType::BigInt { unsigned, .. } => Self::integer(64, *unsigned),
+            DataType::Bit { m } => Self::integer(m.unwrap_or(1).clamp(1, 64), true),
+            DataType::Year { .. } => Self {
+                minimum: Some(json!(1901)),
+                maximum: Some(json!(2155)),
+                ..Self::typed("integer")
+            },
+            // Exported as text so no precision is lost.
+            DataType::Decimal { m, d, unsigned, .. } => {
+                let scale = d.unwrap_or(0);
+                let digits = m.unwrap_or(10).saturating_sub(scale);
+                let sign = if *unsigned { "" } else { "-?" };
+                let fraction = if scale > 0 {
+                    format!("(\\.\\d{{1,{scale}}})?")
+                } else {
+                    String::new()
+                };
+                Self {
+                    pattern: Some(format!("^{sign}\\d{{1,{}}}{fraction}$", digits.max(1))),
+                    ..Self::typed("string")
+                }
+            }
+            DataType::Float { unsigned, .. } | DataType::Double { unsigned, .. } => Self {
+                minimum: unsigned.then(|| json!(0)),
+                ..Self::typed("number")
+            },
+            DataType::Date => Self::formatted("date"),
+            // Not the RFC 3339 `date-time`/`time` formats, which require a UTC
+            // offset. Both `2024-01-31 12:00:00` and the ISO form of NDJSON
+            // exports match.
+            DataType::DateTime { fsp } | DataType::Timestamp { fsp } => Self::patterned(format!(
+                "^\\d{{4}}-\\d{{2}}-\\d{{2}}[T ]\\d{{2}}:\\d{{2}}:\\d{{2}}{}$",
+                fraction(*fsp)
+            )),
+            DataType::Time { fsp } => Self::patterned(format!(
+                "^-?\\d{{2,3}}:\\d{{2}}:\\d{{2}}{}$",
+                fraction(*fsp)
+            )),
+            DataType::Char { m, .. } => Self::string(Some(m.unwrap_or(1) as u64)),
+            DataType::Varchar { m, .. } => Self::string(m.map(u64::from)),
+            DataType::TinyText { .. } => Self::string(Some(255)),
+            DataType::Text { m, .. } => Self::string(Some(m.map_or(65_535, u64::from))),
+            DataType::MediumText { .. } => Self::string(Some(16_777_215)),
+            DataType::LongText { .. } => Self::string(Some(4_294_967_295)),
+            DataType::Enum { values, .. } => Self {
+                values: Some(
+                    values
+                        .iter()
+                        .map(|value| json!(unescape_str(value)))
+                        .collect(),
+                ),
+                ..Self::typed("string")
+            },
+            DataType::Set { values, .. } => Self {
+                items: Some(Box::new(Self {
+                    values: Some(
+                        values
+                            .iter()
+                            .map(|value| json!(unescape_str(value)))
+                            .collect(),
+                    ),
+                    ..Self::typed("string")
+                })),
+                unique_items: Some(true),
+                ..Self::typed("array")
+            },
+            // Any JSON value is allowed.
+            DataType::Json => Self::default(),
+            DataType::Binary { .. }
+            | DataType::Varbinary { .. }
+            | DataType::Blob { .. }
+            | DataType::TinyBlob
+            | DataType::MediumBlob
+            | DataType::LongBlob => Self::typed("string"),
+        }
+    }
+}
+
+/// Optional fractional seconds of up to `fsp` digits.
+fn fraction(fsp: Option<u32>) -> String {
+    match fsp {
+        Some(fsp @ 1..=6) => format!("(\\.\\d{{1,{fsp}}})?"),
+        _ => String::new(),
+    }
+}
+
+/// Component/definition names may only contain letters, digits, `.`, `-` and `_`.
+fn definition_name(table: &str) -> String {
+    table
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// One document describing every table of a database.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum SchemaDocument {
+    JsonSchema {
+        #[serde(rename = "$schema")]
+        dialect: &'static str,
+        title: String,
+        #[serde(rename = "$defs")]
+        defs: OrderedMap<TableSchema>,
+    },
+    OpenApi {
+        openapi: &'static str,
+        info: ApiInfo,
+        components: Components,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiInfo {
+    title: String,
+    version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Components {
+    schemas: OrderedMap<TableSchema>,
+}
+
+impl SchemaDocument {
+    pub fn new(database: &DatabaseSchema, format: SchemaFormat) -> Self {
+        let title = database
+            .name
+            .clone()
+            .unwrap_or_else(|| String::from("default"));
+        let schemas = OrderedMap(
+            database
+                .tables
+                .iter()
+                .map(|table| (definition_name(&table.name), TableSchema::from(table)))
+                .collect(),
+        );
+
+        match format {
+            SchemaFormat::Jsonschema => Self::JsonSchema {
+                dialect: JSON_SCHEMA_DIALECT,
+                title,
+                defs: schemas,
+            },
+            SchemaFormat::Openapi => Self::OpenApi {
+                openapi: OPENAPI_VERSION,
+                info: ApiInfo {
+                    title,
+                    version: "1.0.0",
+                },
+                components: Components { schemas },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::read_schema;
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `users` (
+  `id` int unsigned NOT NULL AUTO_INCREMENT,
+  `email` varchar(255) NOT NULL COMMENT 'Login address',
+  `status` enum('active','banned') DEFAULT 'active',
+  `age` tinyint NOT NULL DEFAULT '18',
+  `balance` decimal(10,2) NOT NULL,
+  `born_on` date DEFAULT NULL,
+  `created_at` datetime(3) NOT NULL
+) ENGINE=InnoDB COMMENT='Registered users';
+"#;
+
+    fn table_schema() -> TableSchema {
+        let databases = read_schema(DUMP.as_bytes()).unwrap();
+        TableSchema::from(&databases[0].tables[0])
+    }
+
+    #[test]
+    fn test_table_schema() {
+        assert_eq!(
+            serde_json::to_value(table_schema()).unwrap(),
+            json!({
+                "type": "object",
+                "title": "users",
+                "description": "Registered users",
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0, "maximum": 4294967295u64, "readOnly": true },
+                    "email": { "type": "string", "description": "Login address", "maxLength": 255 },
+                    "status": { "type": ["string", "null"], "enum": ["active", "banned", null], "default": "active" },
+                    "age": { "type": "integer", "minimum": -128, "maximum": 127, "default": 18 },
+                    "balance": { "type": "string", "pattern": "^-?\\d{1,8}(\\.\\d{1,2})?$" },
+                    "born_on": { "type": ["string", "null"], "format": "date", "default": null },
+                    "created_at": { "type": "string", "pattern": "^\\d{4}-\\d{2}-\\d{2}[T ]\\d{2}:\\d{2}:\\d{2}(\\.\\d{1,3})?$" },
+                },
+                "required": ["email", "balance", "created_at"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_properties_keep_column_order() {
+        let json = serde_json::to_string(&table_schema()).unwrap();
+        let position = |key: &str| json.find(&format!("\"{key}\"")).unwrap();
+
+        assert!(position("id") < position("email"));
+        assert!(position("balance") < position("born_on"));
+    }
+
+    #[test]
+    fn test_openapi_document() {
+        let databases = read_schema(DUMP.as_bytes()).unwrap();
+        let document =
+            serde_json::to_value(SchemaDocument::new(&databases[0], SchemaFormat::Openapi))
+                .unwrap();
+
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["info"]["title"], "shop");
+        assert_eq!(
+            document["components"]["schemas"]["users"]["required"],
+            json!(["email", "balance", "created_at"])
+        );
+    }
+}
@@ -1,12 +1,27 @@
 pub mod cmd;
+pub mod codegen;
+pub mod compression;
+pub mod docs;
+pub mod dump;
+pub mod erd;
+pub mod export;
+pub mod generate;
+pub mod json_schema;
+pub mod locale;
+pub mod masker;
 pub mod parser;
+pub mod profile;
+pub mod rewrite;
 pub mod rules;
 pub mod settings;
 pub mod sqlparse;
 pub mod types;
-pub mod masker;
+pub mod value;
 
 use anyhow::Result;
 pub type ExtractResult<T = ()> = Result<T>;
 
-pub use sqlparse::simple_parse;
+pub use dump::{DumpReader, DumpStatement};
+pub use parser::statements::Statement;
+pub use rewrite::{Pipeline, StatementRewriter, StatementVisitor};
+pub use sqlparse::{simple_parse, simple_parse_reader};
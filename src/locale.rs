@@ -0,0 +1,343 @@
+//! Locale-specific fakes, so masked rows look right for their country.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// The fakeit generators.
+    #[default]
+    EnUs,
+    DeDe,
+    JaJp,
+    PtBr,
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            Self::EnUs => "en_US",
+            Self::DeDe => "de_DE",
+            Self::JaJp => "ja_JP",
+            Self::PtBr => "pt_BR",
+        })
+    }
+}
+
+impl Locale {
+    /// Parse a locale (`de_DE`, `de-DE`, `de`), or a country code or English
+    /// country name as found in a country column (`DE`, `Germany`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase().replace('-', "_");
+        match name.as_str() {
+            "en_us" | "en" | "us" | "usa" | "united states" => Some(Self::EnUs),
+            "de_de" | "de" | "deu" | "germany" | "deutschland" => Some(Self::DeDe),
+            "ja_jp" | "ja" | "jp" | "jpn" | "japan" => Some(Self::JaJp),
+            "pt_br" | "pt" | "br" | "bra" | "brazil" | "brasil" => Some(Self::PtBr),
+            _ => None,
+        }
+    }
+
+    /// A fake for the faker registered as `name`, drawn from `rng`, or `None`
+    /// when the locale has no generator of its own for it.
+    pub fn fake(&self, name: &str, rng: &mut dyn RngCore) -> Option<String> {
+        let data = self.data()?;
+        Some(match name {
+            "first" => pick(data.first_names, rng),
+            "last" => pick(data.last_names, rng),
+            "full" => match self {
+                Self::JaJp => format!(
+                    "{} {}",
+                    pick(data.last_names, rng),
+                    pick(data.first_names, rng)
+                ),
+                _ => format!(
+                    "{} {}",
+                    pick(data.first_names, rng),
+                    pick(data.last_names, rng)
+                ),
+            },
+            "phone" => digits(&pick(data.phone_formats, rng), rng),
+            "zip" => digits(data.zip_format, rng),
+            "city" => pick(data.cities, rng),
+            "street" => match self {
+                Self::DeDe => format!("{} {}", pick(data.streets, rng), rng.gen_range(1..200)),
+                Self::JaJp => format!(
+                    "{}{}-{}-{}",
+                    pick(data.streets, rng),
+                    rng.gen_range(1..10),
+                    rng.gen_range(1..30),
+                    rng.gen_range(1..20)
+                ),
+                _ => format!("{}, {}", pick(data.streets, rng), rng.gen_range(1..3000)),
+            },
+            "nationalid" => match self {
+                // Steuerliche Identifikationsnummer.
+                Self::DeDe => format!("{}{}", rng.gen_range(1..10), digits("##########", rng)),
+                // My Number.
+                Self::JaJp => digits("############", rng),
+                Self::PtBr => cpf(rng),
+                Self::EnUs => return None,
+            },
+            _ => return None,
+        })
+    }
+
+    fn data(&self) -> Option<&'static LocaleData> {
+        match self {
+            Self::EnUs => None,
+            Self::DeDe => Some(&DE_DE),
+            Self::JaJp => Some(&JA_JP),
+            Self::PtBr => Some(&PT_BR),
+        }
+    }
+}
+
+struct LocaleData {
+    first_names: &'static [&'static str],
+    last_names: &'static [&'static str],
+    /// `#` stands for a random digit.
+    phone_formats: &'static [&'static str],
+    zip_format: &'static str,
+    cities: &'static [&'static str],
+    streets: &'static [&'static str],
+}
+
+fn pick(values: &[&str], rng: &mut dyn RngCore) -> String {
+    values.choose(rng).unwrap().to_string()
+}
+
+/// Replace every `#` of `format` with a random digit.
+fn digits(format: &str, rng: &mut dyn RngCore) -> String {
+    format
+        .chars()
+        .map(|c| match c {
+            '#' => char::from(b'0' + rng.gen_range(0..10)),
+            c => c,
+        })
+        .collect()
+}
+
+/// A CPF, the Brazilian taxpayer number, with valid check digits.
+fn cpf(rng: &mut dyn RngCore) -> String {
+    let mut numbers = (0..9).map(|_| rng.gen_range(0..10)).collect::<Vec<u32>>();
+    for _ in 0..2 {
+        let weights = (2..=numbers.len() as u32 + 1).rev();
+        let sum = numbers.iter().zip(weights).map(|(n, w)| n * w).sum::<u32>();
+        numbers.push(match sum % 11 {
+            0 | 1 => 0,
+            rest => 11 - rest,
+        });
+    }
+    let n = numbers.iter().map(u32::to_string).collect::<String>();
+    format!("{}.{}.{}-{}", &n[..3], &n[3..6], &n[6..9], &n[9..])
+}
+
+static DE_DE: LocaleData = LocaleData {
+    first_names: &[
+        "Anna",
+        "Lena",
+        "Marie",
+        "Sophie",
+        "Hannah",
+        "Lukas",
+        "Jonas",
+        "Felix",
+        "Maximilian",
+        "Paul",
+        "Katharina",
+        "Jürgen",
+        "Sabine",
+        "Stefan",
+        "Ursula",
+    ],
+    last_names: &[
+        "Müller",
+        "Schmidt",
+        "Schneider",
+        "Fischer",
+        "Weber",
+        "Meyer",
+        "Wagner",
+        "Becker",
+        "Schulz",
+        "Hoffmann",
+        "Schäfer",
+        "Koch",
+        "Bauer",
+        "Richter",
+        "Klein",
+    ],
+    phone_formats: &["+49 30 ########", "+49 89 #######", "+49 15# #######"],
+    zip_format: "#####",
+    cities: &[
+        "Berlin",
+        "Hamburg",
+        "München",
+        "Köln",
+        "Frankfurt am Main",
+        "Stuttgart",
+        "Düsseldorf",
+        "Leipzig",
+        "Dresden",
+        "Nürnberg",
+    ],
+    streets: &[
+        "Hauptstraße",
+        "Bahnhofstraße",
+        "Gartenstraße",
+        "Schulstraße",
+        "Dorfstraße",
+        "Bergstraße",
+        "Lindenstraße",
+        "Kirchweg",
+        "Am Markt",
+        "Goethestraße",
+    ],
+};
+
+static JA_JP: LocaleData = LocaleData {
+    first_names: &[
+        "陽翔",
+        "蓮",
+        "湊",
+        "大和",
+        "悠真",
+        "陽葵",
+        "凛",
+        "結菜",
+        "葵",
+        "さくら",
+        "花子",
+        "太郎",
+    ],
+    last_names: &[
+        "佐藤", "鈴木", "高橋", "田中", "伊藤", "渡辺", "山本", "中村", "小林", "加藤", "吉田",
+        "山田",
+    ],
+    phone_formats: &[
+        "03-####-####",
+        "06-####-####",
+        "090-####-####",
+        "080-####-####",
+    ],
+    zip_format: "###-####",
+    cities: &[
+        "東京都",
+        "横浜市",
+        "大阪市",
+        "名古屋市",
+        "札幌市",
+        "福岡市",
+        "神戸市",
+        "京都市",
+        "川崎市",
+        "仙台市",
+    ],
+    streets: &[
+        "千代田区丸の内",
+        "渋谷区神南",
+        "新宿区西新宿",
+        "港区六本木",
+        "中央区銀座",
+        "北区梅田",
+        "中区栄",
+        "博多区博多駅前",
+    ],
+};
+
+static PT_BR: LocaleData = LocaleData {
+    first_names: &[
+        "Ana", "Maria", "Juliana", "Beatriz", "Larissa", "João", "Pedro", "Lucas", "Gabriel",
+        "Rafael", "Mateus", "Camila", "Thiago", "Fernanda", "Gustavo",
+    ],
+    last_names: &[
+        "Silva",
+        "Santos",
+        "Oliveira",
+        "Souza",
+        "Rodrigues",
+        "Ferreira",
+        "Alves",
+        "Pereira",
+        "Lima",
+        "Gomes",
+        "Costa",
+        "Ribeiro",
+        "Martins",
+        "Carvalho",
+        "Araújo",
+    ],
+    phone_formats: &["+55 11 9####-####", "+55 21 9####-####", "+55 31 ####-####"],
+    zip_format: "#####-###",
+    cities: &[
+        "São Paulo",
+        "Rio de Janeiro",
+        "Belo Horizonte",
+        "Salvador",
+        "Brasília",
+        "Curitiba",
+        "Fortaleza",
+        "Recife",
+        "Porto Alegre",
+        "Manaus",
+    ],
+    streets: &[
+        "Rua das Flores",
+        "Avenida Paulista",
+        "Rua XV de Novembro",
+        "Avenida Brasil",
+        "Rua São João",
+        "Rua Sete de Setembro",
+        "Avenida Atlântica",
+        "Rua da Consolação",
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn test_parses_locales_and_countries() {
+        assert_eq!(Locale::from_name("de-DE"), Some(Locale::DeDe));
+        assert_eq!(Locale::from_name(" Japan "), Some(Locale::JaJp));
+        assert_eq!(Locale::from_name("BR"), Some(Locale::PtBr));
+        assert_eq!(Locale::from_name("fr"), None);
+        assert_eq!(Locale::PtBr.to_string(), "pt_BR");
+    }
+
+    #[test]
+    fn test_fakes_for_the_locale() {
+        let rng = &mut rand::thread_rng();
+        for (locale, zip, phone) in [
+            (Locale::DeDe, r"^\d{5}$", r"^\+49 \d{2,3} \d{7,8}$"),
+            (Locale::JaJp, r"^\d{3}-\d{4}$", r"^0\d{1,2}-\d{4}-\d{4}$"),
+            (
+                Locale::PtBr,
+                r"^\d{5}-\d{3}$",
+                r"^\+55 \d{2} 9?\d{4}-\d{4}$",
+            ),
+        ] {
+            assert!(Regex::new(zip)
+                .unwrap()
+                .is_match(&locale.fake("zip", rng).unwrap()));
+            assert!(Regex::new(phone)
+                .unwrap()
+                .is_match(&locale.fake("phone", rng).unwrap()));
+            assert!(locale.fake("city", rng).is_some());
+            assert!(locale.fake("uuidv4", rng).is_none());
+        }
+        assert!(Locale::DeDe
+            .fake("street", rng)
+            .unwrap()
+            .ends_with(|c: char| c.is_ascii_digit()));
+        assert_eq!(Locale::EnUs.fake("first", rng), None);
+        assert!(Regex::new(r"^\d{3}\.\d{3}\.\d{3}-\d{2}$")
+            .unwrap()
+            .is_match(&Locale::PtBr.fake("nationalid", rng).unwrap()));
+    }
+}
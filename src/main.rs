@@ -1,5 +1,14 @@
 use sqlex::cmd::{self};
 
 fn main() {
-    cmd::exec();
+    if let Err(err) = cmd::exec() {
+        // The reader on the other end of a pipe went away (e.g. `| head`).
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            if io_err.kind() == std::io::ErrorKind::BrokenPipe {
+                return;
+            }
+        }
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
 }
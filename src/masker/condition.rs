@@ -0,0 +1,283 @@
+//! Conditions such as `country = 'DE'` or `email NOT LIKE '%@ourcompany.com'`
+//! that limit a masking rule to some rows. They are parsed with the SQL
+//! grammar's `WHERE` clause and checked against the row's original values.
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use anyhow::{anyhow, bail};
+use pest::iterators::Pair;
+use pest::Parser;
+use regex::Regex;
+
+use crate::masker::Row;
+use crate::parser::parse_utils::{literal_contents, unescape_str};
+use crate::parser::types::Where;
+use crate::parser::{MySqlParser, Rule};
+use crate::value::Value;
+use crate::ExtractResult;
+
+#[derive(Debug)]
+enum Operator {
+    Equals,
+    NotEquals,
+    LessThan,
+    GreaterThan,
+    LessThanEquals,
+    GreaterThanEquals,
+    Like(Regex),
+    NotLike(Regex),
+    In,
+    IsNull,
+    IsNotNull,
+}
+
+#[derive(Debug)]
+enum Operand {
+    Literal(Value),
+    Column(String),
+}
+
+#[derive(Debug)]
+struct Comparison {
+    column: String,
+    operator: Operator,
+    operands: Vec<Operand>,
+}
+
+/// Comparisons joined with `AND` and `OR`, `AND` binding tighter.
+#[derive(Debug)]
+pub struct Condition {
+    source: String,
+    /// Alternatives, each true when all of its comparisons are.
+    any: Vec<Vec<Comparison>>,
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.source)
+    }
+}
+
+impl Condition {
+    pub fn parse(source: &str) -> ExtractResult<Self> {
+        let source = source.trim();
+        let clause = format!("WHERE {source}");
+        let pair = MySqlParser::parse(Rule::WHERE_CLAUSE, &clause)
+            .map_err(|err| anyhow!("invalid condition `{source}`:\n{err}"))?
+            .next()
+            .expect("WHERE_CLAUSE");
+        if pair.as_str().len() != clause.len() {
+            bail!(
+                "invalid condition `{source}`: unexpected `{}`",
+                &clause[pair.as_str().len()..].trim()
+            );
+        }
+
+        let mut any = vec![Vec::new()];
+        for condition in pair.into_inner() {
+            collect(condition, &mut any)
+                .map_err(|err| anyhow!("invalid condition `{source}`: {err}"))?;
+        }
+        Ok(Self {
+            source: source.to_string(),
+            any,
+        })
+    }
+
+    /// Whether the original values of `row` satisfy the condition. As in SQL,
+    /// comparisons with `NULL` are false.
+    pub fn matches(&self, row: &mut dyn Row) -> bool {
+        self.any
+            .iter()
+            .any(|all| all.iter().all(|comparison| comparison.matches(row)))
+    }
+}
+
+/// Add the comparisons of a `CONDITION` to the alternatives of `any`.
+fn collect(pair: Pair<'_, Rule>, any: &mut Vec<Vec<Comparison>>) -> ExtractResult<()> {
+    let comparison = Comparison::from_condition(pair.clone())?;
+    any.last_mut().expect("an alternative").push(comparison);
+    let mut rest = pair
+        .into_inner()
+        .skip_while(|pair| pair.as_rule() != Rule::LOGICAL_OPERATOR);
+    while let (Some(logical), Some(condition)) = (rest.next(), rest.next()) {
+        if logical.as_str() == "OR" {
+            any.push(Vec::new());
+        }
+        collect(condition, any)?;
+    }
+    Ok(())
+}
+
+impl Comparison {
+    fn from_condition(pair: Pair<'_, Rule>) -> ExtractResult<Self> {
+        let clause = Where::from_condition(pair.clone());
+        let operands = match pair.into_inner().nth(2) {
+            Some(value) if value.as_rule() == Rule::VALUE_LIST => {
+                value.into_inner().map(operand).collect()
+            }
+            Some(value) if value.as_rule() != Rule::LOGICAL_OPERATOR => vec![operand(value)],
+            _ => Vec::new(),
+        };
+        let like = || match operands.as_slice() {
+            [Operand::Literal(Value::Text(pattern))] => Ok(like_regex(pattern)),
+            _ => Err(anyhow!("`{clause}` needs a string pattern")),
+        };
+        let operator = match clause
+            .operator
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .as_str()
+        {
+            "=" => Operator::Equals,
+            "!=" | "<>" => Operator::NotEquals,
+            "<" => Operator::LessThan,
+            ">" => Operator::GreaterThan,
+            "<=" => Operator::LessThanEquals,
+            ">=" => Operator::GreaterThanEquals,
+            "LIKE" => Operator::Like(like()?),
+            "NOT LIKE" => Operator::NotLike(like()?),
+            "IN" => Operator::In,
+            "IS NULL" => Operator::IsNull,
+            "IS NOT NULL" => Operator::IsNotNull,
+            other => bail!("unsupported operator `{other}`"),
+        };
+
+        Ok(Self {
+            column: clause.column.trim_matches('`').to_string(),
+            operator,
+            operands,
+        })
+    }
+
+    fn matches(&self, row: &mut dyn Row) -> bool {
+        let value = row.original(&self.column);
+        let operands = self
+            .operands
+            .iter()
+            .map(|operand| match operand {
+                Operand::Literal(value) => value.clone(),
+                Operand::Column(column) => row.original(column),
+            })
+            .collect::<Vec<_>>();
+        let first =
+            |ordering: fn(Ordering) -> bool| compare(&value, &operands[0]).is_some_and(ordering);
+        match &self.operator {
+            Operator::Equals => first(Ordering::is_eq),
+            Operator::NotEquals => first(Ordering::is_ne),
+            Operator::LessThan => first(Ordering::is_lt),
+            Operator::GreaterThan => first(Ordering::is_gt),
+            Operator::LessThanEquals => first(Ordering::is_le),
+            Operator::GreaterThanEquals => first(Ordering::is_ge),
+            Operator::Like(regex) => value.to_text().is_some_and(|text| regex.is_match(&text)),
+            Operator::NotLike(regex) => value.to_text().is_some_and(|text| !regex.is_match(&text)),
+            Operator::In => operands
+                .iter()
+                .any(|operand| compare(&value, operand).is_some_and(Ordering::is_eq)),
+            Operator::IsNull => value.is_null(),
+            Operator::IsNotNull => !value.is_null(),
+        }
+    }
+}
+
+fn operand(pair: Pair<'_, Rule>) -> Operand {
+    match pair.as_rule() {
+        Rule::STRING_LITERAL => {
+            Operand::Literal(Value::Text(unescape_str(literal_contents(pair.as_str()))))
+        }
+        Rule::NUMBER => Operand::Literal(Value::Decimal(pair.as_str().to_string())),
+        Rule::NULL => Operand::Literal(Value::Null),
+        _ => match pair.as_str().to_ascii_lowercase().as_str() {
+            "true" => Operand::Literal(Value::Bool(true)),
+            "false" => Operand::Literal(Value::Bool(false)),
+            _ => Operand::Column(pair.as_str().to_string()),
+        },
+    }
+}
+
+/// Numbers compare as numbers, other values as text.
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (number(left), number(right)) {
+        (Some(left), Some(right)) => left.partial_cmp(&right),
+        _ => Some(left.to_text()?.cmp(&right.to_text()?)),
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Bool(value) => Some(f64::from(u8::from(*value))),
+        Value::Int(value) => Some(*value as f64),
+        Value::UInt(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        Value::Decimal(value) | Value::Text(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// A `LIKE` pattern as a regex: `%` matches any text, `_` one character and
+/// `\` escapes them. Case is ignored, as with MySQL's default collations.
+fn like_regex(pattern: &str) -> Regex {
+    let mut regex = String::from("(?is)^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' => regex.push_str(&regex::escape(&chars.next().unwrap_or('\\').to_string())),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).expect("escaped pattern")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::masker::ValuesRow;
+
+    #[test]
+    fn test_matches_rows() {
+        let columns = ["email", "country", "age", "deleted_at"].map(String::from);
+        let row = |email: &str, country: &str, age: &str| {
+            [
+                Value::Text(email.to_string()).to_insert(),
+                Value::Text(country.to_string()).to_insert(),
+                Value::Decimal(age.to_string()).to_insert(),
+                Value::Null.to_insert(),
+            ]
+        };
+        let matches = |condition: &str, values: &[_]| {
+            Condition::parse(condition)
+                .unwrap()
+                .matches(&mut ValuesRow {
+                    columns: &columns,
+                    values,
+                    index: 0,
+                    config: None,
+                    table: None,
+                })
+        };
+
+        let internal = row("qa@OurCompany.com", "DE", "30");
+        let customer = row("ann@corp.com", "US", "9");
+        assert!(matches("email LIKE '%@ourcompany.com'", &internal));
+        assert!(matches("email NOT LIKE '%@ourcompany.com'", &customer));
+        assert!(!matches("email NOT LIKE '%@ourcompany.com'", &internal));
+        assert!(matches("`country` = 'DE' AND age >= 18", &internal));
+        assert!(!matches("country = 'DE' AND age >= 18", &customer));
+        assert!(matches("country = 'DE' OR age < 10", &customer));
+        assert!(matches(
+            "country IN ('AT', 'US') AND deleted_at IS NULL",
+            &customer
+        ));
+        assert!(!matches("deleted_at = NULL", &customer));
+        assert!(!matches("age > 10", &customer));
+
+        assert!(Condition::parse("country = 'DE' extra").is_err());
+        assert!(Condition::parse("email LIKE 3").is_err());
+        assert!(Condition::parse("country").is_err());
+    }
+}
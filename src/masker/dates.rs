@@ -0,0 +1,171 @@
+//! Date shifting: every date of an entity moves by the same number of days,
+//! so intervals between them survive masking.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+use crate::parser::types::DataType;
+
+lazy_static! {
+    static ref DATE: Regex = Regex::new(r"^(\d{4})-(\d{2})-(\d{2})(.*)$").unwrap();
+}
+
+/// 1000-01-01 and 9999-12-31, the range of `DATE` and `DATETIME`.
+const DATE_RANGE: (i64, i64) = (-354_285, 2_932_896);
+/// The range of `TIMESTAMP` (1970-01-01 00:00:01 to 2038-01-19 03:14:07 UTC),
+/// without its first and last day so any time of day fits.
+const TIMESTAMP_RANGE: (i64, i64) = (1, 24_854);
+
+pub struct DateShift {
+    max_days: u32,
+    key: [u8; 32],
+}
+
+impl Debug for DateShift {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("DateShift")
+            .field("max_days", &self.max_days)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DateShift {
+    /// Offsets are derived from `secret`, so they cannot be recomputed from the dump.
+    pub fn new(max_days: u32, secret: &str) -> Self {
+        Self {
+            max_days,
+            key: Sha256::digest(secret.as_bytes()).into(),
+        }
+    }
+
+    /// The offset in days of `entity`, between `-max_days` and `max_days` but never 0.
+    pub fn offset(&self, entity: &str) -> i64 {
+        if self.max_days == 0 {
+            return 0;
+        }
+        let digest = Sha256::new()
+            .chain_update(self.key)
+            .chain_update(entity.as_bytes())
+            .finalize();
+        let bits = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        let days = (bits >> 1) % u64::from(self.max_days) + 1;
+        if bits & 1 == 0 {
+            days as i64
+        } else {
+            -(days as i64)
+        }
+    }
+
+    /// Shift a `YYYY-MM-DD[ time]` value by the offset of `entity`, keeping the
+    /// time of day and fractional seconds as they are and staying within the
+    /// range of `data_type`. Other values, such as zero dates, give `None`.
+    pub fn shift(&self, value: &str, entity: &str, data_type: Option<&DataType>) -> Option<String> {
+        let captures = DATE.captures(value)?;
+        let year = captures[1].parse::<i64>().ok()?;
+        let month = captures[2].parse::<u32>().ok()?;
+        let day = captures[3].parse::<u32>().ok()?;
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let (min, max) = match data_type {
+            Some(DataType::Timestamp { .. }) => TIMESTAMP_RANGE,
+            _ => DATE_RANGE,
+        };
+        let days = (days_from_civil(year, month, day) + self.offset(entity)).clamp(min, max);
+        let (year, month, day) = civil_from_days(days);
+        Some(format!("{year:04}-{month:02}-{day:02}{}", &captures[4]))
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let mp = i64::from((month + 9) % 12);
+    let day_of_year = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The proleptic Gregorian date `days` days after 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(1000, 1, 1), DATE_RANGE.0);
+        assert_eq!(days_from_civil(9999, 12, 31), DATE_RANGE.1);
+    }
+
+    #[test]
+    fn test_shifts_dates_of_an_entity_together() {
+        let shift = DateShift::new(365, "secret");
+        let offset = shift.offset("42");
+        assert!(offset != 0 && offset.abs() <= 365);
+        assert_eq!(offset, DateShift::new(365, "secret").offset("42"));
+
+        let signup = shift
+            .shift("2024-01-31", "42", Some(&DataType::Date))
+            .unwrap();
+        let order = shift
+            .shift(
+                "2024-03-01 10:20:30.125",
+                "42",
+                Some(&DataType::DateTime { fsp: Some(3) }),
+            )
+            .unwrap();
+        assert!(order.ends_with(" 10:20:30.125"));
+        let days = |date: &str| {
+            days_from_civil(
+                date[..4].parse().unwrap(),
+                date[5..7].parse().unwrap(),
+                date[8..10].parse().unwrap(),
+            )
+        };
+        assert_eq!(days(&order) - days(&signup), 30);
+        assert_eq!(days(&signup) - days("2024-01-31"), offset);
+
+        let far = DateShift::new(100_000, "secret");
+        let timestamp = far
+            .shift(
+                "2038-01-01 00:00:00",
+                "7",
+                Some(&DataType::Timestamp { fsp: None }),
+            )
+            .unwrap();
+        assert!(("1970-01-02".."2038-01-19").contains(&timestamp.as_str()));
+        assert_eq!(shift.shift("0000-00-00", "42", None), None);
+    }
+}
@@ -0,0 +1,763 @@
+//! Expressions used as masking rules, such as
+//! `lower(first_name) || '.' || lower(last_name) || '@example.test'`.
+//!
+//! An expression is evaluated once per row. Bare identifiers refer to the
+//! original values of the row's columns (`value` is the column being masked),
+//! `masked.<column>` to the value a column has after masking, and
+//! `domain::name()` calls the faker registered as `name`, in the row's
+//! locale or the one given as in `name::first('de_DE')`.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult, Write};
+
+use anyhow::{anyhow, bail};
+use hmac::{Hmac, Mac};
+use pest::iterators::Pair;
+use pest::Parser;
+use pest_derive::Parser;
+use sha2::{Digest, Sha256};
+
+use crate::locale::Locale;
+use crate::masker::fpe::FpeFormat;
+use crate::masker::numeric;
+use crate::parser::parse_utils::{literal_contents, unescape_str};
+use crate::parser::statements::CreateTable;
+use crate::parser::types::{Column, InsertValue};
+use crate::rules::{find_struct_by_name, FromStrFaking};
+use crate::settings::MaskingConfig;
+use crate::value::Value;
+use crate::ExtractResult;
+
+#[derive(Parser)]
+#[grammar = "masker/expr.pest"]
+struct ExpressionParser;
+
+/// The row an expression is evaluated against.
+pub trait Row {
+    /// The original value of the column being masked.
+    fn current(&mut self) -> Value;
+    /// The original value of `column`, or `NULL` when the row has no such column.
+    fn original(&mut self, column: &str) -> Value;
+    /// The value of `column` after masking.
+    fn masked(&mut self, column: &str) -> Value;
+    /// The config whose ciphers are used by `fpe()`, which fails without one,
+    /// and `shift_date()`, which keeps the value.
+    fn config(&self) -> Option<&MaskingConfig> {
+        None
+    }
+    /// The definition of the column being masked, if its table is known.
+    fn column(&self) -> Option<&Column> {
+        None
+    }
+    /// The locale of fakers that do not name one: the row's value of the
+    /// config's `locale.column` when it is a known country or locale, or
+    /// else the config's default locale.
+    fn locale(&mut self) -> Locale {
+        let Some(config) = self.config() else {
+            return Locale::default();
+        };
+        let default = config.default_locale();
+        let Some(column) = config
+            .locale
+            .as_ref()
+            .and_then(|locale| locale.column.clone())
+        else {
+            return default;
+        };
+        self.original(&column)
+            .to_text()
+            .and_then(|country| Locale::from_name(&country))
+            .unwrap_or(default)
+    }
+}
+
+/// A row of values addressed by column name, for rows that are not masked
+/// (`masked.<column>` returns the same values as `<column>`).
+pub struct ValuesRow<'a> {
+    pub columns: &'a [String],
+    pub values: &'a [InsertValue],
+    /// Position of the column being evaluated.
+    pub index: usize,
+    pub config: Option<&'a MaskingConfig>,
+    pub table: Option<&'a CreateTable>,
+}
+
+impl Row for ValuesRow<'_> {
+    fn current(&mut self) -> Value {
+        self.values
+            .get(self.index)
+            .map_or(Value::Null, |value| Value::from_insert(value, None))
+    }
+
+    fn original(&mut self, column: &str) -> Value {
+        self.columns
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(column))
+            .and_then(|i| self.values.get(i))
+            .map_or(Value::Null, |value| Value::from_insert(value, None))
+    }
+
+    fn masked(&mut self, column: &str) -> Value {
+        self.original(column)
+    }
+
+    fn config(&self) -> Option<&MaskingConfig> {
+        self.config
+    }
+
+    fn column(&self) -> Option<&Column> {
+        let name = self.columns.get(self.index)?;
+        self.table?
+            .columns
+            .iter()
+            .find(|column| column.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A parsed masking expression.
+pub struct Expression {
+    source: String,
+    root: Expr,
+}
+
+impl Debug for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Expression({:?})", self.source)
+    }
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> ExtractResult<Self> {
+        let mut pairs = ExpressionParser::parse(Rule::EXPRESSION, source)
+            .map_err(|err| anyhow!("invalid masking rule `{source}`:\n{err}"))?;
+        let root = Expr::from_pair(pairs.next().expect("EXPR"))
+            .map_err(|err| anyhow!("invalid masking rule `{source}`: {err}"))?;
+
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// The faker, when the whole expression is a faker call such as
+    /// `contact::email()` without a locale.
+    pub fn faker(&self) -> Option<&dyn FromStrFaking> {
+        match &self.root {
+            Expr::Faker {
+                faker,
+                locale: None,
+            } => Some(&**faker),
+            _ => None,
+        }
+    }
+
+    /// The format and tweak, when the whole expression is `fpe(value, ...)`,
+    /// which `unmask` can reverse.
+    pub fn reversible_fpe(&self) -> Option<(FpeFormat, &str)> {
+        match &self.root {
+            Expr::Call {
+                function: Function::Fpe,
+                args,
+            } if matches!(args[0], Expr::Current) => Some(fpe_arguments(args)),
+            _ => None,
+        }
+    }
+
+    /// Whether the expression calls the function `name` anywhere.
+    pub fn uses(&self, name: &str) -> bool {
+        Function::from_name(name).is_some_and(|function| self.root.uses(function))
+    }
+
+    pub fn evaluate(&self, row: &mut dyn Row) -> ExtractResult<Value> {
+        self.root.evaluate(row)
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    Current,
+    Column {
+        name: String,
+        masked: bool,
+    },
+    Faker {
+        faker: Box<dyn FromStrFaking>,
+        locale: Option<Locale>,
+    },
+    Call {
+        function: Function,
+        args: Vec<Expr>,
+    },
+    /// `a || b`, `NULL` when any part is.
+    Concat(Vec<Expr>),
+}
+
+impl Expr {
+    fn from_pair(pair: Pair<'_, Rule>) -> ExtractResult<Self> {
+        match pair.as_rule() {
+            Rule::EXPR => {
+                let mut terms = pair
+                    .into_inner()
+                    .map(Self::from_pair)
+                    .collect::<ExtractResult<Vec<_>>>()?;
+                Ok(if terms.len() == 1 {
+                    terms.remove(0)
+                } else {
+                    Self::Concat(terms)
+                })
+            }
+            Rule::NULL => Ok(Self::Literal(Value::Null)),
+            Rule::BOOLEAN => Ok(Self::Literal(Value::Bool(
+                pair.as_str().eq_ignore_ascii_case("true"),
+            ))),
+            Rule::NUMBER => Ok(Self::Literal(match pair.as_str().parse::<i64>() {
+                Ok(int) => Value::Int(int),
+                Err(_) => Value::Float(pair.as_str().parse()?),
+            })),
+            Rule::STRING => Ok(Self::Literal(Value::Text(unescape_str(literal_contents(
+                pair.as_str(),
+            ))))),
+            Rule::COLUMN => {
+                let parts = pair.into_inner().map(|p| p.as_str()).collect::<Vec<_>>();
+                match parts.as_slice() {
+                    ["value"] => Ok(Self::Current),
+                    [name] => Ok(Self::Column {
+                        name: name.to_string(),
+                        masked: false,
+                    }),
+                    ["original", name] | ["masked", name] => Ok(Self::Column {
+                        name: name.to_string(),
+                        masked: parts[0] == "masked",
+                    }),
+                    [qualifier, _] => {
+                        bail!("unknown qualifier `{qualifier}`, expected `original` or `masked`")
+                    }
+                    _ => unreachable!("COLUMN has one or two parts"),
+                }
+            }
+            Rule::FAKER => {
+                let mut inner = pair.into_inner();
+                let name = inner.nth(1).expect("IDENT").as_str();
+                let faker = find_struct_by_name(&name.to_lowercase().replace('_', ""))
+                    .ok_or_else(|| anyhow!("unknown faker `{name}`"))?;
+                let locale = match inner.next() {
+                    Some(locale) => {
+                        let locale = unescape_str(literal_contents(locale.as_str()));
+                        Some(
+                            Locale::from_name(&locale)
+                                .ok_or_else(|| anyhow!("unknown locale `{locale}`"))?,
+                        )
+                    }
+                    None => None,
+                };
+                Ok(Self::Faker { faker, locale })
+            }
+            Rule::CALL => {
+                let mut inner = pair.into_inner();
+                let name = inner.next().expect("IDENT").as_str();
+                let function = Function::from_name(name)
+                    .ok_or_else(|| anyhow!("unknown function `{name}`"))?;
+                let args = inner
+                    .map(Self::from_pair)
+                    .collect::<ExtractResult<Vec<_>>>()?;
+
+                let (min, max) = function.arity();
+                if args.len() < min || max.is_some_and(|max| args.len() > max) {
+                    let expected = match max {
+                        Some(max) if max == min => min.to_string(),
+                        Some(max) => format!("{min} to {max}"),
+                        None => format!("at least {min}"),
+                    };
+                    bail!("`{name}` takes {expected} argument(s), not {}", args.len());
+                }
+                if function == Function::Fpe {
+                    // The format and tweak must be known to reverse the encryption.
+                    for arg in &args[1..] {
+                        if !matches!(arg, Self::Literal(Value::Text(_))) {
+                            bail!("the format and tweak of `fpe` must be strings");
+                        }
+                    }
+                    if let Some(Self::Literal(Value::Text(format))) = args.get(1) {
+                        if FpeFormat::from_name(format).is_none() {
+                            bail!("unknown fpe format `{format}`, expected `digits`, `alphanumeric` or `email`");
+                        }
+                    }
+                }
+                Ok(Self::Call { function, args })
+            }
+            other => unreachable!("unexpected {other:?} in expression"),
+        }
+    }
+
+    fn uses(&self, function: Function) -> bool {
+        match self {
+            Self::Call {
+                function: called,
+                args,
+            } => *called == function || args.iter().any(|arg| arg.uses(function)),
+            Self::Concat(parts) => parts.iter().any(|part| part.uses(function)),
+            _ => false,
+        }
+    }
+
+    fn evaluate(&self, row: &mut dyn Row) -> ExtractResult<Value> {
+        Ok(match self {
+            Self::Literal(value) => value.clone(),
+            Self::Current => row.current(),
+            Self::Column { name, masked } if *masked => row.masked(name),
+            Self::Column { name, .. } => row.original(name),
+            Self::Faker { faker, locale } => {
+                let locale = locale.unwrap_or_else(|| row.locale());
+                Value::Text(faker.fake_in(locale))
+            }
+            Self::Call { function, args } => return function.call(args, row),
+            Self::Concat(parts) => {
+                let mut out = String::new();
+                for part in parts {
+                    match part.evaluate(row)?.to_text() {
+                        Some(text) => out.push_str(&text),
+                        None => return Ok(Value::Null),
+                    }
+                }
+                Value::Text(out)
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Lower,
+    Upper,
+    Trim,
+    Length,
+    Concat,
+    Substr,
+    Replace,
+    KeepFirst,
+    KeepLast,
+    Hash,
+    If,
+    IsNull,
+    Coalesce,
+    Eq,
+    Not,
+    Fpe,
+    Redact,
+    Null,
+    Truncate,
+    ShiftDate,
+    Bucket,
+    Round,
+    Laplace,
+    Gaussian,
+    CoarsenZip,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "lower" => Self::Lower,
+            "upper" => Self::Upper,
+            "trim" => Self::Trim,
+            "length" => Self::Length,
+            "concat" => Self::Concat,
+            "substr" => Self::Substr,
+            "replace" => Self::Replace,
+            "keep_first" => Self::KeepFirst,
+            "keep_last" => Self::KeepLast,
+            "hash" => Self::Hash,
+            "if" => Self::If,
+            "is_null" => Self::IsNull,
+            "coalesce" => Self::Coalesce,
+            "eq" => Self::Eq,
+            "not" => Self::Not,
+            "fpe" => Self::Fpe,
+            "redact" => Self::Redact,
+            "null" => Self::Null,
+            "truncate" => Self::Truncate,
+            "shift_date" => Self::ShiftDate,
+            "bucket" => Self::Bucket,
+            "round" => Self::Round,
+            "laplace" => Self::Laplace,
+            "gaussian" => Self::Gaussian,
+            "coarsen_zip" => Self::CoarsenZip,
+            _ => return None,
+        })
+    }
+
+    /// Minimum and maximum number of arguments.
+    fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Lower | Self::Upper | Self::Trim | Self::Length | Self::IsNull | Self::Not => {
+                (1, Some(1))
+            }
+            Self::Fpe => (1, Some(3)),
+            Self::Redact | Self::Null => (0, Some(1)),
+            Self::Truncate | Self::ShiftDate | Self::Bucket => (2, Some(2)),
+            Self::Round | Self::CoarsenZip => (1, Some(2)),
+            Self::Laplace | Self::Gaussian => (2, Some(3)),
+            Self::Hash | Self::Eq => (1 + usize::from(*self == Self::Eq), Some(2)),
+            Self::Substr | Self::KeepFirst | Self::KeepLast => (2, Some(3)),
+            Self::Replace | Self::If => (3, Some(3)),
+            Self::Concat | Self::Coalesce => (1, None),
+        }
+    }
+
+    fn call(&self, args: &[Expr], row: &mut dyn Row) -> ExtractResult<Value> {
+        // Only the taken branch of `if` is evaluated, so unused fakers are not drawn.
+        if *self == Self::If {
+            let branch = if truthy(&args[0].evaluate(row)?) {
+                &args[1]
+            } else {
+                &args[2]
+            };
+            return branch.evaluate(row);
+        }
+        if *self == Self::Coalesce {
+            for arg in args {
+                let value = arg.evaluate(row)?;
+                if !value.is_null() {
+                    return Ok(value);
+                }
+            }
+            return Ok(Value::Null);
+        }
+
+        // `NULL` stays `NULL`, other values are replaced.
+        if matches!(self, Self::Redact | Self::Null) {
+            if row.current().is_null() {
+                return Ok(Value::Null);
+            }
+            if *self == Self::Null && row.column().is_none_or(|column| column.nullable) {
+                return Ok(Value::Null);
+            }
+            let default = if *self == Self::Redact { "***" } else { "" };
+            return match args.first() {
+                Some(arg) => arg.evaluate(row),
+                None => Ok(Value::Text(default.to_string())),
+            };
+        }
+
+        let values = args
+            .iter()
+            .map(|arg| arg.evaluate(row))
+            .collect::<ExtractResult<Vec<_>>>()?;
+        if *self == Self::Fpe {
+            return fpe(args, &values[0], row);
+        }
+        Ok(self.apply(&values, row))
+    }
+
+    /// The value of a function that takes the values of its arguments.
+    fn apply(&self, values: &[Value], row: &mut dyn Row) -> Value {
+        match self {
+            Self::IsNull => return Value::Bool(values[0].is_null()),
+            Self::Not => return Value::Bool(!truthy(&values[0])),
+            Self::Eq => return Value::Bool(values[0].to_text() == values[1].to_text()),
+            Self::Concat => return Value::Text(values.iter().filter_map(Value::to_text).collect()),
+            _ => {}
+        }
+
+        // The remaining functions return `NULL` for a `NULL` argument.
+        let Some(texts) = values
+            .iter()
+            .map(Value::to_text)
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Value::Null;
+        };
+        let int = |i: usize| values.get(i).and_then(integer);
+
+        if matches!(
+            self,
+            Self::Bucket | Self::Round | Self::Laplace | Self::Gaussian
+        ) {
+            // Values that are not numbers, such as `'n/a'`, are kept.
+            let (Some(x), Some(parameter)) = (
+                numeric::number(&values[0]),
+                values.get(1).map_or(Some(0.0), numeric::number),
+            ) else {
+                return values[0].clone();
+            };
+            let data_type = row.column().map(|column| &column.data_type);
+            let fit = |x: f64| numeric::fit_number(x, data_type, &texts[0]);
+            let bound = values.get(2).and_then(numeric::number);
+
+            return match self {
+                Self::Bucket if parameter <= 0.0 => values[0].clone(),
+                Self::Bucket => {
+                    let low = numeric::bucket(x, parameter);
+                    match data_type {
+                        Some(data_type) if !numeric::is_numeric(data_type) => {
+                            Value::Text(numeric::bucket_label(low, parameter))
+                        }
+                        _ => fit(low),
+                    }
+                }
+                Self::Round => fit(numeric::round(x, parameter as i32)),
+                Self::Laplace => {
+                    fit(x + numeric::laplace(parameter, bound.unwrap_or(3.0 * parameter)))
+                }
+                _ => fit(x + numeric::gaussian(parameter, bound.unwrap_or(3.0 * parameter))),
+            };
+        }
+
+        match self {
+            Self::Lower => Value::Text(texts[0].to_lowercase()),
+            Self::Upper => Value::Text(texts[0].to_uppercase()),
+            Self::Trim => Value::Text(texts[0].trim().to_string()),
+            Self::Length => Value::Int(texts[0].chars().count() as i64),
+            Self::Substr => {
+                let start = int(1).unwrap_or(1).max(1) as usize - 1;
+                let chars = texts[0].chars().skip(start);
+                Value::Text(match int(2) {
+                    Some(length) => chars.take(length.max(0) as usize).collect(),
+                    None => chars.collect(),
+                })
+            }
+            Self::Replace => Value::Text(texts[0].replace(&texts[1], &texts[2])),
+            Self::ShiftDate => row
+                .config()
+                .and_then(|config| config.date_shifter.as_ref())
+                .and_then(|shifter| {
+                    let data_type = row.column().map(|column| &column.data_type);
+                    shifter.shift(&texts[0], &texts[1], data_type)
+                })
+                .map_or(values[0].clone(), Value::Text),
+            Self::CoarsenZip => Value::Text(numeric::coarsen_zip(
+                &texts[0],
+                int(1).unwrap_or(3).max(0) as usize,
+            )),
+            Self::Truncate => Value::Text(
+                texts[0]
+                    .chars()
+                    .take(int(1).unwrap_or(0).max(0) as usize)
+                    .collect(),
+            ),
+            Self::KeepFirst | Self::KeepLast => {
+                let keep = int(0).unwrap_or(0).max(0) as usize;
+                let mask = texts
+                    .get(2)
+                    .and_then(|mask| mask.chars().next())
+                    .unwrap_or('*');
+                let length = texts[1].chars().count();
+                let kept = |i: usize| {
+                    if *self == Self::KeepFirst {
+                        i < keep
+                    } else {
+                        i + keep >= length
+                    }
+                };
+                Value::Text(
+                    texts[1]
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| if kept(i) { c } else { mask })
+                        .collect(),
+                )
+            }
+            Self::Hash => {
+                // A salt keys an HMAC, so salt and value cannot run into each other.
+                let digest = match texts.get(1) {
+                    Some(salt) => {
+                        let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes())
+                            .expect("HMAC takes keys of any length");
+                        mac.update(texts[0].as_bytes());
+                        mac.finalize().into_bytes()
+                    }
+                    None => Sha256::digest(texts[0].as_bytes()),
+                };
+                Value::Text(digest.iter().fold(String::new(), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                }))
+            }
+            Self::If
+            | Self::IsNull
+            | Self::Not
+            | Self::Eq
+            | Self::Concat
+            | Self::Coalesce
+            | Self::Fpe
+            | Self::Redact
+            | Self::Null
+            | Self::Bucket
+            | Self::Round
+            | Self::Laplace
+            | Self::Gaussian => {
+                unreachable!("handled above")
+            }
+        }
+    }
+}
+
+/// Encrypt `value` with the config's cipher, keeping `NULL`. Values with too
+/// few characters to encrypt are an error rather than left unmasked.
+fn fpe(args: &[Expr], value: &Value, row: &dyn Row) -> ExtractResult<Value> {
+    let Some(text) = value.to_text() else {
+        return Ok(Value::Null);
+    };
+    let Some(cipher) = row.config().and_then(|config| config.cipher.as_ref()) else {
+        bail!("fpe() needs the `fpe` section of the masking config");
+    };
+    let (format, tweak) = fpe_arguments(args);
+    match cipher.encrypt(&text, format, tweak) {
+        Some(encrypted) => Ok(Value::Text(encrypted)),
+        None => bail!(
+            "fpe() cannot encrypt a value of {} characters in the {format:?} format, it needs at least 6 digits or 4 alphanumerics",
+            text.chars().count()
+        ),
+    }
+}
+
+/// The format and tweak of an `fpe` call, checked to be literals when parsed.
+fn fpe_arguments(args: &[Expr]) -> (FpeFormat, &str) {
+    let text = |i: usize| match args.get(i) {
+        Some(Expr::Literal(Value::Text(text))) => Some(text.as_str()),
+        _ => None,
+    };
+    (
+        text(1)
+            .and_then(FpeFormat::from_name)
+            .unwrap_or(FpeFormat::Alphanumeric),
+        text(2).unwrap_or_default(),
+    )
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Int(value) => *value != 0,
+        Value::UInt(value) => *value != 0,
+        Value::Float(value) => *value != 0.0,
+        other => other.to_text().is_some_and(|text| !text.is_empty()),
+    }
+}
+
+fn integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Int(value) => Some(*value),
+        Value::UInt(value) => i64::try_from(*value).ok(),
+        Value::Float(value) => Some(*value as i64),
+        other => other.to_text()?.trim().parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::masker::fpe::{Fpe, FpeAlgorithm};
+    use crate::settings::parse_masking_config;
+
+    fn evaluate(source: &str) -> Value {
+        try_evaluate(source, true).unwrap()
+    }
+
+    fn try_evaluate(source: &str, with_config: bool) -> ExtractResult<Value> {
+        let columns = ["first_name", "last_name", "card", "note"].map(String::from);
+        let values = [
+            InsertValue::Text {
+                value: String::from("Ann"),
+            },
+            InsertValue::Text {
+                value: String::from("O\\'Neil"),
+            },
+            InsertValue::Number {
+                value: String::from("4111111111111111"),
+            },
+            InsertValue::Null,
+        ];
+        let mut config = parse_masking_config("./tests/more.yaml").unwrap();
+        config.cipher = Some(Fpe::new(FpeAlgorithm::Ff1, "secret", 1_000));
+        let mut row = ValuesRow {
+            columns: &columns,
+            values: &values,
+            index: 2,
+            config: with_config.then_some(&config),
+            table: None,
+        };
+        Expression::parse(source).unwrap().evaluate(&mut row)
+    }
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    #[test]
+    fn test_evaluates_expressions() {
+        assert_eq!(
+            evaluate("lower(first_name) || '.' || lower(last_name) || '@example.test'"),
+            text("ann.o'neil@example.test")
+        );
+        assert_eq!(evaluate("keep_last(4, value)"), text("************1111"));
+        assert_eq!(evaluate("keep_first(2, first_name, '#')"), text("An#"));
+        assert_eq!(
+            evaluate("hash('abc')"),
+            text("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            evaluate("hash('what do ya want for nothing?', 'Jefe')"),
+            text("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert_ne!(evaluate("hash('c', 'ab')"), evaluate("hash('bc', 'a')"));
+        assert_eq!(evaluate("if(is_null(note), null, 'x')"), Value::Null);
+        assert_eq!(evaluate("note || 'x'"), Value::Null);
+        assert_eq!(evaluate("concat(note, 'x', 1)"), text("x1"));
+        assert_eq!(evaluate("coalesce(note, upper(first_name))"), text("ANN"));
+        assert_eq!(evaluate("substr(masked.last_name, 3, 2)"), text("Ne"));
+        assert_eq!(evaluate("length(value)"), Value::Int(16));
+        assert_eq!(evaluate("redact()"), text("***"));
+        assert_eq!(evaluate("redact('[card]')"), text("[card]"));
+        assert_eq!(evaluate("truncate(first_name, 2)"), text("An"));
+        assert_eq!(evaluate("null('unknown')"), Value::Null);
+        assert_eq!(evaluate("bucket(37, 10)"), Value::Int(30));
+        assert_eq!(
+            evaluate("round(37.7749, 2)"),
+            Value::Decimal(String::from("37.7700"))
+        );
+        assert_eq!(evaluate("coarsen_zip('94107')"), text("94100"));
+        assert_eq!(evaluate("bucket(first_name, 10)"), text("Ann"));
+        let Value::Text(encrypted) = evaluate("fpe(value, 'digits', 'cards')") else {
+            panic!("fpe returned NULL");
+        };
+        assert!(encrypted.len() == 16 && encrypted.bytes().all(|b| b.is_ascii_digit()));
+        assert!(
+            matches!(evaluate("if(eq(first_name, 'Ann'), name::full(), null)"), Value::Text(name) if !name.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_fails_on_values_fpe_cannot_encrypt() {
+        assert_eq!(evaluate("fpe(note)"), Value::Null);
+        // Too few characters to encrypt safely.
+        assert!(try_evaluate("fpe(first_name)", true).is_err());
+        assert!(try_evaluate("fpe(value, 'digits')", false).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_expressions() {
+        for source in [
+            "lower(",
+            "nope(value)",
+            "lower(a, b)",
+            "contact::nope()",
+            "other.first_name",
+            "fpe(value, 'hex')",
+            "fpe(value, lower('digits'))",
+            "name::first('xx_XX')",
+            "name::first(value)",
+        ] {
+            assert!(Expression::parse(source).is_err(), "{source}");
+        }
+
+        let faker = Expression::parse("contact::email()").unwrap();
+        assert_eq!(format!("{:?}", faker.faker().unwrap()), "Email(\"email\")");
+        assert!(Expression::parse("hash(value)").unwrap().faker().is_none());
+        assert!(Expression::parse("name::first('de')")
+            .unwrap()
+            .faker()
+            .is_none());
+    }
+}
@@ -0,0 +1,174 @@
+//! Inline redaction: PII found inside free text, such as an email in a
+//! support note, is replaced while the rest of the text is kept.
+
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::rules::find_struct_by_name;
+use crate::settings::{InlineReplacement, MaskingRegex};
+
+lazy_static! {
+    /// Detectors available without configuration, named after the fakers
+    /// that stand in for what they find.
+    static ref BUILTIN: Vec<Detector> = [
+        ("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}"),
+        ("credit_card", r"\b(?:\d[ -]?){12,18}\d\b"),
+        ("ssn", r"\b\d{3}-\d{2}-\d{4}\b"),
+        ("phone", r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?|\b\d{2,4}[ .-])\d{3,4}[ .-]?\d{3,4}\b"),
+        ("ipv4_address", r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b"),
+    ]
+    .into_iter()
+    .map(|(name, regex)| Detector {
+        name: name.to_string(),
+        regex: Regex::new(regex).unwrap(),
+    })
+    .collect();
+}
+
+#[derive(Debug, Clone)]
+struct Detector {
+    name: String,
+    regex: Regex,
+}
+
+/// Finds PII with the configured patterns and built-in detectors and
+/// replaces each span found.
+#[derive(Debug)]
+pub struct InlineRedactor {
+    detectors: Vec<Detector>,
+    replacement: InlineReplacement,
+}
+
+impl InlineRedactor {
+    /// Detectors for `patterns`, whose `^` and `$` anchors are dropped, and
+    /// for the built-ins named in `builtin`, or all of them when it is `None`.
+    pub fn new(
+        patterns: &[MaskingRegex],
+        builtin: Option<&[String]>,
+        replacement: InlineReplacement,
+    ) -> crate::ExtractResult<Self> {
+        let mut detectors = patterns
+            .iter()
+            .map(|pattern| {
+                let regex = pattern.regex.strip_prefix('^').unwrap_or(&pattern.regex);
+                let regex = regex.strip_suffix('$').unwrap_or(regex);
+                Ok(Detector {
+                    name: pattern.name.clone().unwrap_or_else(|| String::from("pii")),
+                    regex: Regex::new(regex)?,
+                })
+            })
+            .collect::<crate::ExtractResult<Vec<_>>>()?;
+        match builtin {
+            Some(names) => {
+                for name in names {
+                    let Some(detector) = BUILTIN.iter().find(|d| d.name.eq_ignore_ascii_case(name))
+                    else {
+                        anyhow::bail!(
+                            "unknown detector `{name}`, expected one of {}",
+                            BUILTIN
+                                .iter()
+                                .map(|d| d.name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    };
+                    detectors.push(detector.clone());
+                }
+            }
+            None => detectors.extend(BUILTIN.iter().cloned()),
+        }
+
+        Ok(Self {
+            detectors,
+            replacement,
+        })
+    }
+
+    /// Replace every span of `text` a detector finds. Where spans overlap,
+    /// the one starting first, then the longest, wins.
+    pub fn redact<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut spans = self
+            .detectors
+            .iter()
+            .flat_map(|detector| {
+                detector
+                    .regex
+                    .find_iter(text)
+                    .filter(|found| !found.is_empty())
+                    .map(move |found| (found.start(), found.end(), detector.name.as_str()))
+            })
+            .collect::<Vec<_>>();
+        if spans.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        spans.sort_by_key(|&(start, end, _)| (start, std::cmp::Reverse(end)));
+
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (start, end, name) in spans {
+            if start < copied {
+                continue;
+            }
+            out.push_str(&text[copied..start]);
+            out.push_str(&self.replace(name));
+            copied = end;
+        }
+        out.push_str(&text[copied..]);
+        Cow::Owned(out)
+    }
+
+    fn replace(&self, name: &str) -> String {
+        match self.replacement {
+            InlineReplacement::Fake => match find_struct_by_name(&name.replace('_', "")) {
+                Some(faker) => faker.fake(),
+                None => format!("[{name}]"),
+            },
+            InlineReplacement::Label => format!("[{name}]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_spans_in_text() {
+        let redactor = InlineRedactor::new(
+            &[MaskingRegex {
+                name: Some(String::from("ticket")),
+                regex: String::from(r"^TCK-\d+$"),
+            }],
+            None,
+            InlineReplacement::Label,
+        )
+        .unwrap();
+
+        assert_eq!(
+            redactor.redact(
+                "Mail ann.lee@corp.com or call +1 415-555-0132 about TCK-42 (card 4111 1111 1111 1111, ssn 123-45-6789, from 10.0.0.12)."
+            ),
+            "Mail [email] or call [phone] about [ticket] (card [credit_card], ssn [ssn], from [ipv4_address])."
+        );
+        assert!(matches!(
+            redactor.redact("Nothing to see here, order 42."),
+            Cow::Borrowed(_)
+        ));
+
+        let faking =
+            InlineRedactor::new(&[], Some(&[String::from("email")]), InlineReplacement::Fake)
+                .unwrap();
+        let redacted = faking.redact("Reply to ann@corp.com today, not 415-555-0132");
+        assert!(
+            redacted.starts_with("Reply to ") && redacted.ends_with(" today, not 415-555-0132")
+        );
+        assert!(!redacted.contains("ann@corp.com") && redacted.contains('@'));
+
+        assert!(
+            InlineRedactor::new(&[], Some(&[String::from("dna")]), InlineReplacement::Label)
+                .is_err()
+        );
+    }
+}
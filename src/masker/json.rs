@@ -0,0 +1,167 @@
+//! JSON paths such as `$.profile.email` or `$.addresses[*].street`, which
+//! select the parts of a JSON column that a rule masks.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use anyhow::anyhow;
+use pest::Parser;
+use pest_derive::Parser;
+use serde_json::{Number, Value as Json};
+
+use crate::value::Value;
+use crate::ExtractResult;
+
+#[derive(Parser)]
+#[grammar = "masker/json_path.pest"]
+struct JsonPathParser;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    /// Every member of an object or element of an array.
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    source: String,
+    steps: Vec<Step>,
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.source)
+    }
+}
+
+impl JsonPath {
+    pub fn parse(source: &str) -> ExtractResult<Self> {
+        let pairs = JsonPathParser::parse(Rule::PATH, source.trim())
+            .map_err(|err| anyhow!("invalid JSON path `{source}`:\n{err}"))?;
+        let steps = pairs
+            .filter_map(|pair| match pair.as_rule() {
+                Rule::KEY => Some(Ok(Step::Key(pair.as_str().to_string()))),
+                Rule::QUOTED => {
+                    let quoted = pair.as_str();
+                    Some(Ok(Step::Key(quoted[1..quoted.len() - 1].to_string())))
+                }
+                Rule::INDEX => Some(
+                    pair.as_str()
+                        .parse()
+                        .map(Step::Index)
+                        .map_err(|_| anyhow!("invalid JSON path `{source}`: index too large")),
+                ),
+                Rule::WILDCARD => Some(Ok(Step::Wildcard)),
+                _ => None,
+            })
+            .collect::<ExtractResult<_>>()?;
+
+        Ok(Self {
+            source: source.trim().to_string(),
+            steps,
+        })
+    }
+
+    /// Replace every part of `json` the path selects with the result of
+    /// `mask`. Parts that are missing are skipped.
+    pub fn replace(&self, json: &mut Json, mask: &mut dyn FnMut(&Json) -> Json) {
+        replace(json, &self.steps, mask);
+    }
+}
+
+fn replace(json: &mut Json, steps: &[Step], mask: &mut dyn FnMut(&Json) -> Json) {
+    let Some((step, rest)) = steps.split_first() else {
+        *json = mask(json);
+        return;
+    };
+    match (step, json) {
+        (Step::Key(key), Json::Object(members)) => {
+            if let Some(member) = members.get_mut(key) {
+                replace(member, rest, mask);
+            }
+        }
+        (Step::Index(index), Json::Array(elements)) => {
+            if let Some(element) = elements.get_mut(*index) {
+                replace(element, rest, mask);
+            }
+        }
+        (Step::Wildcard, Json::Object(members)) => {
+            for member in members.values_mut() {
+                replace(member, rest, mask);
+            }
+        }
+        (Step::Wildcard, Json::Array(elements)) => {
+            for element in elements {
+                replace(element, rest, mask);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A part of a JSON document as the value a rule sees.
+pub(crate) fn to_value(json: &Json) -> Value {
+    match json {
+        Json::Null => Value::Null,
+        Json::Bool(value) => Value::Bool(*value),
+        Json::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(int), _, _) => Value::Int(int),
+            (None, Some(uint), _) => Value::UInt(uint),
+            (None, None, Some(float)) => Value::Float(float),
+            _ => Value::Text(number.to_string()),
+        },
+        Json::String(value) => Value::Text(value.clone()),
+        other => Value::Json(other.clone()),
+    }
+}
+
+/// The result of a rule as a part of a JSON document.
+pub(crate) fn from_value(value: Value) -> Json {
+    match value {
+        Value::Null => Json::Null,
+        Value::Bool(value) => Json::Bool(value),
+        Value::Int(value) => Json::from(value),
+        Value::UInt(value) => Json::from(value),
+        Value::Float(value) => Number::from_f64(value).map_or(Json::Null, Json::Number),
+        Value::Decimal(value) => match value.parse() {
+            Ok(Json::Number(number)) => Json::Number(number),
+            _ => Json::String(value),
+        },
+        Value::Text(value) => Json::String(value),
+        Value::Set(values) => Json::String(values.join(",")),
+        Value::Json(value) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replaces_selected_parts() {
+        let mut document = json!({
+            "profile": {"email": "ann@corp.com", "name": "Ann"},
+            "addresses": [{"street": "1 Main St", "zip": "94107"}, {"street": "2 Side St"}],
+            "tags": ["a", "b"]
+        });
+        for path in [
+            "$.profile.email",
+            "$.addresses[*].street",
+            "$['tags'][1]",
+            "$.missing.email",
+        ] {
+            JsonPath::parse(path)
+                .unwrap()
+                .replace(&mut document, &mut |_| json!("x"));
+        }
+        assert_eq!(
+            document.to_string(),
+            r#"{"profile":{"email":"x","name":"Ann"},"addresses":[{"street":"x","zip":"94107"},{"street":"x"}],"tags":["a","x"]}"#
+        );
+
+        assert!(JsonPath::parse("profile.email").is_err());
+        assert!(JsonPath::parse("$.profile..email").is_err());
+    }
+}
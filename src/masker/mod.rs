@@ -1,62 +1,929 @@
-use crate::parser::statements::Insert;
-use crate::parser::types::InsertValue;
+mod condition;
+mod dates;
+mod expr;
+mod fpe;
+mod inline;
+mod json;
+mod numeric;
+mod vault;
+
+pub use condition::Condition;
+pub(crate) use dates::civil_from_days;
+pub use dates::DateShift;
+pub use expr::{Expression, Row, ValuesRow};
+pub use fpe::{Fpe, FpeAlgorithm, FpeFormat};
+pub use inline::InlineRedactor;
+pub use json::JsonPath;
+pub use vault::Vault;
+
+use std::borrow::Cow;
+use std::cell::{RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use anyhow::bail;
+
+use crate::parser::parse_utils::{escape_str, unescape_str};
+use crate::parser::statements::{CreateTable, Insert, Statement};
+use crate::parser::types::{AssignmentValue, Column, DataType, InsertValue};
+use crate::rewrite::{walk_statement_mut, StatementContext, StatementRewriter};
 use crate::rules::get_struct_by_name;
 use crate::settings::MaskingConfig;
+use crate::value::Value;
+use crate::ExtractResult;
 // RFU
 // use pii_masker_pii::similarity;
 
+/// Fakes drawn for a value before settling for one another original already maps to.
+const VAULT_ATTEMPTS: usize = 10;
+
+/// Masked values drawn for a value of a unique column before falling back
+/// to a numbered suffix.
+const UNIQUE_ATTEMPTS: usize = 10;
+
+/// The columns of a table, by lowercased column name.
+type TableColumns = HashMap<String, Column>;
+
+/// The values used so far in each unique column of a table, by lowercased column name.
+type UniqueValues = HashMap<String, HashSet<String>>;
+
+/// A lowercased `(database, table)`, the database being the one selected by
+/// the last `USE` statement.
+type TableKey = (Option<String>, String);
+
+fn table_key(database: Option<&str>, table: &str) -> TableKey {
+    (database.map(str::to_lowercase), table.to_lowercase())
+}
+
+/// A lowercased `(database, table, column)`, see [`TableKey`].
+type ColumnKey = (Option<String>, String, String);
+
+/// The masked values shared by the columns of a chain of foreign keys.
+struct LinkedValues {
+    /// The column whose rule and vault mappings mask the whole chain.
+    rule_column: String,
+    /// Whether a column of the chain has a rule or is listed in `columns`.
+    masked: bool,
+    /// Masked values by original value. Like the values of unique columns,
+    /// these are kept until the end of the dump.
+    values: HashMap<String, InsertValue>,
+    used: HashSet<String>,
+}
+
 pub struct Transform<'a> {
     pub config: &'a MaskingConfig,
+    vault: Option<RefCell<Vault>>,
+    /// Columns of the tables created so far.
+    tables: RefCell<HashMap<TableKey, TableColumns>>,
+    /// Column names of the tables created so far in table order, for
+    /// `INSERT` statements without a column list.
+    column_names: RefCell<HashMap<TableKey, Vec<String>>>,
+    /// Values of the columns that are a primary key or unique index on their
+    /// own. Every distinct value masked into such a column is kept until the
+    /// end of the dump, so memory grows with the number of rows of tables
+    /// with unique masked columns.
+    unique: RefCell<HashMap<TableKey, RefCell<UniqueValues>>>,
+    /// The column each foreign key column references.
+    references: RefCell<HashMap<ColumnKey, ColumnKey>>,
+    /// Values of the foreign key chains, by the column at their root.
+    linked: RefCell<HashMap<ColumnKey, LinkedValues>>,
+    /// Set when a rewritten statement could not be masked, see [`StatementRewriter::check`].
+    error: Option<anyhow::Error>,
 }
 
 impl<'a> Transform<'a> {
     pub fn new(config: &'a MaskingConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            vault: None,
+            tables: RefCell::default(),
+            column_names: RefCell::default(),
+            unique: RefCell::default(),
+            references: RefCell::default(),
+            linked: RefCell::default(),
+            error: None,
+        }
     }
 
-    // define a method that 
-    pub fn mask_dml_stmts(&self, dmls: &mut [Insert]) {
-        for stmt in dmls {
-            let columns: &[String] = &stmt.column_names;
-            for (i, value) in stmt.values.clone().iter().enumerate() {
-                let values_str: Vec<String> = value
-                    .0
-                    .iter()
-                    .map(|v| v.to_string().replace('\'', ""))
-                    .collect();
+    /// Remember the columns of a `CREATE TABLE`, so that rules know whether
+    /// its columns are nullable and which of them hold dates, masked values
+    /// stay unique in its primary key and unique index columns, and its
+    /// foreign keys get the masked values of the columns they reference.
+    /// `database` is the one the table was created in.
+    pub fn learn_table(&self, database: Option<&str>, create_table: &CreateTable) {
+        let key = table_key(database, &create_table.name);
+        let columns = create_table
+            .columns
+            .iter()
+            .map(|column| (column.name.to_lowercase(), column.clone()))
+            .collect();
+        self.tables.borrow_mut().insert(key.clone(), columns);
+        self.column_names.borrow_mut().insert(
+            key.clone(),
+            create_table
+                .columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect(),
+        );
 
-                let masked = columns
+        let unique = create_table
+            .primary_key
+            .iter()
+            .map(|key| &key.column_names)
+            .chain(
+                create_table
+                    .indexes
                     .iter()
-                    .zip(values_str.iter())
-                    .map(|(column, value)| {
-                        if self.config.filter_column(value) || self.config.filter_column(column) {
-                            let rule = get_struct_by_name(column);
-                            let masked_value = rule.fake();
-                            (column.clone(), masked_value.clone())
-                        } else {
-                            (column.clone(), value.clone())
-                        }
-                    })
-                    .collect::<Vec<(String, String)>>();
-
-                stmt.values[i].0 = masked
-                    .iter()
-                    .cloned()
-                    .map(|(_, value)| InsertValue::Text { value })
-                    .collect::<Vec<InsertValue>>();
+                    .filter(|index| index.unique)
+                    .map(|index| &index.columns),
+            )
+            .filter(|columns| columns.len() == 1)
+            .map(|columns| (columns[0].to_lowercase(), HashSet::new()))
+            .collect();
+        self.unique
+            .borrow_mut()
+            .insert(key.clone(), RefCell::new(unique));
+
+        let mut references = self.references.borrow_mut();
+        for foreign_key in &create_table.foreign_keys {
+            for (local, foreign) in foreign_key
+                .local_column_names
+                .iter()
+                .zip(&foreign_key.foreign_column_names)
+            {
+                let parent = table_key(database, &foreign_key.foreign_table_name);
+                references.insert(
+                    (key.0.clone(), key.1.clone(), local.to_lowercase()),
+                    (parent.0, parent.1, foreign.to_lowercase()),
+                );
+            }
+        }
+    }
+
+    /// Reuse the fakes recorded in `vault` and record every new one.
+    pub fn with_vault(mut self, vault: Vault) -> Self {
+        self.vault = Some(RefCell::new(vault));
+        self
+    }
+
+    /// Write the mappings recorded since the vault was opened.
+    pub fn save_vault(&self) -> ExtractResult<()> {
+        match &self.vault {
+            Some(vault) => vault.borrow_mut().save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Replace the values of every column selected by the masking config with
+    /// fake data, or the result of the column's rule, leaving all other values untouched.
+    ///
+    /// Statements without a column list are matched to the columns of their
+    /// table's `CREATE TABLE`.
+    ///
+    /// Fails when a unique column runs out of distinct masked values, and
+    /// when the values of a row cannot be matched to their columns, such as
+    /// for a statement without a column list whose `CREATE TABLE` was not
+    /// seen or could not be parsed, as those would be written unmasked.
+    ///
+    /// `database` is the one the statements run in.
+    pub fn mask_dml_stmts(&self, database: Option<&str>, dmls: &mut [Insert]) -> ExtractResult<()> {
+        let tables = self.tables.borrow();
+        let column_names = self.column_names.borrow();
+        let unique = self.unique.borrow();
+        for stmt in dmls {
+            let key = table_key(database, &stmt.table_name);
+            let columns: &[String] = match stmt.column_names.as_slice() {
+                [] => column_names.get(&key).map_or(&[], Vec::as_slice),
+                columns => columns,
+            };
+            let table = tables.get(&key);
+            let unique = unique.get(&key);
+            for row in stmt.values.iter_mut() {
+                if row.0.len() != columns.len() {
+                    if columns.is_empty() {
+                        bail!(
+                            "unable to mask `{}`: its INSERT has no column list and its CREATE TABLE was not found or could not be parsed",
+                            stmt.table_name
+                        );
+                    }
+                    bail!(
+                        "unable to mask `{}`: a row has {} values for {} columns",
+                        stmt.table_name,
+                        row.0.len(),
+                        columns.len()
+                    );
+                }
+                let mut masking =
+                    MaskingRow::new(self, database, &stmt.table_name, table, columns, &row.0);
+                masking.unique = unique;
+                let masked = (0..columns.len().min(row.0.len()))
+                    .map(|i| masking.mask(i))
+                    .collect::<Vec<_>>();
+                if let Some(err) = masking.error {
+                    return Err(err);
+                }
+                row.0.splice(..masked.len(), masked);
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Slot {
+    Pending,
+    /// Being masked, a rule that refers back to it sees `NULL`.
+    Busy,
+    Done(InsertValue),
+}
+
+/// One row being masked, where each column is masked at most once so that
+/// rules reading `masked.<column>` see the value that ends up in the dump.
+struct MaskingRow<'r> {
+    config: &'r MaskingConfig,
+    vault: Option<&'r RefCell<Vault>>,
+    database: Option<&'r str>,
+    table_name: &'r str,
+    table: Option<&'r TableColumns>,
+    columns: &'r [String],
+    values: &'r [InsertValue],
+    slots: Vec<Slot>,
+    /// The vault column of each column whose fake came from the vault or
+    /// goes into it, recorded once the value is final.
+    vault_columns: Vec<Option<String>>,
+    index: usize,
+    /// The part of a JSON document a `json` rule is masking, seen as `value`.
+    json_part: Option<Value>,
+    unique: Option<&'r RefCell<UniqueValues>>,
+    references: &'r RefCell<HashMap<ColumnKey, ColumnKey>>,
+    linked: &'r RefCell<HashMap<ColumnKey, LinkedValues>>,
+    error: Option<anyhow::Error>,
+}
+
+impl<'r> MaskingRow<'r> {
+    fn new(
+        transform: &'r Transform,
+        database: Option<&'r str>,
+        table_name: &'r str,
+        table: Option<&'r TableColumns>,
+        columns: &'r [String],
+        values: &'r [InsertValue],
+    ) -> Self {
+        Self {
+            config: transform.config,
+            vault: transform.vault.as_ref(),
+            database,
+            table_name,
+            table,
+            columns,
+            values,
+            slots: values.iter().map(|_| Slot::Pending).collect(),
+            vault_columns: values.iter().map(|_| None).collect(),
+            index: 0,
+            json_part: None,
+            unique: None,
+            references: &transform.references,
+            linked: &transform.linked,
+            error: None,
+        }
+    }
+
+    fn mask(&mut self, i: usize) -> InsertValue {
+        match &self.slots[i] {
+            Slot::Done(value) => return value.clone(),
+            Slot::Busy => return InsertValue::Null,
+            Slot::Pending => {}
+        }
+        self.slots[i] = Slot::Busy;
+        // A JSON rule reading `masked.<column>` masks that column with its own `value`.
+        let json_part = self.json_part.take();
+
+        // Columns linked by foreign keys reuse the value masked for the same original.
+        let link = match &self.values[i] {
+            InsertValue::Null | InsertValue::Default => None,
+            _ => self.link_root(i),
+        };
+        let original = value_str(&self.values[i]);
+        let mapped = link
+            .as_ref()
+            .and_then(|root| self.linked_values(root).values.get(&original).cloned());
+
+        let masked = match mapped {
+            Some(mapped) => mapped,
+            None => match self.mask_value(i) {
+                Some(masked) => {
+                    let masked = self.make_unique(i, masked);
+                    self.record(i, &masked);
+                    if let Some(root) = &link {
+                        self.linked_values(root)
+                            .values
+                            .insert(original, masked.clone());
+                    }
+                    masked
+                }
+                None => {
+                    let kept = self.values[i].clone();
+                    if !self.claim(i, &kept) {
+                        self.fail_kept(i);
+                    }
+                    // Rows referencing a kept value keep it too.
+                    if let Some(root) = &link {
+                        self.linked_values(root)
+                            .values
+                            .insert(original, kept.clone());
+                    }
+                    kept
+                }
+            },
+        };
+
+        self.slots[i] = Slot::Done(masked.clone());
+        self.json_part = json_part;
+        masked
+    }
+
+    /// The masked value of column `i`, or `None` when nothing masks it.
+    fn mask_value(&mut self, i: usize) -> Option<InsertValue> {
+        let columns = self.columns;
+        let column = &columns[i];
+        let value = &self.values[i];
+        if let Some(root) = self.link_root(i) {
+            if matches!(value, InsertValue::Null | InsertValue::Default) {
+                return None;
+            }
+            let (rule_column, masked) = {
+                let linked = self.linked_values(&root);
+                (linked.rule_column.clone(), linked.masked)
+            };
+            // The condition of the chain's rule is checked on rows that have its column.
+            if let Some(rule) = self.config.rule_for(&rule_column) {
+                if column.eq_ignore_ascii_case(&rule_column) && !rule.applies(self) {
+                    return None;
+                }
+            }
+            return (masked || self.config.filter_column(&value_str(value)))
+                .then(|| self.fake_recorded(i, &rule_column));
+        }
+
+        if let Some(masked) = self.mask_json(i) {
+            Some(masked)
+        } else if let Some(redacted) = self.redact_inline(i) {
+            Some(redacted)
+        } else if let Some(rule) = self.config.rule_for(column) {
+            // A rule whose condition is false keeps the value, even of a filtered column.
+            rule.applies(self).then(|| self.fake_recorded(i, column))
+        } else if self.config.filter_column(&value_str(value)) || self.config.filter_column(column)
+        {
+            Some(self.fake_recorded(i, column))
+        } else {
+            self.shift_date(i)
+        }
+    }
+
+    /// The column at the root of the foreign keys column `i` references, or
+    /// the column itself when only referenced; `None` when it is not linked.
+    fn link_root(&self, i: usize) -> Option<ColumnKey> {
+        let references = self.references.borrow();
+        let (database, table) = table_key(self.database, self.table_name);
+        let key = (database, table, self.columns[i].to_lowercase());
+        if !references.contains_key(&key) && !references.values().any(|parent| *parent == key) {
+            return None;
+        }
+        Some(root_of(&references, key))
+    }
+
+    /// The values shared by the chain rooted at `root`. Its rule is the root
+    /// column's, or else that of the first column of the chain with one.
+    fn linked_values(&self, root: &ColumnKey) -> RefMut<'r, LinkedValues> {
+        let mut linked = self.linked.borrow_mut();
+        if !linked.contains_key(root) {
+            let references = self.references.borrow();
+            let mut columns = references
+                .keys()
+                .filter(|child| root_of(&references, (*child).clone()) == *root)
+                .map(|(_, _, column)| column.as_str())
+                .collect::<Vec<_>>();
+            columns.sort_unstable();
+            columns.insert(0, &root.2);
+
+            let rule_column = columns
+                .iter()
+                .find(|column| self.config.rule_for(column).is_some())
+                .unwrap_or(&columns[0])
+                .to_string();
+            let masked = columns
+                .iter()
+                .any(|column| self.config.masks_linked_column(column));
+            linked.insert(
+                root.clone(),
+                LinkedValues {
+                    rule_column,
+                    masked,
+                    values: HashMap::new(),
+                    used: HashSet::new(),
+                },
+            );
+        }
+        RefMut::map(linked, |linked| {
+            linked.get_mut(root).expect("inserted above")
+        })
+    }
+
+    /// The set of values used so far in column `i`, when it is unique or
+    /// linked by foreign keys, whose masked values are kept distinct too.
+    fn unique_values(&self, i: usize) -> Option<RefMut<'r, HashSet<String>>> {
+        if let Some(root) = self.link_root(i) {
+            return Some(RefMut::map(self.linked_values(&root), |linked| {
+                &mut linked.used
+            }));
+        }
+        let column = self.columns[i].to_lowercase();
+        let unique = self.unique?.borrow_mut();
+        RefMut::filter_map(unique, |unique| unique.get_mut(&column)).ok()
+    }
+
+    /// Record `value` as used in column `i` and tell whether it was free.
+    fn claim(&self, i: usize, value: &InsertValue) -> bool {
+        match (self.unique_values(i), value) {
+            (Some(_), InsertValue::Null | InsertValue::Default) | (None, _) => true,
+            (Some(mut used), value) => used.insert(value_str(value)),
+        }
+    }
+
+    /// Keep the masked values of a unique column distinct: mask again while
+    /// the value is taken, then append a number to text, and give up on
+    /// other values, recording the error for [`Transform::mask_dml_stmts`].
+    fn make_unique(&mut self, i: usize, mut masked: InsertValue) -> InsertValue {
+        if self.unique_values(i).is_none() {
+            return masked;
+        }
+        for _ in 0..UNIQUE_ATTEMPTS {
+            if self.claim(i, &masked) {
+                return masked;
+            }
+            masked = self.mask_value(i).unwrap_or_else(|| self.values[i].clone());
+        }
+
+        let column = self
+            .table
+            .and_then(|table| table.get(&self.columns[i].to_lowercase()));
+        let max_len = match column.map(|column| &column.data_type) {
+            Some(DataType::Char { m, .. } | DataType::Varchar { m, .. }) => *m,
+            Some(
+                DataType::Text { .. }
+                | DataType::TinyText { .. }
+                | DataType::MediumText { .. }
+                | DataType::LongText { .. },
+            ) => None,
+            Some(_) => return self.fail(i, masked),
+            None if matches!(masked, InsertValue::Text { .. }) => None,
+            None => return self.fail(i, masked),
+        };
+        let text = value_str(&masked);
+        let used = self.unique_values(i).map_or(0, |used| used.len());
+        for n in 2..used + 3 {
+            let Some(numbered) = with_suffix(&text, n, max_len) else {
+                break;
+            };
+            let numbered = InsertValue::Text {
+                value: escape_str(&numbered),
+            };
+            if self.claim(i, &numbered) {
+                return numbered;
+            }
+        }
+        self.fail(i, masked)
+    }
+
+    fn fail(&mut self, i: usize, masked: InsertValue) -> InsertValue {
+        if self.error.is_none() {
+            self.error = Some(anyhow::anyhow!(
+                "unable to mask `{}`.`{}` without duplicates: its masked values ran out after {} rows, the value space is too small",
+                self.table_name,
+                self.columns[i],
+                self.unique_values(i).map_or(0, |used| used.len()),
+            ));
+        }
+        masked
+    }
+
+    /// Record that the original value of column `i`, left unmasked by its
+    /// rule's condition, was already given to another row as a masked value.
+    fn fail_kept(&mut self, i: usize) {
+        if self.error.is_none() {
+            self.error = Some(anyhow::anyhow!(
+                "unable to keep `{}`.`{}` unmasked: its value {} was already used as a masked value",
+                self.table_name,
+                self.columns[i],
+                self.values[i],
+            ));
+        }
+    }
+
+    /// Apply the `json` rules of column `i` to the parts of its document
+    /// they select, keeping everything else as it is. Values that are not
+    /// JSON give `None` and are masked like any other.
+    fn mask_json(&mut self, i: usize) -> Option<InsertValue> {
+        let rules = self.config.json_rules_for(&self.columns[i]);
+        if rules.is_empty() {
+            return None;
+        }
+        let InsertValue::Text { value } = &self.values[i] else {
+            return None;
+        };
+        let mut document = serde_json::from_str(&unescape_str(value)).ok()?;
+
+        let index = std::mem::replace(&mut self.index, i);
+        for json in rules {
+            if !json.rule.applies(self) {
+                continue;
+            }
+            json.path.replace(&mut document, &mut |part| {
+                self.json_part = Some(json::to_value(part));
+                let masked = self.evaluate(json.rule.expression());
+                json::from_value(masked)
+            });
+        }
+        self.json_part = None;
+        self.index = index;
+
+        Some(InsertValue::Text {
+            value: escape_str(&document.to_string()),
+        })
+    }
+
+    /// Replace the PII found inside the text of column `i` when the `inline`
+    /// section lists it, keeping the text around it.
+    fn redact_inline(&self, i: usize) -> Option<InsertValue> {
+        let redactor = self.config.redactor.as_ref()?;
+        if !self.config.inline_column(&self.columns[i]) {
+            return None;
+        }
+        let max_len = match self
+            .table
+            .and_then(|table| table.get(&self.columns[i].to_lowercase()))
+        {
+            Some(column) => match &column.data_type {
+                DataType::Char { m, .. } | DataType::Varchar { m, .. } => *m,
+                DataType::Text { .. }
+                | DataType::TinyText { .. }
+                | DataType::MediumText { .. }
+                | DataType::LongText { .. } => None,
+                _ => return None,
+            },
+            None => None,
+        };
+        let InsertValue::Text { value } = &self.values[i] else {
+            return None;
+        };
+
+        let text = unescape_str(value);
+        let redacted = match redactor.redact(&text) {
+            Cow::Borrowed(_) => return Some(self.values[i].clone()),
+            Cow::Owned(redacted) => redacted,
+        };
+        let redacted = match max_len {
+            Some(max_len) => redacted.chars().take(max_len as usize).collect(),
+            None => redacted,
+        };
+        Some(InsertValue::Text {
+            value: escape_str(&redacted),
+        })
+    }
+
+    /// Shift column `i` by the offset of the row's entity when it holds dates
+    /// and the `date_shift` section names a key column found in the row.
+    fn shift_date(&self, i: usize) -> Option<InsertValue> {
+        let shifter = self.config.date_shifter.as_ref()?;
+        let keys = &self.config.date_shift.as_ref()?.keys;
+        let data_type = &self.table?.get(&self.columns[i].to_lowercase())?.data_type;
+        if !matches!(
+            data_type,
+            DataType::Date | DataType::DateTime { .. } | DataType::Timestamp { .. }
+        ) {
+            return None;
+        }
+
+        let entity = keys.iter().find_map(|key| {
+            let column = match key.split_once('.') {
+                Some((table, column)) if table.eq_ignore_ascii_case(self.table_name) => column,
+                Some(_) => return None,
+                None => key,
+            };
+            match &self.values[self.position(column)?] {
+                InsertValue::Null | InsertValue::Default => None,
+                value => Some(value_str(value)),
+            }
+        })?;
+        let shifted = shifter.shift(&value_str(&self.values[i]), &entity, Some(data_type))?;
+        Some(InsertValue::Text {
+            value: escape_str(&shifted),
+        })
+    }
+
+    /// Fake the value of column `i` with the rule of `column`, through the
+    /// vault when there is one. The fake is recorded by [`Self::record`].
+    fn fake_recorded(&mut self, i: usize, column: &str) -> InsertValue {
+        let (
+            Some(vault),
+            InsertValue::Text { .. } | InsertValue::Introduced { .. } | InsertValue::Number { .. },
+        ) = (self.vault, &self.values[i])
+        else {
+            return self.fake(i, column);
+        };
+        self.vault_columns[i] = Some(column.to_string());
+        let original = value_str(&self.values[i]);
+        if let Some(fake) = vault.borrow().get(column, &original) {
+            return InsertValue::Text {
+                value: escape_str(fake),
+            };
+        }
+
+        // Avoid fakes that already stand for another value, so lookups are unambiguous.
+        let mut fake = self.fake(i, column);
+        for _ in 1..VAULT_ATTEMPTS {
+            if !vault
+                .borrow()
+                .is_taken(column, &value_str(&fake), &original)
+            {
+                break;
+            }
+            fake = self.fake(i, column);
+        }
+        fake
+    }
+
+    /// Record `masked`, the value written for column `i` after it was made
+    /// unique, in the vault when its fake was drawn through it.
+    fn record(&mut self, i: usize, masked: &InsertValue) {
+        let (Some(vault), Some(column)) = (self.vault, self.vault_columns[i].take()) else {
+            return;
+        };
+        if !matches!(masked, InsertValue::Null) {
+            vault
+                .borrow_mut()
+                .insert(&column, &value_str(&self.values[i]), &value_str(masked));
+        }
+    }
+
+    /// The rule of `column`, or a faker named after it, evaluated for column `i`.
+    fn fake(&mut self, i: usize, column: &str) -> InsertValue {
+        match self.config.rule_for(column) {
+            Some(rule) => {
+                let index = std::mem::replace(&mut self.index, i);
+                let masked = self.evaluate(rule.expression()).to_insert();
+                self.index = index;
+                masked
+            }
+            None => {
+                let faker = get_struct_by_name(column);
+                let fake = faker.fake_in(self.locale());
+                InsertValue::Text {
+                    value: escape_str(&fake),
+                }
+            }
+        }
+    }
+
+    /// Evaluate `expression` for the column being masked. A failure gives
+    /// `NULL` and is recorded for [`Transform::mask_dml_stmts`].
+    fn evaluate(&mut self, expression: &Expression) -> Value {
+        match expression.evaluate(self) {
+            Ok(value) => value,
+            Err(err) => {
+                let column = &self.columns[self.index];
+                let err = err.context(format!("unable to mask `{}`.`{column}`", self.table_name));
+                self.error.get_or_insert(err);
+                Value::Null
             }
         }
     }
+
+    fn position(&self, column: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .take(self.values.len())
+            .position(|name| name.eq_ignore_ascii_case(column))
+    }
+}
+
+impl Row for MaskingRow<'_> {
+    fn current(&mut self) -> Value {
+        match &self.json_part {
+            Some(part) => part.clone(),
+            None => Value::from_insert(&self.values[self.index], None),
+        }
+    }
+
+    fn original(&mut self, column: &str) -> Value {
+        self.position(column)
+            .map_or(Value::Null, |i| Value::from_insert(&self.values[i], None))
+    }
+
+    fn masked(&mut self, column: &str) -> Value {
+        match self.position(column) {
+            Some(i) => Value::from_insert(&self.mask(i), None),
+            None => Value::Null,
+        }
+    }
+
+    fn config(&self) -> Option<&MaskingConfig> {
+        Some(self.config)
+    }
+
+    fn column(&self) -> Option<&Column> {
+        self.table?.get(&self.columns[self.index].to_lowercase())
+    }
+}
+
+/// Follow the references from `key` to the column at the end of the chain,
+/// stopping on cycles.
+pub(crate) fn root_of<K: Clone + Eq + Hash>(references: &HashMap<K, K>, mut key: K) -> K {
+    for _ in 0..references.len() {
+        match references.get(&key) {
+            Some(parent) if *parent != key => key = parent.clone(),
+            _ => break,
+        }
+    }
+    key
+}
+
+/// `text` with the number `n` appended, before the `@` of an email, cut to
+/// `max_len` characters, or `None` when the number alone does not fit.
+fn with_suffix(text: &str, n: usize, max_len: Option<u32>) -> Option<String> {
+    let suffix = n.to_string();
+    let (local, domain) = match text.rfind('@') {
+        Some(at) => text.split_at(at),
+        None => (text, ""),
+    };
+    let room = match max_len {
+        Some(max_len) => (max_len as usize).checked_sub(suffix.len() + domain.chars().count())?,
+        None => usize::MAX,
+    };
+    let local = local.chars().take(room).collect::<String>();
+    Some(format!("{local}{suffix}{domain}"))
+}
+
+fn value_str(value: &InsertValue) -> String {
+    match value {
+        InsertValue::Text { value } | InsertValue::Introduced { value, .. } => unescape_str(value),
+        other => other.to_string(),
+    }
 }
 
+/// Lets the masker run as a [`Pipeline`](crate::rewrite::Pipeline) stage,
+/// masking `INSERT` values and `UPDATE` assignments.
+impl StatementRewriter for Transform<'_> {
+    fn rewrite_statement(&mut self, ctx: &StatementContext, statement: &mut Statement) -> bool {
+        // Rows the grammar cannot read would be written out unmasked.
+        if let Statement::Unknown(sql) = statement {
+            let keyword = sql.split_ascii_whitespace().next().unwrap_or_default();
+            if ["INSERT", "REPLACE"]
+                .iter()
+                .any(|insert| keyword.eq_ignore_ascii_case(insert))
+            {
+                let start = sql.chars().take(80).collect::<String>();
+                self.error.get_or_insert(anyhow::anyhow!(
+                    "unable to parse the statement starting with `{start}`, so its values cannot be masked"
+                ));
+            }
+        }
+        walk_statement_mut(self, ctx, statement)
+    }
+
+    fn rewrite_create_table(&mut self, ctx: &StatementContext, create_table: &mut CreateTable) {
+        self.learn_table(ctx.database.as_deref(), create_table);
+    }
+
+    fn rewrite_insert(&mut self, ctx: &StatementContext, insert: &mut Insert) {
+        let database = ctx.database.as_deref();
+        if let Err(err) = self.mask_dml_stmts(database, std::slice::from_mut(insert)) {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    fn check(&mut self) -> ExtractResult<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn rewrite_assignment(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: &str,
+        value: &mut AssignmentValue,
+    ) {
+        // An assignment is masked on its own, rules only see its column.
+        let columns = [column.to_string()];
+        let values = [match &*value {
+            AssignmentValue::String(value) => InsertValue::Text {
+                value: value.clone(),
+            },
+            AssignmentValue::Null => InsertValue::Null,
+            other => InsertValue::Number {
+                value: other.to_string(),
+            },
+        }];
+        let tables = self.tables.borrow();
+        let table_name = table;
+        let table = tables.get(&table_key(ctx.database.as_deref(), table_name));
+        let mut masking = MaskingRow::new(
+            self,
+            ctx.database.as_deref(),
+            table_name,
+            table,
+            &columns,
+            &values,
+        );
+        let masked = masking.mask(0);
+        if let Some(err) = masking.error {
+            self.error.get_or_insert(err);
+        }
+        if masked != values[0] {
+            *value = match masked {
+                InsertValue::Null => AssignmentValue::Null,
+                masked => AssignmentValue::String(escape_str(&value_str(&masked))),
+            };
+        }
+    }
+}
+
+/// Reverses the `fpe(value, ...)` rules of a masking config, restoring the
+/// original values of a dump masked with the same config and secret.
+pub struct Unmask<'a> {
+    pub config: &'a MaskingConfig,
+}
+
+impl<'a> Unmask<'a> {
+    pub fn new(config: &'a MaskingConfig) -> Self {
+        Self { config }
+    }
+
+    fn decrypt(&self, column: &str, value: &str) -> Option<String> {
+        let cipher = self.config.cipher.as_ref()?;
+        let (format, tweak) = self
+            .config
+            .rule_for(column)?
+            .expression()
+            .reversible_fpe()?;
+        cipher.decrypt(value, format, tweak)
+    }
+}
+
+impl StatementRewriter for Unmask<'_> {
+    fn rewrite_value(
+        &mut self,
+        _ctx: &StatementContext,
+        _table: &str,
+        column: Option<&str>,
+        value: &mut InsertValue,
+    ) {
+        let Some(column) = column else {
+            return;
+        };
+        let decrypted = match &*value {
+            InsertValue::Null | InsertValue::Default => None,
+            other => self.decrypt(column, &value_str(other)),
+        };
+        if let Some(decrypted) = decrypted {
+            *value = InsertValue::Text {
+                value: escape_str(&decrypted),
+            };
+        }
+    }
+
+    fn rewrite_assignment(
+        &mut self,
+        _ctx: &StatementContext,
+        _table: &str,
+        column: &str,
+        value: &mut AssignmentValue,
+    ) {
+        let decrypted = match &*value {
+            AssignmentValue::String(value) => self.decrypt(column, &unescape_str(value)),
+            AssignmentValue::Number(value) => self.decrypt(column, &value.to_string()),
+            _ => None,
+        };
+        if let Some(decrypted) = decrypted {
+            *value = AssignmentValue::String(escape_str(&decrypted));
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::statements::Insert;
     use crate::parser::MySqlParser;
+    use crate::rewrite::Pipeline;
+    use crate::settings::{
+        DateShiftConfig, InlineConfig, InlineReplacement, JsonRule, LocaleConfig, MaskingRule,
+    };
     use crate::{parser::Rule, settings::parse_masking_config};
     use pest::Parser;
     use regex::Regex;
-    use std::collections::HashMap;
 
     #[test]
     fn test_mask_dml_stmts() {
@@ -72,7 +939,7 @@ mod tests {
             .next()
             .expect("Unable to parse input"),
         )];
-        transform.mask_dml_stmts(dmls.as_mut_slice());
+        transform.mask_dml_stmts(None, dmls.as_mut_slice()).unwrap();
         let email_regex = Regex::new(
             &cfg.patterns
                 .iter()
@@ -108,6 +975,665 @@ mod tests {
             }
         );
         assert!(email_regex.is_match(&dmls[0].values[1].0[1].to_string().replace('\'', "")));
- 
+    }
+
+    #[test]
+    fn test_mask_dml_stmts_without_column_list() {
+        let cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        let transform = Transform::new(&cfg);
+        let Statement::CreateTable(table) =
+            Statement::parse("CREATE TABLE `u` (`id` int NOT NULL, `email` varchar(255))")
+        else {
+            panic!("expected CREATE TABLE");
+        };
+        transform.learn_table(None, &table);
+        let Statement::Insert(insert) = Statement::parse("INSERT INTO `u` VALUES (1,'real@x.com')")
+        else {
+            panic!("expected INSERT");
+        };
+        let mut dmls = vec![insert];
+        transform.mask_dml_stmts(None, &mut dmls).unwrap();
+
+        let masked = dmls[0].to_string();
+        assert!(
+            masked.starts_with("INSERT INTO `u` VALUES (1, '"),
+            "{masked}"
+        );
+        assert!(!masked.contains("real@x.com"), "{masked}");
+    }
+
+    #[test]
+    fn test_applies_rule_expressions() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        for (column, rule) in [
+            ("email", "masked.first_name || '@example.test'"),
+            ("first_name", "upper(value)"),
+            ("card", "keep_last(4, value)"),
+            ("a", "masked.b"),
+            ("b", "masked.a"),
+        ] {
+            cfg.rules
+                .insert(column.to_string(), rule.try_into().unwrap());
+        }
+        let transform = Transform::new(&cfg);
+        let mut dmls = vec![Insert::from(
+            MySqlParser::parse(
+                Rule::INSERT_STATEMENT,
+                "INSERT INTO `users` (`email`, `first_name`, `card`, `a`, `b`, `age`) VALUES ('ann@gmail.com', 'Ann', '4111111111111111', 1, 2, 30);",
+            )
+            .expect("Invalid input")
+            .next()
+            .expect("Unable to parse input"),
+        )];
+        transform.mask_dml_stmts(None, dmls.as_mut_slice()).unwrap();
+
+        assert_eq!(
+            dmls[0].values[0].to_string(),
+            "('ANN@example.test', 'ANN', '************1111', NULL, NULL, 30)"
+        );
+    }
+
+    #[test]
+    fn test_unmasks_fpe_rules() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.cipher = Some(Fpe::new(FpeAlgorithm::Ff31, "secret", 1_000));
+        cfg.rules.insert(
+            String::from("ssn"),
+            "fpe(value, 'digits', 'ssn')".try_into().unwrap(),
+        );
+        cfg.rules.insert(
+            String::from("email"),
+            "fpe(value, 'email')".try_into().unwrap(),
+        );
+        let sql = "INSERT INTO `users` (`id`, `ssn`, `email`, `password`) VALUES (1, '123-45-6789', 'ann.lee@example.com', 'hunter2');\nUPDATE `users` SET `ssn` = '987-65-4321' WHERE `id` = 1;\n";
+
+        let mut masked = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(sql.as_bytes(), &mut masked)
+            .unwrap();
+        let masked = String::from_utf8(masked).unwrap();
+        assert!(!masked.contains("123-45-6789") && !masked.contains("ann.lee@"));
+        assert!(masked.contains("@example.com"));
+
+        let mut unmasked = Vec::new();
+        Pipeline::new()
+            .rewriter(Unmask::new(&cfg))
+            .run(masked.as_bytes(), &mut unmasked)
+            .unwrap();
+        let unmasked = String::from_utf8(unmasked).unwrap();
+        assert!(unmasked.contains("(1, '123-45-6789', 'ann.lee@example.com', '"));
+        assert!(!unmasked.contains("hunter2"));
+        assert!(unmasked.contains("SET `ssn` = '987-65-4321'"));
+    }
+
+    #[test]
+    fn test_fails_on_values_fpe_cannot_encrypt() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.cipher = Some(Fpe::new(FpeAlgorithm::Ff1, "secret", 1_000));
+        cfg.rules.insert(
+            String::from("ssn"),
+            "fpe(value, 'digits')".try_into().unwrap(),
+        );
+
+        for sql in [
+            "INSERT INTO `users` (`id`, `ssn`) VALUES (1, '123');\n",
+            "UPDATE `users` SET `ssn` = '12-3' WHERE `id` = 1;\n",
+        ] {
+            let err = Pipeline::new()
+                .rewriter(Transform::new(&cfg))
+                .run(sql.as_bytes(), &mut Vec::new())
+                .unwrap_err();
+            assert!(format!("{err:#}").contains("`users`.`ssn`"), "{err:#}");
+        }
+    }
+
+    #[test]
+    fn test_reuses_vault_mappings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("masking.vault");
+        let cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        let mask = || {
+            let mut transform =
+                Transform::new(&cfg).with_vault(Vault::open(&path, "secret", 1_000).unwrap());
+            let mut out = Vec::new();
+            Pipeline::new()
+                .rewriter(&mut transform)
+                .run(
+                    "INSERT INTO `users` (`id`, `password`) VALUES (1, 'hunter2'), (2, 'hunter2');\n"
+                        .as_bytes(),
+                    &mut out,
+                )
+                .unwrap();
+            transform.save_vault().unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let first = mask();
+        assert_eq!(first, mask());
+        assert!(!first.contains("hunter2"));
+
+        let fake = first.split('\'').nth(1).unwrap();
+        let vault = Vault::open(&path, "secret", 1_000).unwrap();
+        assert_eq!(vault.lookup(None, fake), vec![("password", "hunter2")]);
+    }
+
+    #[test]
+    fn test_records_unique_fakes_as_written() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("masking.vault");
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.rules.insert(
+            String::from("nick"),
+            "substr(value, 1, 1)".try_into().unwrap(),
+        );
+        let mask = || {
+            let mut transform =
+                Transform::new(&cfg).with_vault(Vault::open(&path, "secret", 1_000).unwrap());
+            let mut out = Vec::new();
+            Pipeline::new()
+                .rewriter(&mut transform)
+                .run(
+                    "CREATE TABLE `users` (`nick` varchar(8), UNIQUE KEY `nick` (`nick`));
+INSERT INTO `users` (`nick`) VALUES ('ann'), ('al');
+"
+                    .as_bytes(),
+                    &mut out,
+                )
+                .unwrap();
+            transform.save_vault().unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let first = mask();
+        assert!(first.contains("VALUES ('a'), ('a2');"), "{first}");
+        assert_eq!(first, mask());
+
+        // The numbered fake is the one recorded, so it can be looked up.
+        let vault = Vault::open(&path, "secret", 1_000).unwrap();
+        assert_eq!(vault.lookup(None, "a"), vec![("nick", "ann")]);
+        assert_eq!(vault.lookup(None, "a2"), vec![("nick", "al")]);
+    }
+
+    #[test]
+    fn test_applies_strategies() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        for (column, rule) in [
+            ("nickname", "null()"),
+            ("surname", "null(keep_first(1, value))"),
+            ("phone", "redact()"),
+            ("initial", "keep_first(1, name::last(), '.')"),
+        ] {
+            cfg.rules
+                .insert(column.to_string(), rule.try_into().unwrap());
+        }
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "CREATE TABLE `users` (`nickname` varchar(20), `surname` varchar(20) NOT NULL, `phone` varchar(20), `initial` varchar(20));\nINSERT INTO `users` (`nickname`, `surname`, `phone`, `initial`) VALUES ('Annie', 'Lee', NULL, 'Ann');\n"
+                    .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let values = out.lines().find(|l| l.starts_with("INSERT")).unwrap();
+        assert!(values.contains("VALUES (NULL, 'L**', NULL, '"), "{values}");
+        assert!(values.ends_with(".');"), "{values}");
+    }
+
+    #[test]
+    fn test_masks_json_paths() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.rules
+            .insert("name".to_string(), "upper(value)".try_into().unwrap());
+        let json_rule = |path: &str, rule: &str| JsonRule {
+            path: JsonPath::parse(path).unwrap(),
+            rule: rule.try_into().unwrap(),
+        };
+        cfg.json.insert(
+            "profile".to_string(),
+            vec![
+                json_rule("$.email", "lower(masked.name) || '@example.test'"),
+                json_rule("$.addresses[*].street", "redact()"),
+                json_rule("$.age", "bucket(value, 10)"),
+            ],
+        );
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                r#"INSERT INTO `users` (`name`, `profile`) VALUES ('ann', '{"email":"ann@corp.com","nick":"O\'Brien \\"Ann\\"","age":37,"addresses":[{"street":"1 Main St","city":"Paris"},{"street":null}]}'), ('bob', 'not json');
+"#
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"INSERT INTO `users` (`name`, `profile`) VALUES ('ANN', '{"email":"ann@example.test","nick":"O\'Brien \\"Ann\\"","age":30,"addresses":[{"street":"***","city":"Paris"},{"street":null}]}'), ('BOB', 'not json');
+"#
+        );
+    }
+
+    #[test]
+    fn test_redacts_pii_inline() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.inline = Some(InlineConfig {
+            columns: vec![String::from("notes"), String::from("subject")],
+            detectors: None,
+            replacement: InlineReplacement::Label,
+        });
+        cfg.redactor =
+            Some(InlineRedactor::new(&cfg.patterns, None, InlineReplacement::Label).unwrap());
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                r"CREATE TABLE `tickets` (`id` int, `subject` varchar(16), `notes` text);
+INSERT INTO `tickets` (`id`, `subject`, `notes`) VALUES (1, 'Call 415-555-0132 now', 'Customer\'s email is ann@corp.com, phone 415-555-0132.'), (2, 'Refund', 'No PII here');
+"
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let values = out.lines().find(|l| l.starts_with("INSERT")).unwrap();
+        assert_eq!(
+            values,
+            r"INSERT INTO `tickets` (`id`, `subject`, `notes`) VALUES (1, 'Call [phone] now', 'Customer\'s email is [email], phone [phone].'), (2, 'Refund', 'No PII here');"
+        );
+    }
+
+    #[test]
+    fn test_fakes_in_the_row_locale() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.locale = Some(LocaleConfig {
+            column: Some(String::from("country")),
+            default: Some(String::from("pt_BR")),
+        });
+        for (column, rule) in [
+            ("zip", "address::zip()"),
+            ("phone", "contact::phone()"),
+            ("city", "address::city('ja_JP')"),
+        ] {
+            cfg.rules
+                .insert(column.to_string(), rule.try_into().unwrap());
+        }
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "INSERT INTO `customers` (`country`, `zip`, `phone`, `city`) VALUES ('DE', '1', '1', 'x'), ('Japan', '1', '1', 'x'), ('FR', '1', '1', 'x');
+"
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let row = r"\('([A-Za-z]+)', '([^']+)', '([^']+)', '([^']+)'\)";
+        let rows = Regex::new(row)
+            .unwrap()
+            .captures_iter(&out)
+            .map(|c| (c[2].to_string(), c[3].to_string(), c[4].to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3, "{out}");
+        for ((zip, phone, city), (zip_format, phone_prefix)) in rows.iter().zip([
+            (r"^\d{5}$", "+49 "),
+            (r"^\d{3}-\d{4}$", "0"),
+            (r"^\d{5}-\d{3}$", "+55 "),
+        ]) {
+            assert!(Regex::new(zip_format).unwrap().is_match(zip), "{out}");
+            assert!(phone.starts_with(phone_prefix), "{out}");
+            assert!(!city.is_ascii(), "{out}");
+        }
+    }
+
+    #[test]
+    fn test_keeps_unique_columns_unique() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        for (column, rule) in [
+            ("email", "substr(value, 1, 1) || '@example.test'"),
+            ("nick", "substr(value, 1, 1)"),
+            ("code", "bucket(value, 100)"),
+        ] {
+            cfg.rules
+                .insert(column.to_string(), rule.try_into().unwrap());
+        }
+        let mask = |dump: &str| {
+            let mut out = Vec::new();
+            Pipeline::new()
+                .rewriter(Transform::new(&cfg))
+                .run(dump.as_bytes(), &mut out)
+                .map(|_| String::from_utf8(out).unwrap())
+        };
+
+        let out = mask(
+            "CREATE TABLE `users` (`id` int, `email` varchar(16), `nick` varchar(8), PRIMARY KEY (`id`), UNIQUE KEY `email` (`email`));
+INSERT INTO `users` (`id`, `email`, `nick`) VALUES (1, 'ann@corp.com', 'ann'), (2, 'al@corp.com', 'al');
+INSERT INTO `users` (`id`, `email`, `nick`) VALUES (3, 'amy@corp.com', 'amy'), (4, NULL, NULL), (5, NULL, NULL);
+",
+        )
+        .unwrap();
+        let inserts = out
+            .lines()
+            .filter(|l| l.starts_with("INSERT"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inserts,
+            [
+                "INSERT INTO `users` (`id`, `email`, `nick`) VALUES (1, 'a@example.test', 'a'), (2, 'a2@example.test', 'a');",
+                "INSERT INTO `users` (`id`, `email`, `nick`) VALUES (3, 'a3@example.test', 'a'), (4, NULL, NULL), (5, NULL, NULL);",
+            ]
+        );
+
+        // Keys declared on the column itself count too.
+        let out = mask(
+            "CREATE TABLE `people` (`id` int NOT NULL PRIMARY KEY, `nick` varchar(8) UNIQUE);
+INSERT INTO `people` VALUES (1, 'ann'), (2, 'al');
+",
+        )
+        .unwrap();
+        assert!(out.contains("VALUES (1, 'a'), (2, 'a2');"), "{out}");
+
+        // Tables of the same name in other databases have their own columns and values.
+        let out = mask(
+            "USE `a`;
+CREATE TABLE `users` (`id` int, `email` varchar(16), UNIQUE KEY `email` (`email`));
+USE `b`;
+CREATE TABLE `users` (`email` varchar(16), `id` int, UNIQUE KEY `email` (`email`));
+USE `a`;
+INSERT INTO `users` VALUES (1, 'ann@corp.com');
+USE `b`;
+INSERT INTO `users` VALUES ('amy@corp.com', 1);
+",
+        )
+        .unwrap();
+        assert!(out.contains("VALUES (1, 'a@example.test');"), "{out}");
+        assert!(out.contains("VALUES ('a@example.test', 1);"), "{out}");
+
+        let codes = "CREATE TABLE `codes` (`code` tinyint unsigned NOT NULL, PRIMARY KEY (`code`));
+INSERT INTO `codes` (`code`) VALUES (1), (2);
+";
+        let err = mask(codes).unwrap_err();
+        assert!(
+            err.to_string().contains("`codes`.`code`") && err.to_string().contains("too small"),
+            "{err}"
+        );
+
+        // A borrowed transform, kept to save its vault afterwards, fails the same way.
+        let mut transform = Transform::new(&cfg);
+        let err = Pipeline::new()
+            .rewriter(&mut transform)
+            .run(codes.as_bytes(), Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("too small"), "{err}");
+    }
+
+    #[test]
+    fn test_numbers_unique_text_within_its_length() {
+        assert_eq!(with_suffix("ann", 2, None).as_deref(), Some("ann2"));
+        assert_eq!(
+            with_suffix("ann@example.test", 12, Some(16)).as_deref(),
+            Some("a12@example.test")
+        );
+        assert_eq!(with_suffix("a@example.test", 1000, Some(16)), None);
+    }
+
+    #[test]
+    fn test_masks_foreign_keys_like_their_parent() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.rules.insert(
+            "code".to_string(),
+            "upper(substr(unique::uuid_v4(), 1, 8))".try_into().unwrap(),
+        );
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "CREATE TABLE `customers` (`code` varchar(8) NOT NULL, `email` varchar(64), PRIMARY KEY (`code`), UNIQUE KEY `email` (`email`));
+CREATE TABLE `orders` (`id` int NOT NULL, `customer_code` varchar(8), `customer_email` varchar(64), PRIMARY KEY (`id`), CONSTRAINT `fk_code` FOREIGN KEY (`customer_code`) REFERENCES `customers` (`code`), CONSTRAINT `fk_email` FOREIGN KEY (`customer_email`) REFERENCES `customers` (`email`));
+CREATE TABLE `shipments` (`order_code` varchar(8), CONSTRAINT `fk_order` FOREIGN KEY (`order_code`) REFERENCES `orders` (`customer_code`));
+INSERT INTO `orders` (`id`, `customer_code`, `customer_email`) VALUES (1, 'ACME', 'ann@acme.com'), (2, 'ACME', 'ann@acme.com'), (3, NULL, NULL);
+INSERT INTO `customers` (`code`, `email`) VALUES ('ACME', 'ann@acme.com'), ('GLOBEX', 'bob@globex.com');
+INSERT INTO `shipments` (`order_code`) VALUES ('ACME'), ('GLOBEX');
+"
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let texts = |table: &str| {
+            let insert = out
+                .lines()
+                .find(|l| l.starts_with(&format!("INSERT INTO `{table}`")))
+                .unwrap();
+            Regex::new(r"'([^']*)'|NULL")
+                .unwrap()
+                .captures_iter(insert)
+                .map(|c| c.get(1).map(|m| m.as_str().to_string()))
+                .collect::<Vec<_>>()
+        };
+        let orders = texts("orders");
+        let customers = texts("customers");
+        let shipments = texts("shipments");
+
+        let (acme, acme_email) = (customers[0].clone().unwrap(), customers[1].clone().unwrap());
+        let globex = customers[2].clone().unwrap();
+        assert!(acme != "ACME" && acme != globex && acme_email != "ann@acme.com");
+        assert_eq!(
+            orders,
+            [
+                Some(acme.clone()),
+                Some(acme_email.clone()),
+                Some(acme.clone()),
+                Some(acme_email),
+                None,
+                None
+            ]
+        );
+        assert_eq!(shipments, [Some(acme), Some(globex)]);
+
+        // A table of the same name in another database has its own foreign keys.
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "USE `a`;
+CREATE TABLE `customers` (`code` varchar(8) NOT NULL, PRIMARY KEY (`code`));
+CREATE TABLE `orders` (`customer_code` varchar(8), CONSTRAINT `fk_code` FOREIGN KEY (`customer_code`) REFERENCES `customers` (`code`));
+USE `b`;
+CREATE TABLE `orders` (`customer_code` varchar(8));
+INSERT INTO `orders` (`customer_code`) VALUES ('ACME');
+"
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("VALUES ('ACME');"), "{out}");
+    }
+
+    #[test]
+    fn test_shifts_dates_per_entity() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        cfg.date_shift = Some(DateShiftConfig {
+            keys: vec![String::from("users.id"), String::from("user_id")],
+            max_days: 30,
+            secret_env: String::new(),
+        });
+        cfg.date_shifter = Some(DateShift::new(30, "secret"));
+        cfg.rules.insert(
+            String::from("birthday"),
+            "shift_date(value, first_name)".try_into().unwrap(),
+        );
+        let sql = "CREATE TABLE `users` (`id` int NOT NULL, `first_name` varchar(20), `signup` date, `birthday` date);
+CREATE TABLE `orders` (`id` int NOT NULL, `user_id` int, `created_at` datetime(3), `note` varchar(20));
+INSERT INTO `users` (`id`, `first_name`, `signup`, `birthday`) VALUES (1, 'Ann', '2024-01-31', '1990-06-15');
+INSERT INTO `orders` (`id`, `user_id`, `created_at`, `note`) VALUES (7, 1, '2024-03-01 10:20:30.125', '2024-03-01'), (8, NULL, '2024-03-01 10:20:30.125', NULL);
+";
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(sql.as_bytes(), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let offset = cfg.date_shifter.as_ref().unwrap().offset("1");
+        let shifted = |date: &str| {
+            let (year, month, day) = civil_from_days(
+                dates::days_from_civil(
+                    date[..4].parse().unwrap(),
+                    date[5..7].parse().unwrap(),
+                    date[8..10].parse().unwrap(),
+                ) + offset,
+            );
+            format!("{year:04}-{month:02}-{day:02}{}", &date[10..])
+        };
+        assert!(out.contains(&format!("(1, 'Ann', '{}', '", shifted("2024-01-31"))));
+        assert!(!out.contains("1990-06-15"));
+        assert!(out.contains(&format!(
+            "(7, 1, '{}', '2024-03-01'), (8, NULL, '2024-03-01 10:20:30.125', NULL)",
+            shifted("2024-03-01 10:20:30.125")
+        )));
+    }
+
+    #[test]
+    fn test_generalizes_numbers_for_the_column_type() {
+        let mut cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        for (column, rule) in [
+            ("age", "bucket(value, 10)"),
+            ("age_band", "bucket(value, 10)"),
+            ("salary", "bucket(value, 10000)"),
+            ("lat", "round(value, 2)"),
+            ("zip", "coarsen_zip(value)"),
+            ("score", "laplace(value, 5, 10)"),
+        ] {
+            cfg.rules
+                .insert(column.to_string(), rule.try_into().unwrap());
+        }
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "CREATE TABLE `people` (`age` tinyint unsigned, `age_band` varchar(10), `salary` decimal(10,2), `lat` double, `zip` char(5), `score` tinyint unsigned);
+INSERT INTO `people` (`age`, `age_band`, `salary`, `lat`, `zip`, `score`) VALUES (37, '37', 54321.99, 37.774929, '94107', 3);
+"
+                    .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let values = out.lines().find(|l| l.starts_with("INSERT")).unwrap();
+        assert!(
+            values.contains("VALUES (30, '30-39', 50000.00, 37.77, '94100', "),
+            "{values}"
+        );
+        let score = values.rsplit(", ").next().unwrap().trim_end_matches(");");
+        assert!(
+            (0..=13).contains(&score.parse::<i64>().unwrap()),
+            "{values}"
+        );
+    }
+
+    #[test]
+    fn test_masks_as_pipeline_stage() {
+        let cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "INSERT INTO `users` (`id`, `password`) VALUES (1, 'hunter2');\nUPDATE `users` SET `password` = 'swordfish' WHERE `id` = 1;"
+                    .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("INSERT INTO `users` (`id`, `password`) VALUES (1, '"));
+        assert!(!out.contains("hunter2"));
+        assert!(out.contains("UPDATE `users` SET `password` = '"));
+        assert!(!out.contains("swordfish"));
+    }
+
+    #[test]
+    fn test_fails_on_inserts_it_cannot_parse() {
+        let cfg = parse_masking_config("./tests/more.yaml").unwrap();
+        let err = Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "INSERT INTO `users` (`email`) VALUES (CONCAT('ann', '@corp.com'));\n".as_bytes(),
+                Vec::new(),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot be masked"), "{err}");
+    }
+
+    #[test]
+    fn test_masks_rows_matching_the_condition() {
+        let cfg = parse_masking_config("./tests/conditional.yaml").unwrap();
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(Transform::new(&cfg))
+            .run(
+                "INSERT INTO `users` (`email`, `country`, `street`) VALUES ('qa@ourcompany.com', 'DE', 'Hauptstr. 1'), ('ann@corp.com', 'US', '1 Main St');
+"
+                .as_bytes(),
+                &mut out,
+            )
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("('qa@ourcompany.com', 'DE', "), "{out}");
+        assert!(!out.contains("Hauptstr. 1"), "{out}");
+        assert!(!out.contains("ann@corp.com"), "{out}");
+        assert!(out.contains("'US', '1 Main St')"), "{out}");
+    }
+
+    #[test]
+    fn test_fails_when_a_kept_value_collides_with_a_fake() {
+        let mut cfg = parse_masking_config("./tests/conditional.yaml").unwrap();
+        cfg.rules.insert(
+            String::from("code"),
+            MaskingRule::parse("redact('k')", Some("code <> 'k'")).unwrap(),
+        );
+        let mask = |dump: &str| {
+            let mut out = Vec::new();
+            Pipeline::new()
+                .rewriter(Transform::new(&cfg))
+                .run(dump.as_bytes(), &mut out)
+                .map(|_| String::from_utf8(out).unwrap())
+        };
+
+        let err = mask(
+            "CREATE TABLE `codes` (`code` varchar(1) NOT NULL, PRIMARY KEY (`code`));
+INSERT INTO `codes` (`code`) VALUES ('a'), ('k');
+",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("`codes`.`code`") && err.to_string().contains("'k'"),
+            "{err}"
+        );
+
+        // Rows referencing a kept value keep it too.
+        let out = mask(
+            "CREATE TABLE `codes` (`code` varchar(1) NOT NULL, PRIMARY KEY (`code`));
+CREATE TABLE `uses` (`code` varchar(1), CONSTRAINT `fk` FOREIGN KEY (`code`) REFERENCES `codes` (`code`));
+INSERT INTO `codes` (`code`) VALUES ('k');
+INSERT INTO `uses` (`code`) VALUES ('k');
+",
+        )
+        .unwrap();
+        assert!(
+            out.contains("INSERT INTO `uses` (`code`) VALUES ('k');"),
+            "{out}"
+        );
     }
 }
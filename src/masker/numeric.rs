@@ -0,0 +1,198 @@
+//! Generalization and noise for numeric columns, with results fitted to the
+//! column's type so they can be loaded back.
+
+use rand::Rng;
+
+use crate::parser::types::DataType;
+use crate::value::Value;
+
+/// The number in a value, for numbers and numeric text.
+pub(crate) fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(value) => Some(*value as f64),
+        Value::UInt(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        Value::Bool(value) => Some(f64::from(u8::from(*value))),
+        other => other
+            .to_text()?
+            .trim()
+            .parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite()),
+    }
+}
+
+pub(crate) fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::TinyInt { .. }
+            | DataType::SmallInt { .. }
+            | DataType::MediumInt { .. }
+            | DataType::Int { .. }
+            | DataType::BigInt { .. }
+            | DataType::Decimal { .. }
+            | DataType::Float { .. }
+            | DataType::Double { .. }
+            | DataType::Year { .. }
+    )
+}
+
+/// The lower bound of the bucket of `width` that `x` falls in.
+pub(crate) fn bucket(x: f64, width: f64) -> f64 {
+    (x / width).floor() * width
+}
+
+/// A `low-high` label for the bucket starting at `low`, inclusive for whole numbers.
+pub(crate) fn bucket_label(low: f64, width: f64) -> String {
+    if low.fract() == 0.0 && width.fract() == 0.0 {
+        format!("{}-{}", low, low + width - 1.0)
+    } else {
+        format!("{}-{}", low, low + width)
+    }
+}
+
+/// Round `x` to `digits` decimals, or to tens, hundreds, ... when negative.
+pub(crate) fn round(x: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (x * factor).round() / factor
+}
+
+/// Laplace noise of `scale`, clamped to `±bound`.
+pub(crate) fn laplace(scale: f64, bound: f64) -> f64 {
+    let u: f64 = rand::thread_rng().gen_range(-0.5..0.5);
+    (-scale * u.signum() * (1.0 - 2.0 * u.abs()).ln()).clamp(-bound, bound)
+}
+
+/// Gaussian noise of `stddev`, clamped to `±bound`.
+pub(crate) fn gaussian(stddev: f64, bound: f64) -> f64 {
+    let mut rng = rand::thread_rng();
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+    (stddev * z).clamp(-bound, bound)
+}
+
+/// Keep the first `digits` digits of a zip code and zero the others.
+pub(crate) fn coarsen_zip(zip: &str, digits: usize) -> String {
+    let mut seen = 0;
+    zip.chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen > digits {
+                '0'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Fit `x` to the column type: integers are rounded and clamped to the range
+/// of their type, decimals to their precision and scale. Without a numeric
+/// type, `x` keeps as many decimals as `original` has.
+pub(crate) fn fit_number(x: f64, data_type: Option<&DataType>, original: &str) -> Value {
+    let integer = |min: f64, max: f64| {
+        let x = x.round().clamp(min, max);
+        if x > i64::MAX as f64 {
+            Value::UInt(x as u64)
+        } else {
+            Value::Int(x as i64)
+        }
+    };
+    let range = |bits: i32, unsigned: bool| {
+        if unsigned {
+            (0.0, 2f64.powi(bits) - 1.0)
+        } else {
+            (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1) - 1.0)
+        }
+    };
+
+    match data_type {
+        Some(DataType::TinyInt { unsigned, .. }) => {
+            let (min, max) = range(8, *unsigned);
+            integer(min, max)
+        }
+        Some(DataType::SmallInt { unsigned, .. }) => {
+            let (min, max) = range(16, *unsigned);
+            integer(min, max)
+        }
+        Some(DataType::MediumInt { unsigned, .. }) => {
+            let (min, max) = range(24, *unsigned);
+            integer(min, max)
+        }
+        Some(DataType::Int { unsigned, .. }) => {
+            let (min, max) = range(32, *unsigned);
+            integer(min, max)
+        }
+        Some(DataType::BigInt { unsigned, .. }) => {
+            let (min, max) = range(64, *unsigned);
+            integer(min, max)
+        }
+        Some(DataType::Year { .. }) => integer(1901.0, 2155.0),
+        Some(DataType::Decimal { m, d, unsigned, .. }) => {
+            let (m, d) = (m.unwrap_or(10), d.unwrap_or(0));
+            let max = 10f64.powi(m.saturating_sub(d) as i32) - 10f64.powi(-(d as i32));
+            let min = if *unsigned { 0.0 } else { -max };
+            Value::Decimal(format!("{:.*}", d as usize, x.clamp(min, max)))
+        }
+        Some(DataType::Float { d, unsigned, .. } | DataType::Double { d, unsigned, .. }) => {
+            let x = if *unsigned { x.max(0.0) } else { x };
+            Value::Float(d.map_or(x, |d| round(x, d as i32)))
+        }
+        _ => match original.trim().split_once('.') {
+            Some((_, decimals)) => Value::Decimal(format!("{:.*}", decimals.len(), x)),
+            None => Value::Int(x.round() as i64),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generalizes_numbers() {
+        assert_eq!(bucket(37.0, 10.0), 30.0);
+        assert_eq!(bucket(-1.0, 10.0), -10.0);
+        assert_eq!(bucket_label(30.0, 10.0), "30-39");
+        assert_eq!(bucket_label(2.5, 2.5), "2.5-5");
+        assert_eq!(round(37.7749, 2), 37.77);
+        assert_eq!(round(123_456.0, -3), 123_000.0);
+        assert_eq!(coarsen_zip("94107-1234", 3), "94100-0000");
+
+        for _ in 0..100 {
+            assert!(laplace(10.0, 5.0).abs() <= 5.0);
+            assert!(gaussian(10.0, 5.0).abs() <= 5.0);
+        }
+    }
+
+    #[test]
+    fn test_fits_numbers_to_the_column_type() {
+        let tinyint = DataType::TinyInt {
+            m: None,
+            unsigned: true,
+            zerofill: false,
+        };
+        let decimal = DataType::Decimal {
+            m: Some(5),
+            d: Some(2),
+            unsigned: false,
+            zerofill: false,
+        };
+
+        assert_eq!(fit_number(-3.4, Some(&tinyint), "1"), Value::Int(0));
+        assert_eq!(fit_number(300.0, Some(&tinyint), "1"), Value::Int(255));
+        assert_eq!(
+            fit_number(1234.567, Some(&decimal), "1.00"),
+            Value::Decimal(String::from("999.99"))
+        );
+        assert_eq!(
+            fit_number(12.345, None, "10.5"),
+            Value::Decimal(String::from("12.3"))
+        );
+        assert_eq!(fit_number(12.5, None, "10"), Value::Int(13));
+    }
+}
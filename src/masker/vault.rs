@@ -0,0 +1,249 @@
+//! An encrypted file recording the fake chosen for every original value, so
+//! fakes stay stable across runs and can be traced back by `vault lookup`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context};
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::ExtractResult;
+
+const MAGIC: &[u8] = b"SQLEXVAULT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Original to fake mappings, per column.
+type Mappings = BTreeMap<String, BTreeMap<String, String>>;
+
+pub struct Vault {
+    path: PathBuf,
+    /// Salt of the key, kept in the file after [`MAGIC`].
+    salt: [u8; SALT_LEN],
+    cipher: Aes256Gcm,
+    mappings: Mappings,
+    /// Fake to originals, per column.
+    reverse: HashMap<String, HashMap<String, Vec<String>>>,
+    changed: bool,
+}
+
+impl Debug for Vault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Vault")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Vault {
+    /// PBKDF2-HMAC-SHA256 rounds deriving the key from the secret.
+    pub const KDF_ROUNDS: u32 = 600_000;
+
+    /// Open the vault at `path`, or start an empty one if the file does not
+    /// exist yet. The key is derived from `secret` with `rounds` of
+    /// PBKDF2-HMAC-SHA256, [`Vault::KDF_ROUNDS`] outside of tests, and a
+    /// random salt stored in the file.
+    pub fn open(path: impl AsRef<Path>, secret: &str, rounds: u32) -> ExtractResult<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let (salt, cipher, mappings) = match fs::read(&path) {
+            Ok(bytes) => {
+                let Some(sealed) = bytes
+                    .strip_prefix(MAGIC)
+                    .filter(|s| s.len() > SALT_LEN + NONCE_LEN)
+                else {
+                    bail!("{} is not a sqlex vault", path.display());
+                };
+                let (salt, sealed) = sealed.split_at(SALT_LEN);
+                let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+                let salt: [u8; SALT_LEN] = salt.try_into()?;
+                let cipher = cipher(secret, &salt, rounds);
+                let plaintext = cipher
+                    .decrypt(Nonce::from_slice(nonce), ciphertext)
+                    .map_err(|_| {
+                        anyhow!("unable to decrypt {}, is the secret right?", path.display())
+                    })?;
+                (salt, cipher, serde_json::from_slice(&plaintext)?)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0u8; SALT_LEN];
+                rand::thread_rng().fill_bytes(&mut salt);
+                (salt, cipher(secret, &salt, rounds), Mappings::new())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to read {}", path.display()))
+            }
+        };
+
+        let mut reverse = HashMap::<String, HashMap<String, Vec<String>>>::new();
+        for (column, values) in &mappings {
+            let column_reverse = reverse.entry(column.clone()).or_default();
+            for (original, fake) in values {
+                column_reverse
+                    .entry(fake.clone())
+                    .or_default()
+                    .push(original.clone());
+            }
+        }
+
+        Ok(Self {
+            path,
+            salt,
+            cipher,
+            mappings,
+            reverse,
+            changed: false,
+        })
+    }
+
+    /// The fake recorded for `original` in `column`.
+    pub fn get(&self, column: &str, original: &str) -> Option<&str> {
+        self.mappings
+            .get(&column.to_lowercase())?
+            .get(original)
+            .map(String::as_str)
+    }
+
+    /// Whether `fake` is already recorded for an original other than `original`.
+    pub fn is_taken(&self, column: &str, fake: &str, original: &str) -> bool {
+        self.reverse
+            .get(&column.to_lowercase())
+            .and_then(|reverse| reverse.get(fake))
+            .is_some_and(|originals| originals.iter().any(|o| o != original))
+    }
+
+    pub fn insert(&mut self, column: &str, original: &str, fake: &str) {
+        let column = column.to_lowercase();
+        let previous = self
+            .mappings
+            .entry(column.clone())
+            .or_default()
+            .insert(original.to_string(), fake.to_string());
+        if previous.as_deref() == Some(fake) {
+            return;
+        }
+
+        let reverse = self.reverse.entry(column).or_default();
+        if let Some(previous) = previous {
+            if let Some(originals) = reverse.get_mut(&previous) {
+                originals.retain(|o| o != original);
+            }
+        }
+        reverse
+            .entry(fake.to_string())
+            .or_default()
+            .push(original.to_string());
+        self.changed = true;
+    }
+
+    /// The originals that were masked as `fake`, with their columns, in all
+    /// columns or only in `column`.
+    pub fn lookup(&self, column: Option<&str>, fake: &str) -> Vec<(&str, &str)> {
+        let column = column.map(str::to_lowercase);
+        let mut found = self
+            .reverse
+            .iter()
+            .filter(|(name, _)| column.as_ref().is_none_or(|column| column == *name))
+            .filter_map(|(name, reverse)| Some((name, reverse.get(fake)?)))
+            .flat_map(|(name, originals)| {
+                originals
+                    .iter()
+                    .map(move |original| (name.as_str(), original.as_str()))
+            })
+            .collect::<Vec<_>>();
+        found.sort_unstable();
+        found
+    }
+
+    /// Write the vault back if anything was recorded, replacing the file atomically.
+    pub fn save(&mut self) -> ExtractResult<()> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                serde_json::to_vec(&self.mappings)?.as_slice(),
+            )
+            .map_err(|_| anyhow!("unable to encrypt the vault"))?;
+
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, [MAGIC, &self.salt, &nonce, &ciphertext].concat())
+            .with_context(|| format!("unable to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("unable to write {}", self.path.display()))?;
+        self.changed = false;
+        Ok(())
+    }
+}
+
+/// The cipher keyed by `secret` stretched with `salt` over `rounds`.
+fn cipher(secret: &str, salt: &[u8], rounds: u32) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(secret.as_bytes(), salt, rounds, &mut key);
+    Aes256Gcm::new(&key.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_persists_encrypted_mappings() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("masking.vault");
+
+        let mut vault = Vault::open(&path, "secret", 1_000).unwrap();
+        vault.insert("Email", "ann@corp.com", "x@example.test");
+        vault.insert("email", "bob@corp.com", "y@example.test");
+        vault.insert("name", "Ann", "x@example.test");
+        assert!(vault.is_taken("email", "x@example.test", "bob@corp.com"));
+        assert!(!vault.is_taken("email", "x@example.test", "ann@corp.com"));
+        vault.save().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("ann@corp.com"));
+        assert!(Vault::open(&path, "wrong", 1_000).is_err());
+
+        // Every vault gets its own salt, kept when the vault is saved again.
+        let salt = &bytes[MAGIC.len()..MAGIC.len() + SALT_LEN];
+        let other = dir.path().join("other.vault");
+        let mut vault = Vault::open(&other, "secret", 1_000).unwrap();
+        vault.insert("email", "ann@corp.com", "x@example.test");
+        vault.save().unwrap();
+        assert_ne!(
+            &fs::read(&other).unwrap()[..MAGIC.len() + SALT_LEN],
+            &bytes[..MAGIC.len() + SALT_LEN]
+        );
+
+        let mut vault = Vault::open(&path, "secret", 1_000).unwrap();
+        vault.insert("email", "cy@corp.com", "z@example.test");
+        vault.save().unwrap();
+        assert_eq!(
+            &fs::read(&path).unwrap()[MAGIC.len()..MAGIC.len() + SALT_LEN],
+            salt
+        );
+
+        let vault = Vault::open(&path, "secret", 1_000).unwrap();
+        assert_eq!(vault.get("EMAIL", "ann@corp.com"), Some("x@example.test"));
+        assert_eq!(
+            vault.lookup(None, "x@example.test"),
+            vec![("email", "ann@corp.com"), ("name", "Ann")]
+        );
+        assert_eq!(
+            vault.lookup(Some("email"), "y@example.test"),
+            vec![("email", "bob@corp.com")]
+        );
+    }
+}
@@ -279,8 +279,6 @@ pub struct MySqlParser;
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     // #[test]
     // fn test_create_database() {
     //     let input = "CREATE DATABASE `test_db`;";
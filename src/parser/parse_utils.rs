@@ -5,3 +5,78 @@ use super::Rule;
 pub fn trim_str(s: Pair<'_, Rule>) -> String {
     s.as_str().trim_matches('`').trim_matches('\'').to_string()
 }
+
+/// Strip the surrounding quotes of a `STRING_LITERAL`, leaving escapes intact.
+pub fn literal_contents(s: &str) -> &str {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .unwrap_or(s)
+}
+
+/// Resolve the MySQL escape sequences in the contents of a string literal.
+pub fn unescape_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('0') => out.push('\0'),
+                Some('b') => out.push('\x08'),
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('Z') => out.push('\x1a'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            '\'' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                out.push('\'');
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+/// Escape a value so it can be placed inside a single-quoted string literal.
+pub fn escape_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '\0' => out.push_str("\\0"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\x1a' => out.push_str("\\Z"),
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_unescape_str() {
+        assert_eq!(unescape_str(r"O\'Brien"), "O'Brien");
+        assert_eq!(unescape_str("O''Brien"), "O'Brien");
+        assert_eq!(
+            unescape_str(r"line\nbreak \\ slash"),
+            "line\nbreak \\ slash"
+        );
+    }
+
+    #[test]
+    fn can_escape_str() {
+        assert_eq!(escape_str("O'Brien"), r"O\'Brien");
+        assert_eq!(unescape_str(&escape_str("a\\b\nc'd")), "a\\b\nc'd");
+    }
+}
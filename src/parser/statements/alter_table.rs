@@ -0,0 +1,135 @@
+use crate::parser::{
+    types::{Column, Index},
+    Rule,
+};
+use pest::iterators::Pair;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug, Clone)]
+pub enum AlterSpecification {
+    AddColumn(Column),
+    ModifyColumn(Column),
+    DropColumn(String),
+    AddIndex(Index),
+    DropIndex(String),
+}
+
+impl From<Pair<'_, Rule>> for AlterSpecification {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let uppercase = pair.as_str().to_ascii_uppercase();
+        let inner = pair
+            .into_inner()
+            .next()
+            .expect("Expected an alter specification");
+
+        match inner.as_rule() {
+            Rule::COLUMN_DEFINITION if uppercase.starts_with("MODIFY") => {
+                Self::ModifyColumn(Column::from(inner))
+            }
+            Rule::COLUMN_DEFINITION => Self::AddColumn(Column::from(inner)),
+            Rule::INDEX_DEFINITION => Self::AddIndex(Index::from(inner)),
+            _ => {
+                let name = inner.as_str().trim_matches('`').to_string();
+                if uppercase
+                    .split_ascii_whitespace()
+                    .nth(1)
+                    .is_some_and(|word| word == "INDEX")
+                {
+                    Self::DropIndex(name)
+                } else {
+                    Self::DropColumn(name)
+                }
+            }
+        }
+    }
+}
+
+impl Display for AlterSpecification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::AddColumn(column) => write!(f, "ADD COLUMN {column}"),
+            Self::ModifyColumn(column) => write!(f, "MODIFY COLUMN {column}"),
+            Self::DropColumn(name) => write!(f, "DROP COLUMN `{name}`"),
+            Self::AddIndex(index) => write!(f, "ADD {index}"),
+            Self::DropIndex(name) => write!(f, "DROP INDEX `{name}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlterTable {
+    pub table_name: String,
+    pub specifications: Vec<AlterSpecification>,
+}
+
+impl From<Pair<'_, Rule>> for AlterTable {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let mut inner = pair.into_inner();
+        let table_name = inner
+            .next()
+            .map(|p| p.as_str().trim_matches('`').to_string())
+            .expect("Expected a table name");
+
+        Self {
+            table_name,
+            specifications: inner.map(AlterSpecification::from).collect(),
+        }
+    }
+}
+
+impl Display for AlterTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "ALTER TABLE `{}` {}",
+            self.table_name,
+            self.specifications
+                .iter()
+                .map(|spec| spec.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::MySqlParser;
+    use pest::Parser;
+
+    #[test]
+    fn can_parse_alter_table() {
+        let alter_table = AlterTable::from(
+            MySqlParser::parse(
+                Rule::ALTER_TABLE,
+                "ALTER TABLE `users` ADD COLUMN `age` int NOT NULL, MODIFY `name` varchar(50), DROP `legacy`, ADD UNIQUE KEY `email` (`email`), DROP INDEX `old`",
+            )
+            .expect("Invalid input")
+            .next()
+            .expect("Unable to parse input"),
+        );
+
+        assert_eq!(alter_table.table_name, "users");
+        assert_eq!(alter_table.specifications.len(), 5);
+        assert!(
+            matches!(&alter_table.specifications[0], AlterSpecification::AddColumn(c) if c.name == "age" && !c.nullable)
+        );
+        assert!(
+            matches!(&alter_table.specifications[1], AlterSpecification::ModifyColumn(c) if c.name == "name")
+        );
+        assert!(
+            matches!(&alter_table.specifications[2], AlterSpecification::DropColumn(name) if name == "legacy")
+        );
+        assert!(
+            matches!(&alter_table.specifications[3], AlterSpecification::AddIndex(index) if index.unique)
+        );
+        assert!(
+            matches!(&alter_table.specifications[4], AlterSpecification::DropIndex(name) if name == "old")
+        );
+        assert_eq!(
+            alter_table.to_string(),
+            "ALTER TABLE `users` ADD COLUMN `age` INT NOT NULL, MODIFY COLUMN `name` VARCHAR (50), DROP COLUMN `legacy`, ADD UNIQUE KEY `email` (`email`), DROP INDEX `old`"
+        );
+    }
+}
@@ -30,10 +30,10 @@ impl From<Pair<'_, Rule>> for CreateDatabase {
             .next()
             .map(|p| {
                 p.into_inner()
-                    .map(|opt| DatabaseOption::from(opt))
+                    .map(DatabaseOption::from)
                     .collect::<Vec<DatabaseOption>>()
             })
-            .unwrap_or_else(|| Vec::new());
+            .unwrap_or_default();
 
         Self {
             name,
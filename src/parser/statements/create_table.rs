@@ -43,7 +43,25 @@ impl From<Pair<'_, Rule>> for CreateTable {
             .expect("table specs")
             .into_inner()
             .for_each(|spec| match spec.as_rule() {
-                Rule::COLUMN_DEFINITION => columns.push(Column::from(spec)),
+                Rule::COLUMN_DEFINITION => {
+                    let column = Column::from(spec.clone());
+                    let (primary, unique) = inline_keys(spec);
+                    if primary {
+                        primary_key = Some(PrimaryKey {
+                            name: None,
+                            column_names: vec![column.name.clone()],
+                        });
+                    }
+                    if unique {
+                        // MySQL names the index after the column.
+                        indexes.push(Index::new(
+                            column.name.clone(),
+                            vec![column.name.clone()],
+                            true,
+                        ));
+                    }
+                    columns.push(column);
+                }
                 Rule::PRIMARY_KEY => primary_key = Some(PrimaryKey::from(spec)),
                 Rule::FOREIGN_KEY => foreign_keys.push(ForeignKey::from(spec)),
                 Rule::INDEX_DEFINITION => indexes.push(Index::from(spec)),
@@ -67,6 +85,27 @@ impl From<Pair<'_, Rule>> for CreateTable {
     }
 }
 
+/// Whether a column definition declares itself the `PRIMARY KEY` and
+/// whether it is `UNIQUE`.
+fn inline_keys(column: Pair<'_, Rule>) -> (bool, bool) {
+    column
+        .into_inner()
+        .filter(|constraint| constraint.as_rule() == Rule::COLUMN_CONSTRAINT)
+        .map(|constraint| {
+            constraint
+                .as_str()
+                .split_ascii_whitespace()
+                .map(str::to_ascii_uppercase)
+                .collect::<Vec<_>>()
+        })
+        .fold((false, false), |(primary, unique), words| {
+            (
+                primary || words == ["PRIMARY", "KEY"],
+                unique || words.first().is_some_and(|word| word == "UNIQUE"),
+            )
+        })
+}
+
 impl Display for CreateTable {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         let mut table_specs = self
@@ -145,6 +184,36 @@ mod test {
         assert_eq!(create_table.options.len(), 4);
     }
 
+    #[test]
+    fn can_parse_inline_keys() {
+        let create_table = CreateTable::from(
+            MySqlParser::parse(
+                Rule::CREATE_TABLE,
+                "CREATE TABLE `users` (`id` int NOT NULL PRIMARY KEY, `email` varchar(20) UNIQUE, `code` char(2) unique key NOT NULL)",
+            )
+            .expect("Invalid input")
+            .next()
+            .expect("Unable to parse input"),
+        );
+
+        assert_eq!(
+            create_table.primary_key.map(|key| key.column_names),
+            Some(vec![String::from("id")])
+        );
+        assert_eq!(
+            create_table
+                .indexes
+                .iter()
+                .map(|index| (index.name.as_str(), index.columns.join(","), index.unique))
+                .collect::<Vec<_>>(),
+            [
+                ("email", String::from("email"), true),
+                ("code", String::from("code"), true)
+            ]
+        );
+        assert!(!create_table.columns[2].nullable);
+    }
+
     #[test]
     fn can_write_create_table() {
         assert_eq!(
@@ -249,6 +318,7 @@ mod test {
                     local_column_names: vec![String::from("ProductId")],
                     foreign_column_names: vec![String::from("Id")],
                     foreign_table_name: String::from("product"),
+                    on_delete: None,
                     on_update: None,
                 },],
                 indexes: vec![Index {
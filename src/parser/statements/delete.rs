@@ -0,0 +1,63 @@
+use crate::parser::{types::Where, Rule};
+use pest::iterators::Pair;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub table_name: String,
+    pub where_clauses: Vec<Where>,
+}
+
+impl From<Pair<'_, Rule>> for Delete {
+    fn from(pair: Pair<'_, Rule>) -> Self {
+        let mut inner = pair.into_inner();
+        let table_name = inner
+            .next()
+            .map(|p| p.as_str().trim_matches('`').to_string())
+            .expect("Expected a table name");
+
+        Self {
+            table_name,
+            where_clauses: inner.map(Where::from).collect(),
+        }
+    }
+}
+
+impl Display for Delete {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "DELETE FROM `{}`", self.table_name)?;
+        if !self.where_clauses.is_empty() {
+            write!(
+                f,
+                " WHERE {}",
+                self.where_clauses
+                    .iter()
+                    .map(|clause| clause.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" AND "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::MySqlParser;
+    use pest::Parser;
+
+    #[test]
+    fn can_parse_delete() {
+        let delete = Delete::from(
+            MySqlParser::parse(Rule::DELETE_STATEMENT, "DELETE FROM `users` WHERE id = 1")
+                .expect("Invalid input")
+                .next()
+                .expect("Unable to parse input"),
+        );
+
+        assert_eq!(delete.table_name, "users");
+        assert_eq!(delete.where_clauses.len(), 1);
+        assert_eq!(delete.to_string(), "DELETE FROM `users` WHERE id = 1");
+    }
+}
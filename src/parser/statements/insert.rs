@@ -28,10 +28,10 @@ impl From<Pair<'_, Rule>> for Insert {
 
             match element.as_rule() {
                 Rule::INSERT_PRIORITY => priority = Some(InsertPriority::from(element)),
-                Rule::INSERT_IGNORE => ignore = true,
+                Rule::INSERT_IGNORE => ignore = !element.as_str().is_empty(),
                 Rule::QUOTED_IDENTIFIER => table_name = Some(element.as_str().trim_matches('`').to_string()),
                 Rule::INSERT_COLUMNS => column_names = Some(element.into_inner().map(|p| p.as_str().trim_matches('`').to_string()).collect::<Vec<String>>()),
-                Rule::INSERT_VALUES_LIST => { values = element.into_inner().map(|p| InsertValues::from(p)).collect::<Vec<InsertValues>>(); break },
+                Rule::INSERT_VALUES_LIST => { values = element.into_inner().map(InsertValues::from).collect::<Vec<InsertValues>>(); break },
                 other => panic!("Expected INSERT_PRIORITY, INSERT_IGNORE, QUOTED_IDENTIFIER, INSERT_COLUMNS or INSERT_VALUES_LIST, not {other:?}"),
             }
         }
@@ -50,7 +50,7 @@ impl Display for Insert {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(
             f,
-            "INSERT{}{} INTO `{}` ({}) VALUES {}",
+            "INSERT{}{} INTO `{}` {}VALUES {}",
             if let Some(ref priority) = self.priority {
                 format!(" {priority}")
             } else {
@@ -58,11 +58,19 @@ impl Display for Insert {
             },
             if self.ignore { " IGNORE" } else { "" },
             self.table_name,
-            self.column_names
-                .iter()
-                .map(|name| format!("`{name}`"))
-                .collect::<Vec<String>>()
-                .join(", "),
+            // Without a column list, values are in the order of the table's columns.
+            if self.column_names.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "({}) ",
+                    self.column_names
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            },
             self.values
                 .iter()
                 .map(|value| format!("{value}"))
@@ -100,6 +108,58 @@ mod test {
         assert_eq!(insert.values.len(), 2);
     }
 
+    #[test]
+    fn can_parse_insert_with_escaped_quotes() {
+        let insert = Insert::from(
+            MySqlParser::parse(
+                Rule::INSERT_STATEMENT,
+                "INSERT INTO `my_table` (`col1`, `col2`) VALUES ('O\\'Brien', 'it''s');",
+            )
+            .expect("Invalid input")
+            .next()
+            .expect("Unable to parse input"),
+        );
+
+        assert_eq!(
+            insert.values[0].0,
+            vec![
+                InsertValue::Text {
+                    value: String::from("O\\'Brien")
+                },
+                InsertValue::Text {
+                    value: String::from("it''s")
+                },
+            ]
+        );
+        assert_eq!(
+            insert.to_string().as_str(),
+            "INSERT INTO `my_table` (`col1`, `col2`) VALUES ('O\\'Brien', 'it''s')"
+        );
+    }
+
+    #[test]
+    fn can_parse_mysqldump_literals() {
+        let sql = "INSERT INTO `files` VALUES (0x41ff,X'4142',b'0101',_binary 'a\\'b',_utf8mb4'x',1.5e3,-2E-1)";
+        let insert = Insert::from(
+            MySqlParser::parse(Rule::INSERT_STATEMENT, sql)
+                .expect("Invalid input")
+                .next()
+                .expect("Unable to parse input"),
+        );
+
+        assert_eq!(
+            insert.values[0].0[3],
+            InsertValue::Introduced {
+                charset: String::from("binary"),
+                value: String::from("a\\'b"),
+            }
+        );
+        assert_eq!(
+            insert.to_string().as_str(),
+            "INSERT INTO `files` VALUES (0x41ff, X'4142', b'0101', _binary 'a\\'b', _utf8mb4 'x', 1.5e3, -2E-1)"
+        );
+    }
+
     #[test]
     fn can_write_insert() {
         assert_eq!(
@@ -1,11 +1,17 @@
+mod alter_table;
 mod create_database;
 mod create_table;
+mod delete;
 mod drop_table;
 mod insert;
+mod statement;
 mod use_database;
 
+pub use alter_table::{AlterSpecification, AlterTable};
 pub use create_database::CreateDatabase;
 pub use create_table::CreateTable;
+pub use delete::Delete;
 pub use drop_table::DropTable;
 pub use insert::Insert;
+pub use statement::Statement;
 pub use use_database::UseDatabase;
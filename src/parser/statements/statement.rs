@@ -0,0 +1,132 @@
+use crate::dump::parse_statement;
+use crate::parser::{
+    statements::{AlterTable, CreateDatabase, CreateTable, Delete, DropTable, Insert, UseDatabase},
+    types::{Set, Update},
+    Rule,
+};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Any statement of a dump.
+#[derive(Debug, Clone)]
+pub enum Statement {
+    CreateDatabase(CreateDatabase),
+    UseDatabase(UseDatabase),
+    CreateTable(CreateTable),
+    AlterTable(AlterTable),
+    DropTable(DropTable),
+    Insert(Insert),
+    Update(Update),
+    Delete(Delete),
+    Set(Set),
+    /// A statement the grammar does not cover, kept verbatim.
+    Unknown(String),
+}
+
+impl Statement {
+    /// Parse a single statement, without its terminating `;`.
+    pub fn parse(sql: &str) -> Self {
+        let sql = sql.trim();
+        let keyword = sql
+            .split_ascii_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        let statement = match keyword.as_str() {
+            "CREATE" => parse_statement(Rule::CREATE_TABLE, sql)
+                .map(Self::CreateTable)
+                .or_else(|| parse_statement(Rule::CREATE_DATABASE, sql).map(Self::CreateDatabase)),
+            "USE" => parse_statement(Rule::USE_DATABASE, sql).map(Self::UseDatabase),
+            "ALTER" => parse_statement(Rule::ALTER_TABLE, sql).map(Self::AlterTable),
+            "DROP" => parse_statement(Rule::DROP_TABLE, sql).map(Self::DropTable),
+            "INSERT" => parse_statement(Rule::INSERT_STATEMENT, sql).map(Self::Insert),
+            "UPDATE" => parse_statement(Rule::UPDATE_STATEMENT, sql).map(Self::Update),
+            "DELETE" => parse_statement(Rule::DELETE_STATEMENT, sql).map(Self::Delete),
+            "SET" => parse_statement(Rule::SET_STATEMENT, sql).map(Self::Set),
+            _ => None,
+        };
+
+        statement.unwrap_or_else(|| Self::Unknown(sql.to_string()))
+    }
+
+    /// The table the statement acts on, if it targets a single table.
+    pub fn table_name(&self) -> Option<&str> {
+        match self {
+            Self::CreateTable(create_table) => Some(&create_table.name),
+            Self::AlterTable(alter_table) => Some(&alter_table.table_name),
+            Self::Insert(insert) => Some(&insert.table_name),
+            Self::Update(update) => Some(&update.table_name),
+            Self::Delete(delete) => Some(&delete.table_name),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::CreateDatabase(statement) => write!(f, "{statement}"),
+            Self::UseDatabase(statement) => write!(f, "{statement}"),
+            Self::CreateTable(statement) => write!(f, "{statement}"),
+            Self::AlterTable(statement) => write!(f, "{statement}"),
+            Self::DropTable(statement) => write!(f, "{statement}"),
+            Self::Insert(statement) => write!(f, "{statement}"),
+            // `Update` includes its own terminator.
+            Self::Update(statement) => write!(f, "{}", statement.to_string().trim_end_matches(';')),
+            Self::Delete(statement) => write!(f, "{statement}"),
+            Self::Set(statement) => write!(f, "{statement}"),
+            Self::Unknown(sql) => write!(f, "{sql}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_parse_every_statement() {
+        let statements = [
+            "CREATE DATABASE IF NOT EXISTS `shop`",
+            "USE `shop`",
+            "CREATE TABLE `users` (`id` int NOT NULL, PRIMARY KEY (`id`))",
+            "ALTER TABLE `users` DROP COLUMN `legacy`",
+            "DROP TABLE IF EXISTS `users`",
+            "INSERT INTO `users` (`id`) VALUES (1)",
+            "UPDATE `users` SET `name` = 'Ann' WHERE `id` = 1",
+            "DELETE FROM `users` WHERE id = 1",
+            "SET @a = 1",
+            "LOCK TABLES `users` WRITE",
+        ]
+        .map(Statement::parse);
+
+        assert!(matches!(statements[0], Statement::CreateDatabase(_)));
+        assert!(matches!(statements[1], Statement::UseDatabase(_)));
+        assert!(matches!(statements[2], Statement::CreateTable(_)));
+        assert!(matches!(statements[3], Statement::AlterTable(_)));
+        assert!(matches!(statements[4], Statement::DropTable(_)));
+        assert!(matches!(statements[5], Statement::Insert(_)));
+        assert!(matches!(statements[6], Statement::Update(_)));
+        assert!(matches!(statements[7], Statement::Delete(_)));
+        assert!(matches!(statements[8], Statement::Set(_)));
+        assert!(
+            matches!(&statements[9], Statement::Unknown(sql) if sql == "LOCK TABLES `users` WRITE")
+        );
+        assert_eq!(statements[6].table_name(), Some("users"));
+        assert_eq!(
+            statements[5].to_string(),
+            "INSERT INTO `users` (`id`) VALUES (1)"
+        );
+    }
+
+    #[test]
+    fn keeps_statements_the_grammar_only_partly_covers() {
+        let sql = "INSERT INTO `users` (`email`) VALUES ('a@corp.com') ON DUPLICATE KEY UPDATE email=VALUES(email)";
+        assert!(matches!(Statement::parse(sql), Statement::Unknown(raw) if raw == sql));
+
+        let partitioned = Statement::parse(
+            "CREATE TABLE `t` (`id` int NOT NULL) ENGINE=InnoDB\n/*!50100 PARTITION BY HASH (`id`) PARTITIONS 4 */",
+        );
+        assert!(matches!(partitioned, Statement::CreateTable(_)));
+    }
+}
@@ -132,7 +132,7 @@ impl From<Pairs<'_, Rule>> for Assignment {
                 acc
             });
 
-        Assignment { kv_pairs: kv_pairs }
+        Assignment { kv_pairs }
     }
 }
 
@@ -52,7 +52,6 @@ impl From<Pair<'_, Rule>> for Column {
                     ))
                 }
                 "AUTO_INCREMENT" => column.auto_increment = true,
-                "PRIMARY KEY" => todo!("support for PRIMARY KEY"),
                 s if s.starts_with("COMMENT") => {
                     column.comment = Some(
                         constraint
@@ -63,7 +62,9 @@ impl From<Pair<'_, Rule>> for Column {
                             .to_string(),
                     )
                 }
-                other => todo!("support for {}", other),
+                // Inline keys are part of the table, see `CreateTable`, and
+                // the character set and collation are not modeled.
+                _ => {}
             }
         }
 
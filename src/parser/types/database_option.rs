@@ -75,7 +75,7 @@ mod test {
         .next()
         .expect("Unable to parse input")
         .into_inner()
-        .map(|p| DatabaseOption::from(p))
+        .map(DatabaseOption::from)
         .collect::<Vec<DatabaseOption>>();
 
         match &database_options[0] {
@@ -1,4 +1,4 @@
-use crate::parser::Rule;
+use crate::parser::{parse_utils::literal_contents, Rule};
 use pest::iterators::Pair;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
@@ -6,9 +6,21 @@ use std::fmt::{Display, Formatter, Result as FmtResult};
 pub enum InsertValue {
     Null,
     Default,
-    Text { value: String },
-    Number { value: String },
-    Identifier { value: String },
+    Text {
+        value: String,
+    },
+    /// A string with a character set introducer, such as `_binary 'ab'`,
+    /// with `charset` holding the name after the `_`.
+    Introduced {
+        charset: String,
+        value: String,
+    },
+    Number {
+        value: String,
+    },
+    Identifier {
+        value: String,
+    },
 }
 
 impl From<Pair<'_, Rule>> for InsertValue {
@@ -24,9 +36,16 @@ impl From<Pair<'_, Rule>> for InsertValue {
 
             match inner.as_rule() {
                 Rule::STRING_LITERAL => Self::Text {
-                    value: inner.as_str().trim_matches('\'').to_string(),
+                    value: literal_contents(inner.as_str()).to_string(),
                 },
-                Rule::NUMBER => Self::Number {
+                Rule::INTRODUCED_STRING => {
+                    let mut inner = inner.into_inner();
+                    let charset = inner.next().unwrap().as_str()[1..].to_string();
+                    let value = literal_contents(inner.next().unwrap().as_str()).to_string();
+                    Self::Introduced { charset, value }
+                }
+                // Hex and bit-value literals are written back as they are, like `0x41`.
+                Rule::NUMBER | Rule::BIT_VALUE_LITERAL => Self::Number {
                     value: inner.as_str().to_string(),
                 },
                 Rule::IDENTIFIER => Self::Identifier {
@@ -44,6 +63,7 @@ impl Display for InsertValue {
             Self::Null => write!(f, "NULL"),
             Self::Default => write!(f, "DEFAULT"),
             Self::Text { value } => write!(f, "'{value}'"),
+            Self::Introduced { charset, value } => write!(f, "_{charset} '{value}'"),
             Self::Number { value } => write!(f, "{value}"),
             Self::Identifier { value } => write!(f, "{value}"),
         }
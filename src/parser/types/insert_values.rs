@@ -7,7 +7,7 @@ pub struct InsertValues(pub Vec<InsertValue>);
 
 impl From<Pair<'_, Rule>> for InsertValues {
     fn from(pair: Pair<'_, Rule>) -> Self {
-        Self(pair.into_inner().map(|p| InsertValue::from(p)).collect())
+        Self(pair.into_inner().map(InsertValue::from).collect())
     }
 }
 
@@ -20,7 +20,7 @@ impl Display for InsertValues {
                 write!(f, ", ")?;
             }
 
-            write!(f, "{}", value.to_string())?;
+            write!(f, "{}", value)?;
         }
 
         write!(f, ")")
@@ -136,10 +136,10 @@ mod test {
         .next()
         .expect("Unable to parse input")
         .into_inner()
-        .map(|p| TableOption::from(p))
+        .map(TableOption::from)
         .collect::<Vec<TableOption>>();
 
-        match table_options.get(0).unwrap() {
+        match table_options.first().unwrap() {
             TableOption::Engine { value } => assert_eq!(value.as_str(), "InnoDB"),
             _ => panic!("Expected engine"),
         }
@@ -32,7 +32,7 @@ impl From<Pair<'_, Rule>> for Update {
         let mut update_sets: Vec<Assignment> = Vec::new();
         let mut where_clauses: Vec<Where> = Vec::new();
 
-        while let Some(pair) = inner.next() {
+        for pair in inner {
             match pair.as_rule() {
                 Rule::ASSIGNMENT_CLAUSE => {
                     let set_clause = Assignment::from(pair);
@@ -55,25 +55,28 @@ impl From<Pair<'_, Rule>> for Update {
 
 impl Display for Update {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let table_name = self.table_name.clone();
-        let set_clauses = self.set_clauses.clone();
-        let where_clauses = self.where_clauses.clone();
-
         write!(
             f,
-            r#"UPDATE `{table_name}` SET {set_clauses} WHERE {where_clauses};"#,
-            table_name = table_name,
-            set_clauses = set_clauses
-                .into_iter()
+            "UPDATE `{}` SET {}",
+            self.table_name,
+            self.set_clauses
+                .iter()
                 .map(|a| a.to_string())
                 .collect::<Vec<String>>()
                 .join(","),
-            where_clauses = where_clauses
-                .into_iter()
-                .map(|a| a.to_string())
-                .collect::<Vec<String>>()
-                .join(" "),
-        )
+        )?;
+        if !self.where_clauses.is_empty() {
+            write!(
+                f,
+                " WHERE {}",
+                self.where_clauses
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" "),
+            )?;
+        }
+        write!(f, ";")
     }
 }
 
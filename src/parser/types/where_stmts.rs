@@ -11,11 +11,22 @@ pub struct Where {
 
 impl From<Pair<'_, Rule>> for Where {
     fn from(pair: Pair<'_, Rule>) -> Self {
+        Self::from_condition(pair.into_inner().next().unwrap())
+    }
+}
+
+impl Where {
+    /// The first comparison of a `CONDITION`. `IS NULL` and `IS NOT NULL`
+    /// have an empty value.
+    pub fn from_condition(pair: Pair<'_, Rule>) -> Self {
         let mut inner = pair.into_inner();
-        let mut inner_pair = inner.next().unwrap().into_inner();
-        let column = inner_pair.next().unwrap().as_str().to_string();
-        let operator = inner_pair.next().unwrap().as_str().to_string();
-        let value = inner_pair.next().unwrap().as_str().to_string();
+        let column = inner.next().unwrap().as_str().to_string();
+        let operator = inner.next().unwrap().as_str().to_string();
+        let value = inner
+            .next()
+            .filter(|pair| pair.as_rule() != Rule::LOGICAL_OPERATOR)
+            .map(|pair| pair.as_str().to_string())
+            .unwrap_or_default();
 
         Self {
             column,
@@ -27,7 +38,11 @@ impl From<Pair<'_, Rule>> for Where {
 
 impl Display for Where {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{} {} {}", self.column, self.operator, self.value)
+        write!(f, "{} {}", self.column, self.operator)?;
+        if !self.value.is_empty() {
+            write!(f, " {}", self.value)?;
+        }
+        Ok(())
     }
 }
 
@@ -66,4 +81,18 @@ mod tests {
         assert_eq!(where_stmt.operator, ">");
         assert_eq!(where_stmt.value, "1");
     }
+
+    #[test]
+    fn test_with_null_check_and_list() {
+        let sql = "WHERE deleted_at IS NOT NULL AND country IN ('DE', 'AT')";
+        let mut parsed = MySqlParser::parse(Rule::WHERE_CLAUSE, sql).unwrap();
+        let where_stmt = Where::from(parsed.next().unwrap());
+        assert_eq!(where_stmt.operator, "IS NOT NULL");
+        assert_eq!(where_stmt.value, "");
+        assert_eq!(where_stmt.to_string(), "deleted_at IS NOT NULL");
+
+        let sql = "WHERE id <= 10";
+        let mut parsed = MySqlParser::parse(Rule::WHERE_CLAUSE, sql).unwrap();
+        assert_eq!(Where::from(parsed.next().unwrap()).operator, "<=");
+    }
 }
@@ -0,0 +1,717 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::BufRead;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::dump::{parse_statement, StatementSplitter};
+use crate::parser::statements::{CreateTable, Insert, UseDatabase};
+use crate::parser::types::DataType;
+use crate::parser::Rule;
+use crate::value::Value;
+use crate::ExtractResult;
+
+mod hll;
+
+pub use self::hll::HyperLogLog;
+
+/// Longest cell printed in the table report before it is cut.
+const MAX_CELL_WIDTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileOptions {
+    /// Most frequent values reported per column.
+    pub top_k: usize,
+    /// Report values as SHA-256 digests instead of the values themselves.
+    pub hash_values: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableProfile {
+    pub database: Option<String>,
+    pub table: String,
+    pub rows: u64,
+    pub columns: Vec<ColumnProfile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnProfile {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: Option<String>,
+    pub nulls: u64,
+    pub null_ratio: f64,
+    /// HyperLogLog estimate of the number of distinct non-null values.
+    pub distinct: u64,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub avg_length: Option<f64>,
+    pub max_length: Option<usize>,
+    pub top_values: Vec<ValueCount>,
+    /// The kinds of values found, most frequent first.
+    pub value_types: Vec<TypeCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypeCount {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub count: u64,
+}
+
+type TableKey = (Option<String>, String);
+
+struct TableStats {
+    database: Option<String>,
+    table: String,
+    rows: u64,
+    columns: Vec<ColumnStats>,
+}
+
+struct ColumnStats {
+    name: String,
+    data_type: Option<DataType>,
+    nulls: u64,
+    values: u64,
+    distinct: HyperLogLog,
+    min: Option<Value>,
+    max: Option<Value>,
+    total_length: u64,
+    max_length: usize,
+    top_values: SpaceSaving,
+    value_types: HashMap<&'static str, u64>,
+}
+
+/// Streams the `INSERT` statements of a dump and collects statistics per column.
+pub struct Profiler {
+    options: ProfileOptions,
+    current_db: Option<String>,
+    tables: Vec<TableStats>,
+    index: HashMap<TableKey, usize>,
+}
+
+impl Profiler {
+    pub fn new(options: ProfileOptions) -> Self {
+        Self {
+            options,
+            current_db: None,
+            tables: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Collect the statistics of every `INSERT` in `reader`. Fails on an
+    /// `INSERT` the grammar cannot read, rather than leaving its rows out.
+    pub fn profile<R: BufRead>(&mut self, reader: R) -> ExtractResult<()> {
+        let mut splitter = StatementSplitter::new(reader);
+        while let Some(statement) = splitter.next_with_span() {
+            let (sql, span) = statement?;
+            self.handle_statement(&sql)
+                .with_context(|| format!("at byte {} of the dump", span.start))?;
+        }
+        Ok(())
+    }
+
+    fn handle_statement(&mut self, sql: &str) -> ExtractResult<()> {
+        let keyword = sql
+            .split_ascii_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match keyword.as_str() {
+            "USE" => {
+                if let Some(use_database) = parse_statement::<UseDatabase>(Rule::USE_DATABASE, sql)
+                {
+                    self.current_db = Some(use_database.name);
+                }
+            }
+            "CREATE" => {
+                if let Some(create_table) = parse_statement::<CreateTable>(Rule::CREATE_TABLE, sql)
+                {
+                    let columns = create_table
+                        .columns
+                        .iter()
+                        .map(|column| {
+                            ColumnStats::new(
+                                &column.name,
+                                Some(column.data_type.clone()),
+                                self.capacity(),
+                            )
+                        })
+                        .collect();
+                    self.add_table(&create_table.name, columns);
+                }
+            }
+            "INSERT" => match parse_statement::<Insert>(Rule::INSERT_STATEMENT, sql) {
+                Some(insert) => self.add_insert(&insert),
+                None => bail!(
+                    "unable to parse the INSERT starting with `{}`",
+                    sql.chars().take(80).collect::<String>()
+                ),
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Values tracked per column to find the most frequent ones.
+    fn capacity(&self) -> usize {
+        (self.options.top_k * 200).max(1_000)
+    }
+
+    fn add_table(&mut self, table: &str, columns: Vec<ColumnStats>) -> usize {
+        let key = (self.current_db.clone(), table.to_string());
+        if let Some(&i) = self.index.get(&key) {
+            return i;
+        }
+
+        self.tables.push(TableStats {
+            database: self.current_db.clone(),
+            table: table.to_string(),
+            rows: 0,
+            columns,
+        });
+        self.index.insert(key, self.tables.len() - 1);
+        self.tables.len() - 1
+    }
+
+    fn add_insert(&mut self, insert: &Insert) {
+        let columns = if insert.column_names.is_empty() {
+            let width = insert.values.first().map_or(0, |row| row.0.len());
+            (1..=width).map(|i| format!("column_{i}")).collect()
+        } else {
+            insert.column_names.clone()
+        };
+        let capacity = self.capacity();
+        let i = self.add_table(
+            &insert.table_name,
+            columns
+                .iter()
+                .map(|name| ColumnStats::new(name, None, capacity))
+                .collect(),
+        );
+        let table = &mut self.tables[i];
+
+        // Map the insert's columns onto the table's columns.
+        let positions = if insert.column_names.is_empty() {
+            (0..table.columns.len()).map(Some).collect::<Vec<_>>()
+        } else {
+            insert
+                .column_names
+                .iter()
+                .map(|name| table.columns.iter().position(|c| &c.name == name))
+                .collect()
+        };
+
+        for row in &insert.values {
+            table.rows += 1;
+            for (value, position) in row.0.iter().zip(&positions) {
+                if let Some(column) = position.map(|i| &mut table.columns[i]) {
+                    let value = Value::from_insert(value, column.data_type.as_ref());
+                    column.observe(value);
+                }
+            }
+        }
+    }
+
+    /// The profile of every table, in dump order.
+    pub fn finish(self) -> Vec<TableProfile> {
+        let options = self.options;
+
+        self.tables
+            .into_iter()
+            .map(|table| TableProfile {
+                database: table.database,
+                table: table.table,
+                rows: table.rows,
+                columns: table
+                    .columns
+                    .into_iter()
+                    .map(|column| column.finish(&options))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl ColumnStats {
+    fn new(name: &str, data_type: Option<DataType>, capacity: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type,
+            nulls: 0,
+            values: 0,
+            distinct: HyperLogLog::default(),
+            min: None,
+            max: None,
+            total_length: 0,
+            max_length: 0,
+            top_values: SpaceSaving::new(capacity),
+            value_types: HashMap::new(),
+        }
+    }
+
+    fn observe(&mut self, value: Value) {
+        let Some(text) = value.to_text() else {
+            self.nulls += 1;
+            return;
+        };
+
+        self.values += 1;
+        self.distinct.insert(&text);
+        let length = text.chars().count();
+        self.total_length += length as u64;
+        self.max_length = self.max_length.max(length);
+        *self.value_types.entry(value_type(&value)).or_default() += 1;
+
+        if !matches!(value, Value::Set(_) | Value::Json(_)) {
+            if self
+                .min
+                .as_ref()
+                .is_none_or(|min| compare(&value, min) == Ordering::Less)
+            {
+                self.min = Some(value.clone());
+            }
+            if self
+                .max
+                .as_ref()
+                .is_none_or(|max| compare(&value, max) == Ordering::Greater)
+            {
+                self.max = Some(value);
+            }
+        }
+        self.top_values.insert(text);
+    }
+
+    fn finish(self, options: &ProfileOptions) -> ColumnProfile {
+        let reported = |value: Value| match value.to_text() {
+            Some(text) if options.hash_values => Value::Text(hash_value(&text)),
+            _ => value,
+        };
+        let observed = self.nulls + self.values;
+        let mut value_types = self
+            .value_types
+            .into_iter()
+            .map(|(kind, count)| TypeCount { kind, count })
+            .collect::<Vec<_>>();
+        value_types.sort_by(|a, b| b.count.cmp(&a.count).then(a.kind.cmp(b.kind)));
+
+        ColumnProfile {
+            name: self.name,
+            data_type: self.data_type.map(|data_type| data_type.to_string()),
+            nulls: self.nulls,
+            null_ratio: if observed == 0 {
+                0.0
+            } else {
+                self.nulls as f64 / observed as f64
+            },
+            distinct: self.distinct.estimate().min(self.values),
+            min: self.min.map(reported),
+            max: self.max.map(reported),
+            avg_length: (self.values > 0).then(|| self.total_length as f64 / self.values as f64),
+            max_length: (self.values > 0).then_some(self.max_length),
+            top_values: self
+                .top_values
+                .top(options.top_k)
+                .into_iter()
+                .map(|(value, count)| ValueCount {
+                    value: if options.hash_values {
+                        hash_value(&value)
+                    } else {
+                        value
+                    },
+                    count,
+                })
+                .collect(),
+            value_types,
+        }
+    }
+}
+
+/// Approximate most frequent values using the Space-Saving algorithm, so
+/// memory stays bounded on high-cardinality columns. Counts are exact until
+/// more than `capacity` distinct values are seen, and upper bounds after that.
+struct SpaceSaving {
+    capacity: usize,
+    counts: HashMap<String, u64>,
+    /// Highest count evicted so far; new values start above it.
+    floor: u64,
+}
+
+impl SpaceSaving {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            counts: HashMap::new(),
+            floor: 0,
+        }
+    }
+
+    fn insert(&mut self, value: String) {
+        if let Some(count) = self.counts.get_mut(&value) {
+            *count += 1;
+            return;
+        }
+
+        // Evict the least frequent half at once rather than one value per insert.
+        if self.counts.len() >= self.capacity * 2 {
+            let mut counts = self.counts.values().copied().collect::<Vec<_>>();
+            let (_, &mut threshold, _) = counts.select_nth_unstable(self.capacity);
+            self.counts.retain(|_, count| *count > threshold);
+            self.floor = self.floor.max(threshold);
+        }
+        self.counts.insert(value, self.floor + 1);
+    }
+
+    fn top(self, k: usize) -> Vec<(String, u64)> {
+        let mut counts = self.counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(k);
+        counts
+    }
+}
+
+fn hash_value(value: &str) -> String {
+    let digest = Sha256::digest(value.as_bytes());
+    let hex = digest.iter().take(8).fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    });
+    format!("sha256:{hex}")
+}
+
+/// Orders numbers before text; numbers by value, text lexicographically.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (number(a), number(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.to_text().cmp(&b.to_text()),
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Bool(value) => Some(f64::from(u8::from(*value))),
+        Value::Int(value) => Some(*value as f64),
+        Value::UInt(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        Value::Decimal(value) => value.parse().ok(),
+        Value::Text(value) if INTEGER.is_match(value) || DECIMAL.is_match(value) => {
+            value.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+lazy_static! {
+    static ref INTEGER: Regex = Regex::new(r"^[+-]?\d+$").unwrap();
+    static ref DECIMAL: Regex = Regex::new(r"^[+-]?(\d+\.\d*|\.\d+)([eE][+-]?\d+)?$").unwrap();
+    static ref BOOLEAN: Regex = Regex::new(r"(?i)^(true|false)$").unwrap();
+    static ref DATE: Regex = Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+    static ref DATETIME: Regex =
+        Regex::new(r"^\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?$")
+            .unwrap();
+    static ref TIME: Regex = Regex::new(r"^-?\d{1,3}:\d{2}:\d{2}(\.\d+)?$").unwrap();
+    static ref UUID: Regex =
+        Regex::new(r"(?i)^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$").unwrap();
+    static ref EMAIL: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+    static ref URL: Regex = Regex::new(r"(?i)^[a-z][a-z0-9+.-]*://\S+$").unwrap();
+    static ref IPV4: Regex = Regex::new(r"^(\d{1,3}\.){3}\d{1,3}$").unwrap();
+}
+
+/// The kind of value, inferred from its content rather than the declared type.
+fn value_type(value: &Value) -> &'static str {
+    let text = match value {
+        Value::Null => return "null",
+        Value::Bool(_) => return "boolean",
+        Value::Int(_) | Value::UInt(_) => return "integer",
+        Value::Float(_) | Value::Decimal(_) => return "decimal",
+        Value::Set(_) => return "set",
+        Value::Json(_) => return "json",
+        Value::Text(text) => text.trim(),
+    };
+
+    match text {
+        "" => "empty",
+        _ if INTEGER.is_match(text) => "integer",
+        _ if DECIMAL.is_match(text) => "decimal",
+        _ if BOOLEAN.is_match(text) => "boolean",
+        _ if DATE.is_match(text) => "date",
+        _ if DATETIME.is_match(text) => "datetime",
+        _ if TIME.is_match(text) => "time",
+        _ if UUID.is_match(text) => "uuid",
+        _ if EMAIL.is_match(text) => "email",
+        _ if URL.is_match(text) => "url",
+        _ if IPV4.is_match(text) => "ipv4",
+        _ if (text.starts_with('{') || text.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(text).is_ok() =>
+        {
+            "json"
+        }
+        _ => "text",
+    }
+}
+
+/// Render the profiles as one aligned text table per table.
+pub fn render_table(profiles: &[TableProfile]) -> String {
+    let mut out = String::new();
+
+    for profile in profiles {
+        let name = match &profile.database {
+            Some(database) => format!("{database}.{}", profile.table),
+            None => profile.table.clone(),
+        };
+        let _ = writeln!(out, "{name} ({} rows)\n", profile.rows);
+
+        let mut rows = vec![[
+            "column",
+            "type",
+            "nulls",
+            "distinct",
+            "min",
+            "max",
+            "avg len",
+            "max len",
+            "types",
+            "top values",
+        ]
+        .map(String::from)];
+        for column in &profile.columns {
+            let text =
+                |value: &Option<Value>| value.as_ref().and_then(Value::to_text).unwrap_or_default();
+            rows.push([
+                column.name.clone(),
+                column.data_type.clone().unwrap_or_default(),
+                format!("{:.1}%", column.null_ratio * 100.0),
+                column.distinct.to_string(),
+                text(&column.min),
+                text(&column.max),
+                column
+                    .avg_length
+                    .map(|length| format!("{length:.1}"))
+                    .unwrap_or_default(),
+                column
+                    .max_length
+                    .map(|length| length.to_string())
+                    .unwrap_or_default(),
+                column
+                    .value_types
+                    .iter()
+                    .map(|kind| kind.kind)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                column
+                    .top_values
+                    .iter()
+                    .map(|top| format!("{} ({})", cut(&top.value), top.count))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ]);
+        }
+
+        let rows = rows
+            .into_iter()
+            .map(|row| row.map(|cell| cut(&cell)))
+            .collect::<Vec<_>>();
+        let widths = (0..rows[0].len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Single-line `value`, shortened to `MAX_CELL_WIDTH` characters.
+fn cut(value: &str) -> String {
+    let value = value.replace(['\n', '\r', '\t'], " ");
+    if value.chars().count() <= MAX_CELL_WIDTH {
+        value
+    } else {
+        let mut cut = value.chars().take(MAX_CELL_WIDTH - 1).collect::<String>();
+        cut.push('…');
+        cut
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"
+USE `shop`;
+CREATE TABLE `users` (
+  `id` int unsigned NOT NULL AUTO_INCREMENT,
+  `email` varchar(255) DEFAULT NULL,
+  `score` decimal(5,2) NOT NULL,
+  `note` text,
+  `born` date DEFAULT NULL,
+  `tags` set('new','vip') DEFAULT NULL,
+  `prefs` json DEFAULT NULL,
+  PRIMARY KEY (`id`)
+) ENGINE=InnoDB;
+INSERT INTO `users` VALUES (1,'ann@example.com','10.50','2024-01-02','1990-05-01','new,vip','{"lang":"de"}'),(2,NULL,'-3.00','hello',NULL,NULL,NULL),(3,'bob@example.com','7.25','hello','1985-12-24','vip','{"lang":"en"}');
+INSERT INTO `events` (`kind`) VALUES ('click'),('click'),('view');
+"#;
+
+    fn profile(hash_values: bool) -> Vec<TableProfile> {
+        let mut profiler = Profiler::new(ProfileOptions {
+            top_k: 2,
+            hash_values,
+        });
+        profiler.profile(DUMP.as_bytes()).unwrap();
+        profiler.finish()
+    }
+
+    #[test]
+    fn test_profiles_columns() {
+        let profiles = profile(false);
+        assert_eq!(profiles.len(), 2);
+
+        let users = &profiles[0];
+        assert_eq!(users.database.as_deref(), Some("shop"));
+        assert_eq!(users.rows, 3);
+
+        let id = &users.columns[0];
+        assert_eq!(id.data_type.as_deref(), Some("INT UNSIGNED"));
+        assert_eq!(id.distinct, 3);
+        assert_eq!(id.min, Some(Value::UInt(1)));
+        assert_eq!(id.max, Some(Value::UInt(3)));
+
+        let email = &users.columns[1];
+        assert_eq!(email.nulls, 1);
+        assert!((email.null_ratio - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(email.max_length, Some(15));
+        assert_eq!(email.value_types[0].kind, "email");
+
+        let score = &users.columns[2];
+        assert_eq!(score.min, Some(Value::Decimal(String::from("-3.00"))));
+        assert_eq!(score.max, Some(Value::Decimal(String::from("10.50"))));
+
+        let note = &users.columns[3];
+        assert_eq!(note.top_values[0].value, "hello");
+        assert_eq!(note.top_values[0].count, 2);
+        assert_eq!(
+            note.value_types
+                .iter()
+                .map(|kind| (kind.kind, kind.count))
+                .collect::<Vec<_>>(),
+            vec![("text", 2), ("date", 1)]
+        );
+
+        let events = &profiles[1];
+        assert_eq!(events.columns[0].name, "kind");
+        assert_eq!(events.columns[0].distinct, 2);
+    }
+
+    #[test]
+    fn test_hashes_reported_values() {
+        let profiles = profile(true);
+        let email = &profiles[0].columns[1];
+
+        let hashed = hash_value("ann@example.com");
+        assert!(hashed.starts_with("sha256:"));
+        assert_eq!(email.min, Some(Value::Text(hashed)));
+        assert_eq!(
+            profiles[0].columns[0].min,
+            Some(Value::Text(hash_value("1")))
+        );
+        assert_eq!(
+            profiles[0].columns[2].max,
+            Some(Value::Text(hash_value("10.50")))
+        );
+
+        // Numbers, decimals, dates, sets and JSON are hashed like text.
+        let hashed = |value: &Option<Value>| {
+            value.as_ref().is_none_or(
+                |value| matches!(value, Value::Text(text) if text.starts_with("sha256:")),
+            )
+        };
+        for column in &profiles[0].columns {
+            assert!(
+                hashed(&column.min) && hashed(&column.max),
+                "{}",
+                column.name
+            );
+            assert!(!column.top_values.is_empty(), "{}", column.name);
+            assert!(
+                column
+                    .top_values
+                    .iter()
+                    .all(|top| top.value.starts_with("sha256:")),
+                "{}",
+                column.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_fails_on_unreadable_insert() {
+        let mut profiler = Profiler::new(ProfileOptions {
+            top_k: 2,
+            hash_values: false,
+        });
+        let err = profiler
+            .profile("USE `shop`;\nINSERT INTO `users` VALUES (NOW());\n".as_bytes())
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("at byte 12"), "{err:#}");
+    }
+
+    #[test]
+    fn test_space_saving_keeps_frequent_values() {
+        let mut top = SpaceSaving::new(4);
+        for i in 0..1_000 {
+            top.insert(format!("unique-{i}"));
+            if i % 3 == 0 {
+                top.insert(String::from("frequent"));
+            }
+        }
+
+        let top = top.top(1);
+        assert_eq!(top[0].0, "frequent");
+        assert!(top[0].1 >= 334);
+    }
+
+    #[test]
+    fn test_renders_table() {
+        let table = render_table(&profile(false));
+
+        assert!(table.starts_with("shop.users (3 rows)\n\ncolumn"));
+        assert!(table.contains("hello (2), 2024-01-02 (1)"));
+        assert!(table.contains("33.3%"));
+    }
+}
@@ -0,0 +1,643 @@
+//! Visitors and rewriters over dump statements, and a pipeline to chain them.
+//!
+//! Implement [`StatementVisitor`] to inspect a dump or [`StatementRewriter`] to
+//! change it. Every hook has a default, so implementations only override the
+//! parts they care about; overriding a hook that walks into children (such as
+//! [`StatementRewriter::rewrite_insert`]) replaces that walk unless it calls the
+//! matching `walk_*` function.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use crate::dump::DumpReader;
+use crate::parser::statements::{CreateTable, Insert, Statement};
+use crate::parser::types::{AssignmentValue, Column, InsertValue, InsertValues, Update};
+use crate::ExtractResult;
+
+/// Where a statement was found in the dump.
+#[derive(Debug, Clone, Default)]
+pub struct StatementContext {
+    /// The database selected by the last `USE` statement.
+    pub database: Option<String>,
+}
+
+/// Inspects statements without changing them.
+#[allow(unused_variables)]
+pub trait StatementVisitor {
+    fn visit_statement(&mut self, ctx: &StatementContext, statement: &Statement) {
+        walk_statement(self, ctx, statement);
+    }
+
+    fn visit_create_table(&mut self, ctx: &StatementContext, create_table: &CreateTable) {
+        for column in &create_table.columns {
+            self.visit_column(ctx, &create_table.name, column);
+        }
+    }
+
+    fn visit_column(&mut self, ctx: &StatementContext, table: &str, column: &Column) {}
+
+    fn visit_insert(&mut self, ctx: &StatementContext, insert: &Insert) {
+        for row in &insert.values {
+            self.visit_row(ctx, &insert.table_name, &insert.column_names, row);
+        }
+    }
+
+    /// `columns` is empty when neither the `INSERT` nor an earlier `CREATE TABLE` names them.
+    fn visit_row(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        columns: &[String],
+        row: &InsertValues,
+    ) {
+        for (i, value) in row.0.iter().enumerate() {
+            self.visit_value(ctx, table, columns.get(i).map(String::as_str), value);
+        }
+    }
+
+    fn visit_value(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: Option<&str>,
+        value: &InsertValue,
+    ) {
+    }
+
+    fn visit_update(&mut self, ctx: &StatementContext, update: &Update) {
+        for pair in update
+            .set_clauses
+            .iter()
+            .flat_map(|clause| &clause.kv_pairs)
+        {
+            self.visit_assignment(ctx, &update.table_name, &pair.key.to_string(), &pair.value);
+        }
+    }
+
+    fn visit_assignment(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: &str,
+        value: &AssignmentValue,
+    ) {
+    }
+}
+
+/// Dispatch `statement` to the visitor's hook for its kind.
+pub fn walk_statement<V: StatementVisitor + ?Sized>(
+    visitor: &mut V,
+    ctx: &StatementContext,
+    statement: &Statement,
+) {
+    match statement {
+        Statement::CreateTable(create_table) => visitor.visit_create_table(ctx, create_table),
+        Statement::Insert(insert) => visitor.visit_insert(ctx, insert),
+        Statement::Update(update) => visitor.visit_update(ctx, update),
+        _ => {}
+    }
+}
+
+/// Changes statements in place.
+#[allow(unused_variables)]
+pub trait StatementRewriter {
+    /// Called after every statement, an error stops [`Pipeline::run`].
+    fn check(&mut self) -> ExtractResult<()> {
+        Ok(())
+    }
+
+    /// Return `false` to drop the statement from the output.
+    fn rewrite_statement(&mut self, ctx: &StatementContext, statement: &mut Statement) -> bool {
+        walk_statement_mut(self, ctx, statement)
+    }
+
+    fn rewrite_create_table(&mut self, ctx: &StatementContext, create_table: &mut CreateTable) {
+        walk_create_table_mut(self, ctx, create_table);
+    }
+
+    /// Return `false` to drop the column from `CREATE TABLE`, `INSERT` and `UPDATE` statements.
+    fn retain_column(&mut self, ctx: &StatementContext, table: &str, column: &str) -> bool {
+        true
+    }
+
+    fn rewrite_column(&mut self, ctx: &StatementContext, table: &str, column: &mut Column) {}
+
+    fn rewrite_insert(&mut self, ctx: &StatementContext, insert: &mut Insert) {
+        walk_insert_mut(self, ctx, insert);
+    }
+
+    /// Return `false` to drop the row.
+    fn rewrite_row(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        columns: &[String],
+        row: &mut InsertValues,
+    ) -> bool {
+        for (i, value) in row.0.iter_mut().enumerate() {
+            self.rewrite_value(ctx, table, columns.get(i).map(String::as_str), value);
+        }
+        true
+    }
+
+    fn rewrite_value(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: Option<&str>,
+        value: &mut InsertValue,
+    ) {
+    }
+
+    fn rewrite_update(&mut self, ctx: &StatementContext, update: &mut Update) {
+        walk_update_mut(self, ctx, update);
+    }
+
+    fn rewrite_assignment(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: &str,
+        value: &mut AssignmentValue,
+    ) {
+    }
+}
+
+/// Dispatch `statement` to the rewriter's hook for its kind. Returns `false`
+/// when nothing is left of it, such as an `INSERT` whose rows were all dropped.
+pub fn walk_statement_mut<R: StatementRewriter + ?Sized>(
+    rewriter: &mut R,
+    ctx: &StatementContext,
+    statement: &mut Statement,
+) -> bool {
+    match statement {
+        Statement::CreateTable(create_table) => {
+            rewriter.rewrite_create_table(ctx, create_table);
+            !create_table.columns.is_empty()
+        }
+        Statement::Insert(insert) => {
+            rewriter.rewrite_insert(ctx, insert);
+            !insert.values.is_empty() && insert.values.iter().all(|row| !row.0.is_empty())
+        }
+        Statement::Update(update) => {
+            rewriter.rewrite_update(ctx, update);
+            update
+                .set_clauses
+                .iter()
+                .any(|clause| !clause.kv_pairs.is_empty())
+        }
+        _ => true,
+    }
+}
+
+/// Drop the columns the rewriter does not retain, along with the keys and
+/// indexes that use them, then rewrite the remaining columns.
+pub fn walk_create_table_mut<R: StatementRewriter + ?Sized>(
+    rewriter: &mut R,
+    ctx: &StatementContext,
+    create_table: &mut CreateTable,
+) {
+    let table = create_table.name.clone();
+    let dropped = create_table
+        .columns
+        .iter()
+        .filter(|column| !rewriter.retain_column(ctx, &table, &column.name))
+        .map(|column| column.name.clone())
+        .collect::<Vec<_>>();
+
+    if !dropped.is_empty() {
+        create_table
+            .columns
+            .retain(|column| !dropped.contains(&column.name));
+        if let Some(primary_key) = &mut create_table.primary_key {
+            primary_key
+                .column_names
+                .retain(|name| !dropped.contains(name));
+            if primary_key.column_names.is_empty() {
+                create_table.primary_key = None;
+            }
+        }
+        for index in &mut create_table.indexes {
+            index.columns.retain(|name| !dropped.contains(name));
+        }
+        create_table
+            .indexes
+            .retain(|index| !index.columns.is_empty());
+        create_table.foreign_keys.retain(|key| {
+            !key.local_column_names
+                .iter()
+                .any(|name| dropped.contains(name))
+        });
+    }
+
+    for column in &mut create_table.columns {
+        rewriter.rewrite_column(ctx, &table, column);
+    }
+}
+
+/// Drop the columns the rewriter does not retain, then rewrite each row,
+/// dropping the rows it rejects.
+pub fn walk_insert_mut<R: StatementRewriter + ?Sized>(
+    rewriter: &mut R,
+    ctx: &StatementContext,
+    insert: &mut Insert,
+) {
+    let table = insert.table_name.clone();
+    let retained = insert
+        .column_names
+        .iter()
+        .map(|column| rewriter.retain_column(ctx, &table, column))
+        .collect::<Vec<_>>();
+
+    if retained.contains(&false) {
+        let mut keep = retained.iter();
+        insert
+            .column_names
+            .retain(|_| *keep.next().unwrap_or(&true));
+        for row in &mut insert.values {
+            let mut keep = retained.iter();
+            row.0.retain(|_| *keep.next().unwrap_or(&true));
+        }
+    }
+
+    let columns = insert.column_names.clone();
+    insert
+        .values
+        .retain_mut(|row| rewriter.rewrite_row(ctx, &table, &columns, row));
+}
+
+/// Drop the assignments to columns the rewriter does not retain, then rewrite the rest.
+pub fn walk_update_mut<R: StatementRewriter + ?Sized>(
+    rewriter: &mut R,
+    ctx: &StatementContext,
+    update: &mut Update,
+) {
+    let table = update.table_name.clone();
+    for clause in &mut update.set_clauses {
+        clause
+            .kv_pairs
+            .retain(|pair| rewriter.retain_column(ctx, &table, &pair.key.to_string()));
+        for pair in &mut clause.kv_pairs {
+            rewriter.rewrite_assignment(ctx, &table, &pair.key.to_string(), &mut pair.value);
+        }
+    }
+    update
+        .set_clauses
+        .retain(|clause| !clause.kv_pairs.is_empty());
+}
+
+impl<V: StatementVisitor + ?Sized> StatementVisitor for &mut V {
+    fn visit_statement(&mut self, ctx: &StatementContext, statement: &Statement) {
+        (**self).visit_statement(ctx, statement);
+    }
+
+    fn visit_create_table(&mut self, ctx: &StatementContext, create_table: &CreateTable) {
+        (**self).visit_create_table(ctx, create_table);
+    }
+
+    fn visit_column(&mut self, ctx: &StatementContext, table: &str, column: &Column) {
+        (**self).visit_column(ctx, table, column);
+    }
+
+    fn visit_insert(&mut self, ctx: &StatementContext, insert: &Insert) {
+        (**self).visit_insert(ctx, insert);
+    }
+
+    fn visit_row(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        columns: &[String],
+        row: &InsertValues,
+    ) {
+        (**self).visit_row(ctx, table, columns, row);
+    }
+
+    fn visit_value(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: Option<&str>,
+        value: &InsertValue,
+    ) {
+        (**self).visit_value(ctx, table, column, value);
+    }
+
+    fn visit_update(&mut self, ctx: &StatementContext, update: &Update) {
+        (**self).visit_update(ctx, update);
+    }
+
+    fn visit_assignment(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: &str,
+        value: &AssignmentValue,
+    ) {
+        (**self).visit_assignment(ctx, table, column, value);
+    }
+}
+
+impl<R: StatementRewriter + ?Sized> StatementRewriter for &mut R {
+    fn check(&mut self) -> ExtractResult<()> {
+        (**self).check()
+    }
+
+    fn rewrite_statement(&mut self, ctx: &StatementContext, statement: &mut Statement) -> bool {
+        (**self).rewrite_statement(ctx, statement)
+    }
+
+    fn rewrite_create_table(&mut self, ctx: &StatementContext, create_table: &mut CreateTable) {
+        (**self).rewrite_create_table(ctx, create_table);
+    }
+
+    fn retain_column(&mut self, ctx: &StatementContext, table: &str, column: &str) -> bool {
+        (**self).retain_column(ctx, table, column)
+    }
+
+    fn rewrite_column(&mut self, ctx: &StatementContext, table: &str, column: &mut Column) {
+        (**self).rewrite_column(ctx, table, column);
+    }
+
+    fn rewrite_insert(&mut self, ctx: &StatementContext, insert: &mut Insert) {
+        (**self).rewrite_insert(ctx, insert);
+    }
+
+    fn rewrite_row(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        columns: &[String],
+        row: &mut InsertValues,
+    ) -> bool {
+        (**self).rewrite_row(ctx, table, columns, row)
+    }
+
+    fn rewrite_value(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: Option<&str>,
+        value: &mut InsertValue,
+    ) {
+        (**self).rewrite_value(ctx, table, column, value);
+    }
+
+    fn rewrite_update(&mut self, ctx: &StatementContext, update: &mut Update) {
+        (**self).rewrite_update(ctx, update);
+    }
+
+    fn rewrite_assignment(
+        &mut self,
+        ctx: &StatementContext,
+        table: &str,
+        column: &str,
+        value: &mut AssignmentValue,
+    ) {
+        (**self).rewrite_assignment(ctx, table, column, value);
+    }
+}
+
+/// Runs a visitor as a pipeline stage.
+struct VisitorStage<V>(V);
+
+impl<V: StatementVisitor> StatementRewriter for VisitorStage<V> {
+    fn rewrite_statement(&mut self, ctx: &StatementContext, statement: &mut Statement) -> bool {
+        self.0.visit_statement(ctx, statement);
+        true
+    }
+}
+
+/// Applies a chain of rewriters to every statement of a dump, in order.
+///
+/// `INSERT` statements without a column list get the column names of the
+/// table's `CREATE TABLE` before the first stage runs, so every stage can match
+/// values by column.
+#[derive(Default)]
+pub struct Pipeline<'a> {
+    stages: Vec<Box<dyn StatementRewriter + 'a>>,
+    context: StatementContext,
+    tables: HashMap<(Option<String>, String), Vec<String>>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rewriter to the end of the chain.
+    pub fn rewriter(mut self, rewriter: impl StatementRewriter + 'a) -> Self {
+        self.stages.push(Box::new(rewriter));
+        self
+    }
+
+    /// Add a visitor to the end of the chain; it sees statements as rewritten so far.
+    pub fn visitor(mut self, visitor: impl StatementVisitor + 'a) -> Self {
+        self.stages.push(Box::new(VisitorStage(visitor)));
+        self
+    }
+
+    /// Run every stage on `statement`, returning `None` when a stage dropped it.
+    pub fn rewrite(&mut self, database: Option<String>, statement: Statement) -> Option<Statement> {
+        self.rewrite_tracked(database, statement)
+            .map(|(statement, _)| statement)
+    }
+
+    /// Like [`Self::rewrite`], also telling whether a stage changed the statement.
+    fn rewrite_tracked(
+        &mut self,
+        database: Option<String>,
+        mut statement: Statement,
+    ) -> Option<(Statement, bool)> {
+        self.context.database = database;
+
+        match &mut statement {
+            Statement::CreateTable(create_table) => {
+                self.tables.insert(
+                    (self.context.database.clone(), create_table.name.clone()),
+                    create_table
+                        .columns
+                        .iter()
+                        .map(|column| column.name.clone())
+                        .collect(),
+                );
+            }
+            Statement::Insert(insert) if insert.column_names.is_empty() => {
+                if let Some(columns) = self
+                    .tables
+                    .get(&(self.context.database.clone(), insert.table_name.clone()))
+                {
+                    insert.column_names = columns.clone();
+                }
+            }
+            _ => {}
+        }
+
+        let unchanged = statement.to_string();
+        for stage in &mut self.stages {
+            if !stage.rewrite_statement(&self.context, &mut statement) {
+                return None;
+            }
+        }
+        let changed = statement.to_string() != unchanged;
+        Some((statement, changed))
+    }
+
+    /// Rewrite a whole dump, writing each remaining statement followed by
+    /// the delimiter that ended it. Statements no stage changed are written
+    /// as they were read, so nothing the grammar does not model is lost.
+    pub fn run<R: Read, W: Write>(&mut self, reader: R, mut writer: W) -> ExtractResult<()> {
+        for entry in DumpReader::new(reader) {
+            let entry = entry?;
+            let statement = self.rewrite_tracked(entry.database, entry.statement);
+            for stage in &mut self.stages {
+                stage.check()?;
+            }
+            match statement {
+                Some((statement, true)) => writeln!(writer, "{statement}{}", entry.terminator)?,
+                Some((_, false)) => writeln!(writer, "{}{}", entry.sql, entry.terminator)?,
+                None => {}
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = "USE `app`;
+CREATE TABLE `users` (
+  `id` int NOT NULL,
+  `tenant` varchar(20) NOT NULL,
+  `ssn` varchar(11) DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `ssn` (`ssn`)
+);
+INSERT INTO `users` VALUES (1,'acme','123-45-6789'),(2,'globex','987-65-4321');
+UPDATE `users` SET `ssn` = '000-00-0000' WHERE `id` = 1;
+UPDATE `users` SET `tenant` = 'acme' WHERE `id` = 2;
+";
+
+    /// Renames the `acme` tenant.
+    struct RenameTenant;
+
+    impl StatementRewriter for RenameTenant {
+        fn rewrite_value(
+            &mut self,
+            _ctx: &StatementContext,
+            _table: &str,
+            column: Option<&str>,
+            value: &mut InsertValue,
+        ) {
+            if column == Some("tenant") && value.to_string() == "'acme'" {
+                *value = InsertValue::Text {
+                    value: String::from("initech"),
+                };
+            }
+        }
+
+        fn rewrite_assignment(
+            &mut self,
+            _ctx: &StatementContext,
+            _table: &str,
+            column: &str,
+            value: &mut AssignmentValue,
+        ) {
+            if column == "tenant" {
+                *value = AssignmentValue::String(String::from("initech"));
+            }
+        }
+    }
+
+    /// Drops the `ssn` column and the rows of the `globex` tenant.
+    struct DropSsn;
+
+    impl StatementRewriter for DropSsn {
+        fn retain_column(&mut self, _ctx: &StatementContext, _table: &str, column: &str) -> bool {
+            column != "ssn"
+        }
+
+        fn rewrite_row(
+            &mut self,
+            _ctx: &StatementContext,
+            _table: &str,
+            _columns: &[String],
+            row: &mut InsertValues,
+        ) -> bool {
+            !row.0.iter().any(|value| value.to_string() == "'globex'")
+        }
+    }
+
+    #[derive(Default)]
+    struct CountValues(usize);
+
+    impl StatementVisitor for CountValues {
+        fn visit_value(
+            &mut self,
+            ctx: &StatementContext,
+            _table: &str,
+            column: Option<&str>,
+            _value: &InsertValue,
+        ) {
+            assert_eq!(ctx.database.as_deref(), Some("app"));
+            assert!(column.is_some());
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_writes_unchanged_statements_as_read() {
+        let dump = std::fs::read_to_string("./tests/mysqldump.sql").unwrap();
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(RenameTenant)
+            .run(dump.as_bytes(), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let statements = |sql: &str| {
+            DumpReader::new(sql.as_bytes())
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    (entry.sql, entry.terminator)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statements(&out), statements(&dump));
+        assert!(out.contains("  `created_at` timestamp NULL DEFAULT CURRENT_TIMESTAMP,\n"));
+        assert!(out.contains(
+            "INSERT INTO `orders` VALUES (1,1,'leak@real.com',19.99),(2,2,NULL,5.00);\n"
+        ));
+        assert!(out.contains("DELIMITER ;;\n/*!50003 CREATE*/"));
+    }
+
+    #[test]
+    fn test_runs_rewriters_in_order() {
+        let mut count = CountValues::default();
+        let mut out = Vec::new();
+        Pipeline::new()
+            .rewriter(RenameTenant)
+            .rewriter(DropSsn)
+            .visitor(&mut count)
+            .run(DUMP.as_bytes(), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            out,
+            "USE `app`;
+CREATE TABLE `users` (
+  `id` INT NOT NULL,
+  `tenant` VARCHAR (20) NOT NULL,
+  PRIMARY KEY (`id`)
+);
+INSERT INTO `users` (`id`, `tenant`) VALUES (1, 'initech');
+UPDATE `users` SET `tenant` = 'initech' WHERE `id` = 2;
+"
+        );
+        assert_eq!(count.0, 2);
+    }
+}
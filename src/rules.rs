@@ -1,5 +1,35 @@
+use std::sync::{Mutex, PoisonError};
+
+use rand::{Rng, RngCore};
+
+use crate::locale::Locale;
+
 pub trait Faking {
-    fn fake(&self) -> String;
+    /// A fake drawn from `rng`.
+    fn fake_with(&self, rng: &mut dyn RngCore) -> String;
+
+    fn fake(&self) -> String {
+        self.fake_with(&mut rand::thread_rng())
+    }
+
+    /// A fake that looks right for `locale`, drawn from `rng`; the plain fake
+    /// for fakers without a generator for it.
+    fn fake_in_with(&self, _locale: Locale, rng: &mut dyn RngCore) -> String {
+        self.fake_with(rng)
+    }
+
+    fn fake_in(&self, locale: Locale) -> String {
+        self.fake_in_with(locale, &mut rand::thread_rng())
+    }
+}
+
+/// Call a fakeit function with fakeit's global generator seeded from `rng`,
+/// so that a seeded `rng` gives the same fakes on every run.
+pub fn fakeit_with<T>(rng: &mut dyn RngCore, f: impl FnOnce() -> T) -> T {
+    static FAKEIT: Mutex<()> = Mutex::new(());
+    let _guard = FAKEIT.lock().unwrap_or_else(PoisonError::into_inner);
+    simplerand::set_seed::<u64>(rng.gen::<u64>().into());
+    f()
 }
 pub trait FromStr: std::fmt::Debug {
     fn from_str(s: &str) -> Option<Self>
@@ -12,8 +42,8 @@ pub trait FromStrFaking: FromStr + Faking {}
 #[derive(Debug)]
 pub struct UnknownFaker(pub String);
 impl Faking for UnknownFaker {
-    fn fake(&self) -> String {
-        fakeit::name::first().to_string()
+    fn fake_with(&self, rng: &mut dyn RngCore) -> String {
+        fakeit_with(rng, fakeit::name::first)
     }
 }
 
@@ -25,11 +55,38 @@ impl FromStr for UnknownFaker {
 
 impl<T: FromStr + Faking> FromStrFaking for T {}
 
+/// A faker of the registry, called as `domain::name()` in masking rules.
+pub struct FakerEntry {
+    pub domain: &'static str,
+    pub name: &'static str,
+    create: fn(&str) -> Box<dyn FromStrFaking>,
+}
+
+impl FakerEntry {
+    /// The name the faker is looked up by: lowercase, without underscores.
+    pub fn key(&self) -> String {
+        self.name.replace('_', "")
+    }
+
+    pub fn create(&self) -> Box<dyn FromStrFaking> {
+        (self.create)(&self.key())
+    }
+}
+
+/// Calls the generator given for a faker, or the fakeit function of the same name.
 #[macro_export]
-macro_rules! faking {
-    ($($module:ident, $field_name:ident);*;) => {
-        use std::collections::HashMap;
+macro_rules! fake_with {
+    ($rng:ident, $module:ident, $field_name:ident) => {
+        $crate::rules::fakeit_with($rng, fakeit::$module::$field_name).to_string()
+    };
+    ($rng:ident, $module:ident, $field_name:ident, $generator:path) => {
+        $generator($rng).to_string()
+    };
+}
 
+#[macro_export]
+macro_rules! faking {
+    ($($module:ident, $field_name:ident $(=> $generator:path)?);*;) => {
         $(
             paste::paste! {
                 #[derive(Debug)]
@@ -38,8 +95,17 @@ macro_rules! faking {
 
             paste::paste! {
                 impl Faking for [<$field_name:camel>] {
-                    fn fake(&self) -> String {
-                        fakeit::$module::$field_name().to_string()
+                    fn fake_with(&self, rng: &mut dyn rand::RngCore) -> String {
+                        $crate::fake_with!(rng, $module, $field_name $(, $generator)?)
+                    }
+
+                    fn fake_in_with(
+                        &self,
+                        locale: $crate::locale::Locale,
+                        rng: &mut dyn rand::RngCore,
+                    ) -> String {
+                        let name = stringify!([<$field_name:camel>]).to_lowercase();
+                        locale.fake(&name, rng).unwrap_or_else(|| self.fake_with(rng))
                     }
                 }
             }
@@ -61,25 +127,46 @@ macro_rules! faking {
             }
         )*
 
-        pub fn get_struct_by_name(name: &str) -> Box<dyn FromStrFaking> {
-            let mut map: HashMap<&str, fn(&str) -> Box<dyn FromStrFaking>> = HashMap::new();
-            $(
+        /// Every registered faker, sorted by domain and name.
+        pub fn fakers() -> Vec<FakerEntry> {
+            let mut fakers = vec![$(
                 paste::paste! {
-                    let key = stringify!([<$field_name:camel>]).to_lowercase();
-                    map.insert(key.as_str(), |s: &str| {
-                        Box::new([<$field_name:camel>]::from(s)) as Box<dyn FromStrFaking>
-                    });
+                    FakerEntry {
+                        domain: stringify!($module),
+                        name: stringify!($field_name),
+                        create: |s: &str| {
+                            Box::new([<$field_name:camel>]::from(s)) as Box<dyn FromStrFaking>
+                        },
+                    }
                 }
-            )*
-            match map.get(name) {
-                Some(f) => f(name),
-                None => Box::new(UnknownFaker(name.to_string())),
-            }
+            ),*];
+            fakers.sort_by_key(|faker| (faker.domain, faker.name));
+            fakers
+        }
+
+        /// Look up the faker registered as `name`, if there is one.
+        pub fn find_struct_by_name(name: &str) -> Option<Box<dyn FromStrFaking>> {
+            fakers()
+                .into_iter()
+                .find(|faker| faker.key() == name)
+                .map(|faker| (faker.create)(name))
+        }
+
+        pub fn get_struct_by_name(name: &str) -> Box<dyn FromStrFaking> {
+            find_struct_by_name(name).unwrap_or_else(|| Box::new(UnknownFaker(name.to_string())))
         }
     };
 }
 
 faking! {
+    address, street;
+    address, city;
+    address, zip;
+    address, state;
+    address, country;
+    address, latitude => generators::latitude;
+    address, longitude => generators::longitude;
+
     contact, email;
     contact, phone;
     company, company;
@@ -96,7 +183,10 @@ faking! {
     internet, http_method;
     internet, ipv4_address;
     internet, ipv6_address;
+    internet, mac_address;
     internet, username;
+    internet, url => generators::url;
+    internet, user_agent => generators::user_agent;
 
     job, title;
     job, level;
@@ -107,14 +197,141 @@ faking! {
     name, suffix;
     name, full;
 
+    password, bcrypt => generators::bcrypt;
+
+    payment, credit_card => generators::credit_card;
+    payment, iban => generators::iban;
+
     person, ssn;
     person, gender;
+    person, birth_date => generators::birth_date;
+    person, national_id => generators::national_id;
 
-    unique, uuid_v4;
+    unique, uuid_v4 => generators::uuid_v4;
 
     words, word;
 }
 
+/// Generators for fakers that fakeit has no function for.
+mod generators {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use rand::seq::SliceRandom;
+    use rand::{Rng, RngCore};
+
+    use super::fakeit_with;
+    use crate::masker::civil_from_days;
+
+    const BCRYPT_ALPHABET: &[u8] =
+        b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    pub fn latitude(rng: &mut dyn RngCore) -> String {
+        format!("{:.6}", rng.gen_range(-90.0..=90.0))
+    }
+
+    pub fn longitude(rng: &mut dyn RngCore) -> String {
+        format!("{:.6}", rng.gen_range(-180.0..=180.0))
+    }
+
+    pub fn url(rng: &mut dyn RngCore) -> String {
+        format!(
+            "https://{}/{}",
+            fakeit_with(rng, fakeit::internet::domain_name).replace('/', ""),
+            fakeit_with(rng, fakeit::words::word).to_lowercase()
+        )
+    }
+
+    pub fn user_agent(rng: &mut dyn RngCore) -> String {
+        let agents: [fn() -> String; 4] = [
+            fakeit::user_agent::chrome,
+            fakeit::user_agent::firefox,
+            fakeit::user_agent::safari,
+            fakeit::user_agent::opera,
+        ];
+        let agent = *agents.choose(rng).unwrap();
+        fakeit_with(rng, agent)
+    }
+
+    pub fn uuid_v4(rng: &mut dyn RngCore) -> String {
+        uuid::Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .to_string()
+    }
+
+    /// A hash in the bcrypt format, `$2b$` with a cost of 12 and 53
+    /// characters of salt and digest, that matches no password.
+    pub fn bcrypt(rng: &mut dyn RngCore) -> String {
+        let digest = (0..53)
+            .map(|_| char::from(*BCRYPT_ALPHABET.choose(rng).unwrap()))
+            .collect::<String>();
+        format!("$2b$12${digest}")
+    }
+
+    /// A Visa, Mastercard or American Express number with a valid Luhn check digit.
+    pub fn credit_card(rng: &mut dyn RngCore) -> String {
+        let (prefix, len) = *[("4", 16), ("51", 16), ("55", 16), ("37", 15)]
+            .choose(rng)
+            .unwrap();
+        let mut digits = prefix.bytes().map(|b| b - b'0').collect::<Vec<_>>();
+        while digits.len() < len - 1 {
+            digits.push(rng.gen_range(0..10));
+        }
+        digits.push(luhn_check_digit(&digits));
+        digits.into_iter().map(|d| char::from(b'0' + d)).collect()
+    }
+
+    pub(super) fn luhn_check_digit(digits: &[u8]) -> u8 {
+        let sum = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &d)| match i % 2 {
+                0 if d * 2 > 9 => d * 2 - 9,
+                0 => d * 2,
+                _ => d,
+            } as u32)
+            .sum::<u32>();
+        ((10 - sum % 10) % 10) as u8
+    }
+
+    /// A German IBAN with valid check digits.
+    pub fn iban(rng: &mut dyn RngCore) -> String {
+        let bban = (0..18)
+            .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+            .collect::<String>();
+        // The check digits make `BBAN + "DE00"`, with letters as numbers, 1 modulo 97.
+        let remainder = format!("{bban}131400")
+            .bytes()
+            .fold(0u32, |acc, b| (acc * 10 + u32::from(b - b'0')) % 97);
+        format!("DE{:02}{bban}", 98 - remainder)
+    }
+
+    /// A date of birth of someone 18 to 90 years old.
+    pub fn birth_date(rng: &mut dyn RngCore) -> String {
+        let today = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+        let age = rng.gen_range(18 * 365 + 5..90 * 365 + 22);
+        let (year, month, day) = civil_from_days(today - age);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    /// A US social security number, outside the ranges that are never issued.
+    pub fn national_id(rng: &mut dyn RngCore) -> String {
+        let area = loop {
+            let area = rng.gen_range(1..900);
+            if area != 666 {
+                break area;
+            }
+        };
+        format!(
+            "{area:03}-{:02}-{:04}",
+            rng.gen_range(1..100),
+            rng.gen_range(1..10_000)
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,4 +345,36 @@ mod tests {
             format!("{:?}", First::from_str("first").unwrap())
         );
     }
+
+    #[test]
+    fn test_find_struct_by_name() {
+        assert!(find_struct_by_name("email").is_some());
+        assert!(find_struct_by_name("creditcard").is_some());
+        assert!(find_struct_by_name("not_a_faker").is_none());
+    }
+
+    #[test]
+    fn test_generates_valid_identifiers() {
+        for _ in 0..20 {
+            let card = CreditCard::from("creditcard").fake();
+            let digits = card.bytes().map(|b| b - b'0').collect::<Vec<_>>();
+            let (check, payload) = digits.split_last().unwrap();
+            assert_eq!(generators::luhn_check_digit(payload), *check, "{card}");
+
+            let iban = Iban::from("iban").fake();
+            let rearranged = format!("{}1314{}", &iban[4..], &iban[2..4]);
+            let remainder = rearranged
+                .bytes()
+                .fold(0u32, |acc, b| (acc * 10 + u32::from(b - b'0')) % 97);
+            assert_eq!((iban.len(), remainder), (22, 1), "{iban}");
+        }
+
+        let bcrypt = Bcrypt::from("bcrypt").fake();
+        assert!(bcrypt.starts_with("$2b$12$") && bcrypt.len() == 60);
+        let birth_date = BirthDate::from("birthdate").fake();
+        assert!(("1930".."2010").contains(&&birth_date[..4]), "{birth_date}");
+        assert!(fakers()
+            .iter()
+            .any(|faker| faker.domain == "payment" && faker.name == "credit_card"));
+    }
 }
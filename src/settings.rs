@@ -5,7 +5,11 @@ use config::{Config, ConfigError, Environment, File};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::rules::{self, get_struct_by_name};
+use crate::locale::Locale;
+use crate::masker::{
+    Condition, DateShift, Expression, Fpe, FpeAlgorithm, InlineRedactor, JsonPath, Row, Vault,
+};
+use crate::rules;
 
 // lazy_static! {
 //     static ref FN_NAMES_TO_MODULE: HashMap<String, Box<dyn Fn(String) -> String>> = {
@@ -19,33 +23,237 @@ pub struct MaskingRegex {
     pub regex: String,
 }
 
+/// A masking rule, either a faker call such as `contact::email()` or an
+/// [`Expression`] over the row's columns, optionally limited to the rows
+/// matching a `when` condition.
 #[derive(Debug)]
-pub struct MaskingRule(Box<dyn rules::FromStrFaking>);
+pub struct MaskingRule {
+    expression: Expression,
+    when: Option<Condition>,
+}
 
 impl MaskingRule {
-    pub fn inner(&self) -> &dyn rules::FromStrFaking {
-        &*self.0
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// The faker, when the rule is a plain faker call.
+    pub fn faker(&self) -> Option<&dyn rules::FromStrFaking> {
+        self.expression.faker()
+    }
+
+    pub fn when(&self) -> Option<&Condition> {
+        self.when.as_ref()
+    }
+
+    /// Whether the rule masks `row`, which it does unless its condition is false.
+    pub fn applies(&self, row: &mut dyn Row) -> bool {
+        self.when.as_ref().is_none_or(|when| when.matches(row))
+    }
+
+    pub fn parse(rule: &str, when: Option<&str>) -> anyhow::Result<Self> {
+        Ok(Self {
+            expression: Expression::parse(rule)?,
+            when: when.map(Condition::parse).transpose()?,
+        })
     }
 }
 
-impl From<&str> for MaskingRule {
-    fn from(value: &str) -> Self {
-        let mut parts = value.split("::");
-        let _domain = parts.next().unwrap();
-        let fn_name = parts.next().unwrap();
-        let fn_ = get_struct_by_name(fn_name);
-        MaskingRule(fn_)
+impl TryFrom<&str> for MaskingRule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::parse(value, None)
     }
 }
 
+/// A rule as written in the config: the expression alone, or the expression
+/// and its `when` condition.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaskingRuleSource {
+    Plain(String),
+    Conditional { rule: String, when: Option<String> },
+}
+
+impl TryFrom<MaskingRuleSource> for MaskingRule {
+    type Error = anyhow::Error;
+
+    fn try_from(source: MaskingRuleSource) -> Result<Self, Self::Error> {
+        match source {
+            MaskingRuleSource::Plain(rule) => Self::parse(&rule, None),
+            MaskingRuleSource::Conditional { rule, when } => Self::parse(&rule, when.as_deref()),
+        }
+    }
+}
+
+fn deserialize_rules<'de, D>(deserializer: D) -> Result<HashMap<String, MaskingRule>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let rules = HashMap::<String, MaskingRuleSource>::deserialize(deserializer)?;
+    rules
+        .into_iter()
+        .map(|(column, rule)| {
+            MaskingRule::try_from(rule)
+                .map(|rule| (column.to_lowercase(), rule))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+/// A rule masking the parts of a JSON column selected by `path`.
+#[derive(Debug)]
+pub struct JsonRule {
+    pub path: JsonPath,
+    pub rule: MaskingRule,
+}
+
+/// Paths are given as values rather than keys, which the config loader lowercases.
+#[derive(Deserialize)]
+struct JsonRuleSource {
+    path: String,
+    rule: String,
+    when: Option<String>,
+}
+
+fn deserialize_json_rules<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, Vec<JsonRule>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let columns = HashMap::<String, Vec<JsonRuleSource>>::deserialize(deserializer)?;
+    columns
+        .into_iter()
+        .map(|(column, rules)| {
+            let rules = rules
+                .into_iter()
+                .map(|source| {
+                    Ok(JsonRule {
+                        path: JsonPath::parse(&source.path)?,
+                        rule: MaskingRule::parse(&source.rule, source.when.as_deref())?,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+                .map_err(serde::de::Error::custom)?;
+            Ok((column.to_lowercase(), rules))
+        })
+        .collect()
+}
+
+/// Settings of the `fpe()` rules. The secret the key is derived from is read
+/// from an environment variable so it stays out of the config file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FpeConfig {
+    #[serde(default)]
+    pub algorithm: FpeAlgorithm,
+    #[serde(default = "default_secret_env")]
+    pub secret_env: String,
+}
+
+fn default_secret_env() -> String {
+    String::from("SQLEX_FPE_SECRET")
+}
+
+/// Where the tokenization vault is kept. Like the `fpe` secret, its secret is
+/// read from an environment variable.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultConfig {
+    pub path: String,
+    #[serde(default = "default_vault_secret_env")]
+    pub secret_env: String,
+}
+
+fn default_vault_secret_env() -> String {
+    String::from("SQLEX_VAULT_SECRET")
+}
+
+/// Shifts every `DATE`, `DATETIME` and `TIMESTAMP` column of rows with one
+/// of the `keys` columns (`column` or `table.column`) by a per-entity offset.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DateShiftConfig {
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default = "default_max_days")]
+    pub max_days: u32,
+    #[serde(default = "default_date_shift_secret_env")]
+    pub secret_env: String,
+}
+
+fn default_max_days() -> u32 {
+    365
+}
+
+fn default_date_shift_secret_env() -> String {
+    String::from("SQLEX_DATE_SHIFT_SECRET")
+}
+
+/// What replaces PII found inside free text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineReplacement {
+    /// The detector's name in brackets, such as `[email]`.
+    #[default]
+    Label,
+    /// A value from the faker named like the detector, or the label without one.
+    Fake,
+}
+
+/// The locale of fakers whose rule does not name one.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LocaleConfig {
+    /// A column holding each row's country or locale, such as `DE` or `pt_BR`.
+    #[serde(default)]
+    pub column: Option<String>,
+    /// The locale of rows without a known country, `en_US` when unset.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// Settings of inline redaction, which replaces only the PII found inside
+/// the values of `columns`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlineConfig {
+    pub columns: Vec<String>,
+    /// Built-in detectors to use besides `patterns`, all of them when unset.
+    #[serde(default)]
+    pub detectors: Option<Vec<String>>,
+    #[serde(default)]
+    pub replacement: InlineReplacement,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MaskingConfig {
     pub columns: Vec<String>,
     pub patterns: Vec<MaskingRegex>,
     #[serde(skip)]
     pub regexes: Vec<Regex>,
-    #[serde(skip)]
+    /// Masking rule for a column, keyed by lowercased column name.
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_rules")]
     pub rules: HashMap<String, MaskingRule>,
+    /// Rules for paths inside JSON columns, keyed by lowercased column name.
+    #[serde(default, skip_serializing, deserialize_with = "deserialize_json_rules")]
+    pub json: HashMap<String, Vec<JsonRule>>,
+    #[serde(default)]
+    pub fpe: Option<FpeConfig>,
+    #[serde(default)]
+    pub vault: Option<VaultConfig>,
+    #[serde(default)]
+    pub date_shift: Option<DateShiftConfig>,
+    #[serde(default)]
+    pub inline: Option<InlineConfig>,
+    #[serde(default)]
+    pub locale: Option<LocaleConfig>,
+    /// Built from the `fpe` settings when the config is loaded.
+    #[serde(skip)]
+    pub cipher: Option<Fpe>,
+    /// Built from the `date_shift` settings when the config is loaded.
+    #[serde(skip)]
+    pub date_shifter: Option<DateShift>,
+    /// Built from the `inline` settings and `patterns` when the config is loaded.
+    #[serde(skip)]
+    pub redactor: Option<InlineRedactor>,
 }
 
 impl MaskingConfig {
@@ -59,13 +267,72 @@ impl MaskingConfig {
         }
 
         for regex in &self.build_regexes() {
-            if regex.is_match(&column) {
+            if regex.is_match(column) {
                 return true;
             }
         }
         false
     }
 
+    /// Whether a rule, `json` rule, inline redaction or the `columns` and
+    /// `patterns` mask `column`. Columns linked by foreign keys are masked
+    /// when [`Self::masks_linked_column`] holds for any column of the chain.
+    pub fn masks_column(&self, column: &str) -> bool {
+        self.masks_linked_column(column)
+            || !self.json_rules_for(column).is_empty()
+            || self.inline_column(column)
+    }
+
+    /// Whether `column` masks the chain of foreign keys it is part of.
+    pub fn masks_linked_column(&self, column: &str) -> bool {
+        self.rule_for(column).is_some() || self.filter_column(column)
+    }
+
+    /// Open the vault of the `vault` section, if there is one.
+    pub fn open_vault(&self) -> crate::ExtractResult<Option<Vault>> {
+        let Some(vault) = &self.vault else {
+            return Ok(None);
+        };
+        let secret = std::env::var(&vault.secret_env).map_err(|_| {
+            anyhow::anyhow!(
+                "the vault secret is read from ${}, which is not set",
+                vault.secret_env
+            )
+        })?;
+        Vault::open(&vault.path, &secret, Vault::KDF_ROUNDS).map(Some)
+    }
+
+    /// The rule configured for `column` in the `rules` section, if any.
+    pub fn rule_for(&self, column: &str) -> Option<&MaskingRule> {
+        self.rules.get(&column.to_lowercase())
+    }
+
+    /// The locale of the `locale` section's `default`, `en_US` without one.
+    pub fn default_locale(&self) -> Locale {
+        self.locale
+            .as_ref()
+            .and_then(|locale| locale.default.as_deref())
+            .and_then(Locale::from_name)
+            .unwrap_or_default()
+    }
+
+    /// Whether PII inside the values of `column` is redacted inline.
+    pub fn inline_column(&self, column: &str) -> bool {
+        self.inline.as_ref().is_some_and(|inline| {
+            inline
+                .columns
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(column))
+        })
+    }
+
+    /// The rules of the `json` section for `column`, if any.
+    pub fn json_rules_for(&self, column: &str) -> &[JsonRule] {
+        self.json
+            .get(&column.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
     fn build_regexes(&self) -> Vec<Regex> {
         self.patterns
             .iter()
@@ -85,7 +352,74 @@ pub fn parse_masking_config(path: &str) -> std::result::Result<MaskingConfig, Co
         // You may also programmatically change settings
         .build()?;
 
-    s.try_deserialize()
+    let mut config: MaskingConfig = s.try_deserialize()?;
+    config.cipher = match &config.fpe {
+        Some(fpe) => {
+            let secret = std::env::var(&fpe.secret_env).map_err(|_| {
+                ConfigError::Message(format!(
+                    "the `fpe` secret is read from ${}, which is not set",
+                    fpe.secret_env
+                ))
+            })?;
+            Some(Fpe::new(fpe.algorithm, &secret, Fpe::KDF_ROUNDS))
+        }
+        None => None,
+    };
+    config.date_shifter = match &config.date_shift {
+        Some(date_shift) => {
+            let secret = std::env::var(&date_shift.secret_env).map_err(|_| {
+                ConfigError::Message(format!(
+                    "the `date_shift` secret is read from ${}, which is not set",
+                    date_shift.secret_env
+                ))
+            })?;
+            Some(DateShift::new(date_shift.max_days, &secret))
+        }
+        None => None,
+    };
+    if let Some(default) = config.locale.as_ref().and_then(|l| l.default.as_deref()) {
+        if Locale::from_name(default).is_none() {
+            return Err(ConfigError::Message(format!(
+                "unknown locale `{default}` in the `locale` section"
+            )));
+        }
+    }
+    config.redactor = match &config.inline {
+        Some(inline) => Some(
+            InlineRedactor::new(
+                &config.patterns,
+                inline.detectors.as_deref(),
+                inline.replacement,
+            )
+            .map_err(|err| ConfigError::Message(format!("invalid `inline` section: {err}")))?,
+        ),
+        None => None,
+    };
+    let json_rules = config.json.iter().flat_map(|(column, rules)| {
+        rules
+            .iter()
+            .map(move |json| (format!("{column} {}", json.path), &json.rule))
+    });
+    let rules = config
+        .rules
+        .iter()
+        .map(|(column, rule)| (column.clone(), rule))
+        .chain(json_rules)
+        .collect::<Vec<_>>();
+    for (column, rule) in rules {
+        if config.cipher.is_none() && rule.expression().uses("fpe") {
+            return Err(ConfigError::Message(format!(
+                "the rule for `{column}` uses fpe() but the config has no `fpe` section"
+            )));
+        }
+        if config.date_shifter.is_none() && rule.expression().uses("shift_date") {
+            return Err(ConfigError::Message(format!(
+                "the rule for `{column}` uses shift_date() but the config has no `date_shift` section"
+            )));
+        }
+    }
+
+    Ok(config)
 }
 
 #[cfg(test)]
@@ -107,9 +441,45 @@ mod tests {
     fn test_filtering_columns() {
         let config = parse_masking_config("./tests/more.yaml");
         let cfg = config.unwrap();
-        assert_eq!(cfg.filter_column("email"), false);
-        assert_eq!(cfg.filter_column("account"), true);
-        assert_eq!(cfg.filter_column("password"), true);
-        assert_eq!(cfg.filter_column("age"), false);
+        assert!(!cfg.filter_column("email"));
+        assert!(cfg.filter_column("account"));
+        assert!(cfg.filter_column("password"));
+        assert!(!cfg.filter_column("age"));
+    }
+
+    #[test]
+    fn test_loads_rules() {
+        let config = parse_masking_config("./tests/more.yaml").unwrap();
+        let rule = config.rule_for("Email").expect("email rule");
+        assert_eq!(format!("{:?}", rule.faker().unwrap()), "Email(\"email\")");
+        assert!(rule.when().is_none());
+        assert!(config.rule_for("age").is_none());
+
+        let paths = config
+            .json_rules_for("Preferences")
+            .iter()
+            .map(|json| json.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["$.contact.eMail", "$.addresses[*].street"]);
+    }
+
+    #[test]
+    fn test_loads_conditional_rules() {
+        let config = parse_masking_config("./tests/conditional.yaml").unwrap();
+        let rule = config.rule_for("email").expect("email rule");
+        assert_eq!(format!("{:?}", rule.faker().unwrap()), "Email(\"email\")");
+        assert_eq!(
+            rule.when().unwrap().to_string(),
+            "email NOT LIKE '%@ourcompany.com'"
+        );
+        assert_eq!(
+            config
+                .rule_for("street")
+                .unwrap()
+                .when()
+                .unwrap()
+                .to_string(),
+            "country = 'DE'"
+        );
     }
 }
@@ -1,5 +1,5 @@
 use regex::Regex;
-use std::{collections::HashMap, path::Path}; // 1.1.8
+use std::{collections::HashMap, io::BufRead, path::Path}; // 1.1.8
 
 use sql_parse::{
     parse_statements, CreateDefinition, CreateTable, ParseOptions, QualifiedName, SQLDialect,
@@ -7,11 +7,17 @@ use sql_parse::{
 };
 
 use crate::{
+    compression::open_input,
     types::{Column, ColumnType, Database, Table},
     ExtractResult,
 };
 
 pub fn simple_parse(code_path: &Path) -> ExtractResult<Vec<Database>> {
+    simple_parse_reader(open_input(code_path)?)
+}
+
+/// Like [`simple_parse`], reading the dump from any buffered reader.
+pub fn simple_parse_reader<R: BufRead>(sql_dump: R) -> ExtractResult<Vec<Database>> {
     let options = ParseOptions::new()
         .dialect(SQLDialect::MariaDB)
         .arguments(sql_parse::SQLArguments::QuestionMark)
@@ -19,8 +25,6 @@ pub fn simple_parse(code_path: &Path) -> ExtractResult<Vec<Database>> {
 
     let mut issues = Vec::new();
 
-    let sql_dump = std::fs::read_to_string(code_path).expect("unable to read sql dump");
-
     // Regex to capture the `USE` statement and the database name
     let db_regex = Regex::new(r"USE `([^`]+)`;").unwrap();
 
@@ -30,18 +34,16 @@ pub fn simple_parse(code_path: &Path) -> ExtractResult<Vec<Database>> {
     let mut current_db_sql = String::new();
 
     for line in sql_dump.lines() {
-        if let Some(captures) = db_regex.captures(line) {
+        let line = line?;
+        if let Some(captures) = db_regex.captures(&line) {
             // Process the previous database if any
             if !current_db_name.is_empty() {
                 let ast = parse_statements(&current_db_sql, &mut issues, &options);
                 let mut tables = Vec::new();
                 for node in ast.iter() {
-                    match node {
-                        Statement::CreateTable(create_table) => {
-                            let tbl = parse_create_table(create_table);
-                            tables.push(tbl.clone());
-                        }
-                        _ => {}
+                    if let Statement::CreateTable(create_table) = node {
+                        let tbl = parse_create_table(create_table);
+                        tables.push(tbl.clone());
                     }
                 }
                 databases.push(Database {
@@ -56,7 +58,7 @@ pub fn simple_parse(code_path: &Path) -> ExtractResult<Vec<Database>> {
             current_db_name = captures.get(1).unwrap().as_str().to_string();
         } else {
             // Append the line to the current database's SQL statements
-            current_db_sql.push_str(line);
+            current_db_sql.push_str(&line);
             current_db_sql.push('\n');
         }
     }
@@ -66,12 +68,9 @@ pub fn simple_parse(code_path: &Path) -> ExtractResult<Vec<Database>> {
         let ast = parse_statements(&current_db_sql, &mut issues, &options);
         let mut tables = Vec::new();
         for node in ast.iter() {
-            match node {
-                Statement::CreateTable(create_table) => {
-                    let tbl = parse_create_table(create_table);
-                    tables.push(tbl.clone());
-                }
-                _ => {}
+            if let Statement::CreateTable(create_table) = node {
+                let tbl = parse_create_table(create_table);
+                tables.push(tbl.clone());
             }
         }
         databases.push(Database {
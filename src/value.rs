@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::parser::parse_utils::{escape_str, unescape_str};
+use crate::parser::types::{DataType, InsertValue};
+
+/// A typed cell value, converted from an [`InsertValue`] using the column's [`DataType`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    /// Exact decimals are kept as text so no precision is lost.
+    Decimal(String),
+    Text(String),
+    Set(Vec<String>),
+    Json(serde_json::Value),
+}
+
+impl Value {
+    pub fn from_insert(value: &InsertValue, data_type: Option<&DataType>) -> Self {
+        let raw = match value {
+            InsertValue::Null | InsertValue::Default => return Self::Null,
+            InsertValue::Identifier { value } => match value.to_ascii_uppercase().as_str() {
+                "TRUE" => return Self::Bool(true),
+                "FALSE" => return Self::Bool(false),
+                _ => return Self::Text(value.clone()),
+            },
+            InsertValue::Number { value } => value.clone(),
+            InsertValue::Text { value } | InsertValue::Introduced { value, .. } => {
+                unescape_str(value)
+            }
+        };
+
+        match data_type {
+            Some(
+                DataType::TinyInt { unsigned, .. }
+                | DataType::SmallInt { unsigned, .. }
+                | DataType::MediumInt { unsigned, .. }
+                | DataType::Int { unsigned, .. }
+                | DataType::BigInt { unsigned, .. },
+            ) => Self::parse_int(raw, *unsigned),
+            Some(DataType::Bit { .. } | DataType::Year { .. }) => Self::parse_int(raw, false),
+            Some(DataType::Decimal { .. }) => Self::Decimal(raw),
+            Some(DataType::Float { .. } | DataType::Double { .. }) => Self::parse_float(raw),
+            Some(DataType::Json) => match serde_json::from_str(&raw) {
+                Ok(json) => Self::Json(json),
+                Err(_) => Self::Text(raw),
+            },
+            Some(DataType::Set { .. }) => Self::Set(
+                raw.split(',')
+                    .filter(|member| !member.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            ),
+            Some(_) => Self::Text(raw),
+            None if matches!(value, InsertValue::Number { .. }) => match raw.parse::<i64>() {
+                Ok(int) => Self::Int(int),
+                Err(_) => Self::parse_float(raw),
+            },
+            None => Self::Text(raw),
+        }
+    }
+
+    fn parse_int(raw: String, unsigned: bool) -> Self {
+        if unsigned {
+            if let Ok(uint) = raw.parse::<u64>() {
+                return Self::UInt(uint);
+            }
+        }
+        match raw.parse::<i64>() {
+            Ok(int) => Self::Int(int),
+            Err(_) => Self::Text(raw),
+        }
+    }
+
+    fn parse_float(raw: String) -> Self {
+        match raw.parse::<f64>() {
+            Ok(float) => Self::Float(float),
+            Err(_) => Self::Text(raw),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Plain text rendering of the value, or `None` for `NULL`.
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            Self::Null => None,
+            Self::Bool(value) => Some(value.to_string()),
+            Self::Int(value) => Some(value.to_string()),
+            Self::UInt(value) => Some(value.to_string()),
+            Self::Float(value) => Some(value.to_string()),
+            Self::Decimal(value) | Self::Text(value) => Some(value.clone()),
+            Self::Set(values) => Some(values.join(",")),
+            Self::Json(value) => Some(value.to_string()),
+        }
+    }
+
+    /// The value as it is written in an `INSERT`.
+    pub fn to_insert(&self) -> InsertValue {
+        match self {
+            Self::Null => InsertValue::Null,
+            Self::Bool(value) => InsertValue::Number {
+                value: u8::from(*value).to_string(),
+            },
+            Self::Int(_) | Self::UInt(_) | Self::Float(_) | Self::Decimal(_) => {
+                InsertValue::Number {
+                    value: self.to_text().unwrap_or_default(),
+                }
+            }
+            other => InsertValue::Text {
+                value: escape_str(&other.to_text().unwrap_or_default()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> InsertValue {
+        InsertValue::Text {
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_converts_by_data_type() {
+        let unsigned_int = DataType::Int {
+            m: None,
+            unsigned: true,
+            zerofill: false,
+        };
+        let decimal = DataType::Decimal {
+            m: Some(10),
+            d: Some(2),
+            unsigned: false,
+            zerofill: false,
+        };
+
+        assert_eq!(
+            Value::from_insert(&text("42"), Some(&unsigned_int)),
+            Value::UInt(42)
+        );
+        assert_eq!(
+            Value::from_insert(&text("12.50"), Some(&decimal)),
+            Value::Decimal(String::from("12.50"))
+        );
+        assert_eq!(
+            Value::from_insert(&text(r#"{"a": 1}"#), Some(&DataType::Json)),
+            Value::Json(serde_json::json!({"a": 1}))
+        );
+        assert_eq!(
+            Value::from_insert(&text(r"O\'Brien"), None),
+            Value::Text(String::from("O'Brien"))
+        );
+        assert_eq!(Value::from_insert(&InsertValue::Null, None), Value::Null);
+    }
+
+    #[test]
+    fn test_infers_numbers_without_data_type() {
+        let number = |value: &str| InsertValue::Number {
+            value: value.to_string(),
+        };
+
+        assert_eq!(Value::from_insert(&number("-7"), None), Value::Int(-7));
+        assert_eq!(Value::from_insert(&number("1.5"), None), Value::Float(1.5));
+    }
+}